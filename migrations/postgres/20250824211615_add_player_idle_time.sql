@@ -0,0 +1,4 @@
+-- Add migration script here
+
+ALTER TABLE players
+ADD COLUMN idle_since_ts BIGINT NOT NULL DEFAULT (EXTRACT(EPOCH FROM NOW())::BIGINT);
@@ -0,0 +1,8 @@
+-- Add migration script here
+
+CREATE TABLE jackpot (
+    id INTEGER NOT NULL PRIMARY KEY CHECK (id = 1),
+    pool BIGINT NOT NULL
+);
+
+INSERT INTO jackpot (id, pool) VALUES (1, 0);
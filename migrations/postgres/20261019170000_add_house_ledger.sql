@@ -0,0 +1,16 @@
+-- Add migration script here
+
+CREATE TABLE house (
+    id INTEGER NOT NULL PRIMARY KEY CHECK (id = 1),
+    balance BIGINT NOT NULL
+);
+
+-- Starting bankroll, wins are paid from it.
+INSERT INTO house (id, balance) VALUES (1, 1000000);
+
+CREATE TABLE house_ledger (
+    day BIGINT NOT NULL,
+    source VARCHAR(32) NOT NULL,
+    amount BIGINT NOT NULL,
+    PRIMARY KEY (day, source)
+);
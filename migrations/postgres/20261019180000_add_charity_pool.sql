@@ -0,0 +1,8 @@
+-- Add migration script here
+
+CREATE TABLE charity_pool (
+    id INTEGER NOT NULL PRIMARY KEY CHECK (id = 1),
+    balance BIGINT NOT NULL
+);
+
+INSERT INTO charity_pool (id, balance) VALUES (1, 0);
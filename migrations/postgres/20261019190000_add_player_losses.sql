@@ -0,0 +1,8 @@
+-- Add migration script here
+
+CREATE TABLE player_losses (
+    discord_user_id VARCHAR(255) NOT NULL,
+    day BIGINT NOT NULL,
+    amount BIGINT NOT NULL,
+    PRIMARY KEY (discord_user_id, day)
+);
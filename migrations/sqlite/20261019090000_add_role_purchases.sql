@@ -0,0 +1,21 @@
+-- Add migration script here
+
+CREATE TABLE purchasable_roles (
+    guild_id VARCHAR(255) NOT NULL,
+    role_id VARCHAR(255) NOT NULL,
+    price BIGINT NOT NULL,
+    duration_secs BIGINT,
+    PRIMARY KEY (guild_id, role_id)
+);
+
+CREATE TABLE role_purchases (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id VARCHAR(255) NOT NULL,
+    role_id VARCHAR(255) NOT NULL,
+    discord_user_id VARCHAR(255) NOT NULL,
+    price BIGINT NOT NULL,
+    purchased_ts BIGINT NOT NULL,
+    expires_ts BIGINT
+);
+
+CREATE INDEX role_purchases_expires_ts ON role_purchases (expires_ts);
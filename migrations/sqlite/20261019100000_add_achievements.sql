@@ -0,0 +1,14 @@
+-- Add migration script here
+
+CREATE TABLE achievement_progress (
+    discord_user_id VARCHAR(255) NOT NULL PRIMARY KEY,
+    flip_win_streak BIGINT NOT NULL DEFAULT 0,
+    total_given BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE TABLE player_achievements (
+    discord_user_id VARCHAR(255) NOT NULL,
+    achievement VARCHAR(255) NOT NULL,
+    unlocked_ts BIGINT NOT NULL,
+    PRIMARY KEY (discord_user_id, achievement)
+);
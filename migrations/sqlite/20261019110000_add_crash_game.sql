@@ -0,0 +1,24 @@
+-- Add migration script here
+
+CREATE TABLE crash_rounds (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    channel_id VARCHAR(255) NOT NULL,
+    message_id VARCHAR(255),
+    crash_point BIGINT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    created_ts BIGINT NOT NULL,
+    started_ms BIGINT
+);
+
+CREATE INDEX crash_rounds_status ON crash_rounds (status);
+
+CREATE TABLE crash_bets (
+    round_id BIGINT NOT NULL REFERENCES crash_rounds (id),
+    discord_user_id VARCHAR(255) NOT NULL,
+    amount BIGINT NOT NULL,
+    cashout BIGINT,
+    PRIMARY KEY (round_id, discord_user_id)
+);
+
+CREATE UNIQUE INDEX crash_rounds_one_open_per_channel ON crash_rounds (channel_id)
+WHERE status IN ('betting', 'running');
@@ -0,0 +1,42 @@
+-- Add migration script here
+
+CREATE TABLE poker_tables (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    channel_id VARCHAR(255) NOT NULL,
+    message_id VARCHAR(255),
+    owner_id VARCHAR(255) NOT NULL,
+    buy_in BIGINT NOT NULL,
+    small_blind BIGINT NOT NULL,
+    big_blind BIGINT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    hand_no BIGINT NOT NULL DEFAULT 0,
+    street VARCHAR(16) NOT NULL DEFAULT 'preflop',
+    dealer_seat BIGINT NOT NULL DEFAULT 0,
+    to_act BIGINT,
+    current_bet BIGINT NOT NULL DEFAULT 0,
+    min_raise BIGINT NOT NULL DEFAULT 0,
+    deck TEXT NOT NULL DEFAULT '',
+    board TEXT NOT NULL DEFAULT '',
+    last_result TEXT,
+    version BIGINT NOT NULL
+);
+
+CREATE UNIQUE INDEX poker_tables_one_open_per_channel ON poker_tables (channel_id)
+WHERE status IN ('waiting', 'playing');
+
+CREATE TABLE poker_seats (
+    table_id BIGINT NOT NULL REFERENCES poker_tables (id),
+    seat BIGINT NOT NULL,
+    discord_user_id VARCHAR(255) NOT NULL,
+    stack BIGINT NOT NULL,
+    hole_cards TEXT NOT NULL DEFAULT '',
+    bet BIGINT NOT NULL DEFAULT 0,
+    committed BIGINT NOT NULL DEFAULT 0,
+    in_hand BOOLEAN NOT NULL DEFAULT FALSE,
+    folded BOOLEAN NOT NULL DEFAULT FALSE,
+    all_in BOOLEAN NOT NULL DEFAULT FALSE,
+    acted BOOLEAN NOT NULL DEFAULT FALSE,
+    leaving BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (table_id, seat),
+    UNIQUE (table_id, discord_user_id)
+);
@@ -0,0 +1,16 @@
+-- Add migration script here
+
+CREATE TABLE craps_tables (
+    discord_user_id VARCHAR(255) NOT NULL PRIMARY KEY,
+    point BIGINT
+);
+
+CREATE TABLE craps_bets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    discord_user_id VARCHAR(255) NOT NULL,
+    kind VARCHAR(16) NOT NULL,
+    amount BIGINT NOT NULL,
+    point BIGINT
+);
+
+CREATE INDEX craps_bets_discord_user_id ON craps_bets (discord_user_id);
@@ -0,0 +1,35 @@
+-- Add migration script here
+
+CREATE TABLE races (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    channel_id VARCHAR(255) NOT NULL,
+    message_id VARCHAR(255),
+    status VARCHAR(16) NOT NULL,
+    created_ts BIGINT NOT NULL,
+    finished_ts BIGINT,
+    winner_lane BIGINT,
+    pool BIGINT NOT NULL DEFAULT 0,
+    house_take BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE UNIQUE INDEX races_one_open_per_channel ON races (channel_id)
+WHERE status IN ('betting', 'running');
+
+CREATE TABLE race_racers (
+    race_id BIGINT NOT NULL REFERENCES races (id),
+    lane BIGINT NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    strength BIGINT NOT NULL,
+    distance BIGINT NOT NULL DEFAULT 0,
+    finish_position BIGINT,
+    PRIMARY KEY (race_id, lane)
+);
+
+CREATE TABLE race_bets (
+    race_id BIGINT NOT NULL REFERENCES races (id),
+    discord_user_id VARCHAR(255) NOT NULL,
+    lane BIGINT NOT NULL,
+    amount BIGINT NOT NULL,
+    payout BIGINT,
+    PRIMARY KEY (race_id, discord_user_id, lane)
+);
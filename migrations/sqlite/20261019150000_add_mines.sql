@@ -0,0 +1,22 @@
+-- Add migration script here
+
+CREATE TABLE mines_games (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    discord_user_id VARCHAR(255) NOT NULL,
+    channel_id VARCHAR(255) NOT NULL,
+    message_id VARCHAR(255),
+    amount BIGINT NOT NULL,
+    mines BIGINT NOT NULL,
+    mine_tiles VARCHAR(255) NOT NULL,
+    revealed_tiles VARCHAR(255) NOT NULL DEFAULT '',
+    status VARCHAR(16) NOT NULL,
+    payout BIGINT,
+    created_ts BIGINT NOT NULL,
+    updated_ts BIGINT NOT NULL,
+    version BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE UNIQUE INDEX mines_games_one_active_per_player ON mines_games (discord_user_id)
+WHERE status = 'active';
+
+CREATE INDEX mines_games_status ON mines_games (status, updated_ts);
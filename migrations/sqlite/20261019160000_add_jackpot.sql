@@ -0,0 +1,16 @@
+-- Add migration script here
+
+CREATE TABLE jackpot (
+    id INTEGER NOT NULL PRIMARY KEY CHECK (id = 1),
+    pool BIGINT NOT NULL
+);
+
+INSERT INTO jackpot (id, pool) VALUES (1, 0);
+
+CREATE TABLE jackpot_wins (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    discord_user_id VARCHAR(255) NOT NULL,
+    amount BIGINT NOT NULL,
+    game VARCHAR(32) NOT NULL,
+    won_ts BIGINT NOT NULL
+);
@@ -0,0 +1,21 @@
+-- Add migration script here
+
+CREATE TABLE transfers (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    sender_id VARCHAR(255) NOT NULL,
+    receiver_id VARCHAR(255) NOT NULL,
+    amount BIGINT NOT NULL,
+    fee BIGINT NOT NULL,
+    created_ts BIGINT NOT NULL
+);
+
+CREATE INDEX transfers_sender ON transfers (sender_id, created_ts);
+CREATE INDEX transfers_receiver ON transfers (receiver_id, created_ts);
+CREATE INDEX transfers_created ON transfers (created_ts);
+
+CREATE TABLE charity_pool (
+    id INTEGER NOT NULL PRIMARY KEY CHECK (id = 1),
+    balance BIGINT NOT NULL
+);
+
+INSERT INTO charity_pool (id, balance) VALUES (1, 0);
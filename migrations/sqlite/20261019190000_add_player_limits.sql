@@ -0,0 +1,20 @@
+-- Add migration script here
+
+CREATE TABLE player_limits (
+    discord_user_id VARCHAR(255) NOT NULL PRIMARY KEY,
+    -- 0 means no limit, pending changes apply at their change ts.
+    daily_loss_limit BIGINT NOT NULL DEFAULT 0,
+    pending_daily_loss_limit BIGINT,
+    daily_loss_limit_change_ts BIGINT,
+    excluded_until_ts BIGINT NOT NULL DEFAULT 0,
+    pending_excluded_until_ts BIGINT,
+    excluded_until_change_ts BIGINT,
+    version BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE TABLE player_losses (
+    discord_user_id VARCHAR(255) NOT NULL,
+    day BIGINT NOT NULL,
+    amount BIGINT NOT NULL,
+    PRIMARY KEY (discord_user_id, day)
+);
@@ -0,0 +1,15 @@
+-- Add migration script here
+
+CREATE TABLE events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR(255) NOT NULL,
+    target VARCHAR(32) NOT NULL,
+    multiplier REAL NOT NULL,
+    starts_ts BIGINT NOT NULL,
+    ends_ts BIGINT NOT NULL,
+    created_by VARCHAR(255) NOT NULL,
+    start_announced BOOLEAN NOT NULL DEFAULT FALSE,
+    end_announced BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX events_time ON events (starts_ts, ends_ts);
@@ -0,0 +1,37 @@
+-- Add migration script here
+
+CREATE TABLE markets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    question VARCHAR(255) NOT NULL,
+    channel_id VARCHAR(255) NOT NULL,
+    message_id VARCHAR(255),
+    status VARCHAR(16) NOT NULL,
+    created_by VARCHAR(255) NOT NULL,
+    created_ts BIGINT NOT NULL,
+    closed_ts BIGINT,
+    resolved_by VARCHAR(255),
+    resolved_ts BIGINT,
+    winning_outcome BIGINT,
+    pool BIGINT NOT NULL DEFAULT 0,
+    house_take BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE TABLE market_outcomes (
+    market_id BIGINT NOT NULL REFERENCES markets (id),
+    outcome BIGINT NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    PRIMARY KEY (market_id, outcome)
+);
+
+-- Every stake is kept as its own row so the market can be audited later.
+CREATE TABLE market_positions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    market_id BIGINT NOT NULL REFERENCES markets (id),
+    discord_user_id VARCHAR(255) NOT NULL,
+    outcome BIGINT NOT NULL,
+    amount BIGINT NOT NULL,
+    created_ts BIGINT NOT NULL,
+    payout BIGINT
+);
+
+CREATE INDEX market_positions_market ON market_positions (market_id);
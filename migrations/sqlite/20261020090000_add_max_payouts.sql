@@ -0,0 +1,5 @@
+-- Add migration script here
+
+ALTER TABLE crash_bets ADD COLUMN max_cashout BIGINT;
+
+ALTER TABLE mines_games ADD COLUMN max_payout BIGINT;
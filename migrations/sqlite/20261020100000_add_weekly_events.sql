@@ -0,0 +1,7 @@
+-- Add migration script here
+
+ALTER TABLE events ADD COLUMN weekly BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE crash_rounds ADD COLUMN boost REAL NOT NULL DEFAULT 1;
+
+ALTER TABLE mines_games ADD COLUMN boost REAL NOT NULL DEFAULT 1;
@@ -0,0 +1,54 @@
+use std::path::Path;
+
+use crate::database::backup::{backup_database, restore_database};
+use crate::internal::backups;
+use crate::internal::clock::SystemClock;
+use crate::internal::data::Error;
+use crate::internal::settings::Settings;
+
+/// Copies the database to `path`, or into the backup directory with the
+/// retention applied when there is no path.
+pub async fn backup(path: Option<&Path>, settings: &Settings) -> Result<(), Error> {
+    let (database, _) = super::open_database(settings).await;
+
+    let Some(path) = path else {
+        let clock = SystemClock::new(settings.time.timezone());
+        let backup = backups::backup_now(&database, &settings.backups, &clock)
+            .await
+            .ok_or("Could not back up the database")?;
+        println!("Database copied to {}", backup.path.display());
+        return Ok(());
+    };
+
+    if path.exists() {
+        return Err(format!("{} already exists", path.display()).into());
+    }
+
+    if !backup_database(&database, path).await {
+        return Err("Could not back up the database".into());
+    }
+
+    println!("Database copied to {}", path.display());
+
+    Ok(())
+}
+
+/// Overwrites the database with a backup, the bot should not be running.
+pub async fn restore(path: &Path, settings: &Settings) -> Result<(), Error> {
+    if !path.is_file() {
+        return Err(format!("{} does not exist", path.display()).into());
+    }
+
+    let database = crate::database::init(settings).await;
+
+    if !restore_database(path, &database).await {
+        return Err(format!("Could not restore the database from {}", path.display()).into());
+    }
+
+    // Backups of older versions are brought up to date.
+    crate::database::migrate(&database).await;
+
+    println!("Database restored from {}", path.display());
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use crate::internal::data::Error;
+use crate::internal::settings::{Backend, Settings};
+
+/// Problems in the configuration the bot would only notice while running.
+fn problems(settings: &Settings) -> Vec<String> {
+    let mut problems = vec![];
+
+    if settings.discord.token.is_empty() {
+        problems.push("discord.token is empty".to_string());
+    }
+
+    if settings.potato_feeder.channel_id == 0 {
+        problems.push("potato-feeder.channel-id is not set".to_string());
+    }
+
+    if settings.database.backend == Backend::Postgres {
+        if settings.database.url.is_none() {
+            problems.push("database.url is needed for the PostgreSQL backend".to_string());
+        }
+        problems.push("the bot, export and import run on the SQLite backend only".to_string());
+    }
+
+    if settings.backups.directory.is_empty() {
+        problems.push("backups.directory is empty".to_string());
+    }
+
+    if settings.metrics.enabled
+        && settings
+            .metrics
+            .listen
+            .parse::<std::net::SocketAddr>()
+            .is_err()
+    {
+        problems.push(format!(
+            "metrics.listen {:?} is not an address to listen on",
+            settings.metrics.listen
+        ));
+    }
+
+    if settings.health.enabled
+        && settings
+            .health
+            .listen
+            .parse::<std::net::SocketAddr>()
+            .is_err()
+    {
+        problems.push(format!(
+            "health.listen {:?} is not an address to listen on",
+            settings.health.listen
+        ));
+    }
+
+    let fractions = [
+        ("give.fee", settings.give.fee),
+        ("jackpot.contribution", settings.jackpot.contribution),
+        ("jackpot.win-chance", settings.jackpot.win_chance),
+        ("markets.house-take", settings.markets.house_take),
+        ("poker.rake", settings.poker.rake),
+        ("race.house-take", settings.race.house_take),
+    ];
+
+    for (key, value) in fractions {
+        if !(0.0..=1.0).contains(&value) {
+            problems.push(format!("{} should be between 0 and 1, not {}", key, value));
+        }
+    }
+
+    problems
+}
+
+pub fn check_config(settings: &Settings) -> Result<(), Error> {
+    let problems = problems(settings);
+
+    if problems.is_empty() {
+        println!("Configuration is fine");
+        return Ok(());
+    }
+
+    for problem in &problems {
+        println!("{}", problem);
+    }
+
+    Err(format!("Found {} problems in the configuration", problems.len()).into())
+}
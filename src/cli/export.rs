@@ -0,0 +1,93 @@
+use std::fs;
+use std::path::Path;
+
+use crate::internal::clock::{Clock, SystemClock};
+use crate::internal::data::Error;
+use crate::internal::settings::Settings;
+use crate::internal::snapshot::{
+    self, Conflict, Format, Snapshot, LEDGER_CSV, PLAYERS_CSV, POOLS_CSV,
+};
+
+/// Writes the JSON export to `path` or stdout, the CSV files into the `path`
+/// directory.
+pub async fn export(format: Format, path: Option<&Path>, settings: &Settings) -> Result<(), Error> {
+    super::require_sqlite(settings)?;
+    let (database, players) = super::open_database(settings).await;
+    let clock = SystemClock::new(settings.time.timezone());
+    let snapshot = snapshot::take(&database, players.as_ref(), clock.timestamp()).await;
+
+    match (format, path) {
+        (Format::Json, Some(path)) => fs::write(path, snapshot.to_json()?)?,
+        (Format::Json, None) => println!("{}", snapshot.to_json()?),
+        (Format::Csv, Some(directory)) => {
+            fs::create_dir_all(directory)?;
+            for (name, csv) in snapshot.to_csv() {
+                fs::write(directory.join(name), csv)?;
+            }
+        }
+        (Format::Csv, None) => return Err("The CSV export needs a directory to write to".into()),
+    }
+
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<Snapshot, Error> {
+    if !path.is_dir() {
+        return Snapshot::from_json(&fs::read_to_string(path)?);
+    }
+
+    let read_table = |name| fs::read_to_string(path.join(name)).ok();
+
+    Snapshot::from_csv(
+        read_table(PLAYERS_CSV).as_deref(),
+        read_table(LEDGER_CSV).as_deref(),
+        read_table(POOLS_CSV).as_deref(),
+    )
+}
+
+/// Imports a JSON export, or a directory of CSV files, after checking it
+/// against the database.
+pub async fn import(
+    path: &Path,
+    dry_run: bool,
+    conflict: Conflict,
+    settings: &Settings,
+) -> Result<(), Error> {
+    super::require_sqlite(settings)?;
+
+    let snapshot =
+        read(path).map_err(|why| format!("Could not read {}: {}", path.display(), why))?;
+
+    let clock = SystemClock::new(settings.time.timezone());
+    let problems = snapshot.validate(clock.timestamp());
+    if !problems.is_empty() {
+        for problem in &problems {
+            println!("{}", problem);
+        }
+        return Err(format!("Found {} problems in {}", problems.len(), path.display()).into());
+    }
+
+    let (database, players) = super::open_database(settings).await;
+    let current = snapshot::take(&database, players.as_ref(), clock.timestamp()).await;
+    let diff = snapshot::diff(
+        &snapshot,
+        &current.players,
+        &current.ledger,
+        &current.pools.unwrap_or_default(),
+    );
+
+    for line in diff.lines() {
+        println!("{}", line);
+    }
+    println!("{}", diff.summary());
+
+    if dry_run {
+        return Ok(());
+    }
+
+    snapshot::apply(&diff, conflict, &database).await?;
+
+    println!("Imported {}", path.display());
+
+    Ok(())
+}
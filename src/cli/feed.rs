@@ -0,0 +1,56 @@
+use crate::internal::clock::SystemClock;
+use crate::internal::data::Error;
+use crate::internal::feeder;
+use crate::internal::settings::Settings;
+
+pub async fn feed(dry_run: bool, settings: &Settings) -> Result<(), Error> {
+    let (database, players) = super::open_database(settings).await;
+    let amount = settings.potato_feeder.amount;
+    let clock = SystemClock::new(settings.time.timezone());
+
+    let feeding = if dry_run {
+        let (at, feeding) = feeder::preview(&database, players.as_ref(), &clock, amount).await;
+        println!("Feeding at {}", at.format("%Y-%m-%d %H:%M"));
+        feeding
+    } else {
+        feeder::feed(&database, players.as_ref(), &clock, amount)
+            .await
+            .ok_or("Players kept changing during the feeding, try again")?
+    };
+
+    for player in &feeding.removed {
+        println!("{} removed", player.discord_user_id);
+    }
+
+    for (player, charity) in &feeding.taxed {
+        println!(
+            "{} -{} for charity, {} left",
+            player.discord_user_id, charity, player.balance
+        );
+    }
+
+    for player in &feeding.fed {
+        println!(
+            "{} +{}, {} in total",
+            player.discord_user_id, feeding.share, player.balance
+        );
+    }
+
+    if feeding.charity > 0 && !feeding.fed.is_empty() {
+        println!(
+            "charity {} shared, {} to each fed player",
+            feeding.charity,
+            feeding.charity_share()
+        );
+    }
+
+    if feeding.leftover > 0 {
+        println!("house +{}", feeding.leftover);
+    }
+
+    if feeding.removed.is_empty() && feeding.taxed.is_empty() && feeding.fed.is_empty() {
+        println!("Nobody to feed");
+    }
+
+    Ok(())
+}
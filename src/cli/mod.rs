@@ -0,0 +1,103 @@
+use clap::{Parser, Subcommand};
+use sqlx::{Pool, Sqlite};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::database;
+use crate::database::repository::{self, PlayerRepository};
+use crate::internal::data::Error;
+use crate::internal::settings::{Backend, Settings};
+use crate::internal::snapshot::{Conflict, Format};
+
+mod backup;
+mod check_config;
+mod export;
+mod feed;
+mod player;
+mod simulate;
+
+pub use backup::{backup, restore};
+pub use check_config::check_config;
+pub use export::{export, import};
+pub use feed::feed;
+pub use player::player;
+pub use simulate::simulate;
+
+#[derive(Debug, Parser)]
+#[command(about = "Potato casino for Discord")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Starts the bot, the default without a command.
+    Run,
+    /// Brings the database up to date.
+    Migrate,
+    /// Loads the configuration and reports the problems in it.
+    CheckConfig,
+    /// Copies the database to a new file while the bot keeps running, into
+    /// the backup directory without a path.
+    Backup { path: Option<PathBuf> },
+    /// Overwrites the database with a backup, stop the bot first.
+    Restore { path: PathBuf },
+    /// Writes the players, the house ledger, the pools and the statistics as
+    /// JSON to the file or stdout, or as CSV files into the directory.
+    Export {
+        #[arg(long, value_enum, default_value_t)]
+        format: Format,
+        path: Option<PathBuf>,
+    },
+    /// Reads the players, the house ledger and the pools written by `export`,
+    /// a directory is read as CSV.
+    Import {
+        path: PathBuf,
+        /// Only shows what would change.
+        #[arg(long)]
+        dry_run: bool,
+        /// What to do with the rows that differ from the database.
+        #[arg(long, value_enum, default_value_t)]
+        on_conflict: Conflict,
+    },
+    /// Feeds the players not fed since the last Friday, without telling them
+    /// on Discord.
+    Feed {
+        /// Only shows what the next feeding would do.
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Shows or changes a player.
+    Player {
+        #[command(subcommand)]
+        command: player::PlayerCommand,
+    },
+    /// Simulates the economy for the given weeks and prints a CSV row per
+    /// week, for tuning the feeder before changing it.
+    Simulate(simulate::SimulateArgs),
+}
+
+/// Opens the database the same way the bot does, migrations included.
+async fn open_database(settings: &Settings) -> (Pool<Sqlite>, Arc<dyn PlayerRepository>) {
+    let database = database::init(settings).await;
+    database::migrate(&database).await;
+
+    let players = repository::player_repository(settings, &database).await;
+
+    (database, players)
+}
+
+/// For the commands moving the players along with the game tables, which
+/// only the SQLite backend keeps in the same database.
+fn require_sqlite(settings: &Settings) -> Result<(), Error> {
+    match settings.database.backend {
+        Backend::Sqlite => Ok(()),
+        Backend::Postgres => Err("This command runs on the SQLite backend only".into()),
+    }
+}
+
+pub async fn migrate(settings: &Settings) {
+    open_database(settings).await;
+    println!("Database is up to date");
+}
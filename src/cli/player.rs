@@ -0,0 +1,64 @@
+use clap::Subcommand;
+
+use crate::database::players::IdleStatus;
+use crate::internal::clock::{Clock, SystemClock};
+use crate::internal::data::Error;
+use crate::internal::events::format_time;
+use crate::internal::settings::Settings;
+
+#[derive(Debug, Subcommand)]
+pub enum PlayerCommand {
+    Show {
+        /// Discord user id of the player.
+        id: String,
+    },
+    Set {
+        /// Discord user id of the player.
+        id: String,
+        #[arg(long)]
+        balance: i64,
+    },
+}
+
+fn describe_idle_status(status: IdleStatus) -> &'static str {
+    match status {
+        IdleStatus::Active => "active",
+        IdleStatus::Away => "away",
+        IdleStatus::Sleeping => "sleeping",
+        IdleStatus::Missing => "missing",
+        IdleStatus::Dead => "dead",
+    }
+}
+
+pub async fn player(command: &PlayerCommand, settings: &Settings) -> Result<(), Error> {
+    let (_, players) = super::open_database(settings).await;
+    let clock = SystemClock::new(settings.time.timezone());
+    let tz = clock.timezone();
+
+    let id = match command {
+        PlayerCommand::Show { id } | PlayerCommand::Set { id, .. } => id,
+    };
+
+    let Some(mut player) = players.find_player(id).await else {
+        return Err(format!("Player {} not found", id).into());
+    };
+
+    if let PlayerCommand::Set { balance, .. } = command {
+        player.balance = *balance;
+        if !players.update_player(&mut player).await {
+            return Err(format!("Player {} was changed in the meantime", id).into());
+        }
+    }
+
+    println!("id:          {}", player.discord_user_id);
+    println!("balance:     {}", player.balance);
+    println!("last fed:    {}", format_time(player.last_feed_ts, &tz));
+    println!(
+        "idle since:  {} ({})",
+        format_time(player.idle_since_ts, &tz),
+        describe_idle_status(player.idle_status(clock.timestamp()))
+    );
+    println!("version:     {}", player.version);
+
+    Ok(())
+}
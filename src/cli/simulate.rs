@@ -0,0 +1,103 @@
+use clap::Args;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::database::players::CharityRates;
+use crate::engine::simulation::{self, Model};
+use crate::internal::clock::SystemClock;
+use crate::internal::data::Error;
+use crate::internal::settings::Settings;
+
+#[derive(Debug, Args)]
+pub struct SimulateArgs {
+    #[arg(long, default_value_t = 52)]
+    weeks: u32,
+    /// Simulates this many new players instead of the players in the database.
+    #[arg(long)]
+    synthetic: Option<usize>,
+    /// Potatoes fed to every active player, the configured amount by default.
+    #[arg(long)]
+    amount: Option<i64>,
+    /// Chance of a player playing during a week.
+    #[arg(long, default_value_t = 0.5)]
+    activity: f64,
+    /// Coin flips of a player in an active week.
+    #[arg(long, default_value_t = 5)]
+    bets: u32,
+    /// Percentage of the balance bet on every flip and given away on every
+    /// gift.
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(i8).range(0..=100))]
+    stake: i8,
+    /// Gifts of a player to a random other player in an active week, the fee
+    /// is taken as configured.
+    #[arg(long, default_value_t = 1)]
+    gives: u32,
+    /// Away players give 1/N of their balance to charity at every feeding.
+    #[arg(long, default_value_t = CharityRates::default().away, value_parser = clap::value_parser!(i64).range(1..))]
+    away_charity: i64,
+    /// Sleeping players give 1/N of their balance to charity at every feeding.
+    #[arg(long, default_value_t = CharityRates::default().sleeping, value_parser = clap::value_parser!(i64).range(1..))]
+    sleeping_charity: i64,
+    /// Missing players give 1/N of their balance to charity at every feeding.
+    #[arg(long, default_value_t = CharityRates::default().missing, value_parser = clap::value_parser!(i64).range(1..))]
+    missing_charity: i64,
+    /// Seed of the random numbers, for repeatable runs.
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+pub async fn simulate(args: &SimulateArgs, settings: &Settings) -> Result<(), Error> {
+    let start = simulation::start(&SystemClock::new(settings.time.timezone()));
+
+    let players = match args.synthetic {
+        Some(count) => simulation::synthetic_players(count, start.timestamp()),
+        None => {
+            let (_, players) = super::open_database(settings).await;
+            players.load_players_by_balance().await
+        }
+    };
+
+    let model = Model {
+        weeks: args.weeks,
+        amount: args.amount.unwrap_or(settings.potato_feeder.amount),
+        activity: args.activity,
+        bets: args.bets,
+        stake: args.stake,
+        gives: args.gives,
+        fee: settings.give.fee,
+        fee_to: settings.give.fee_to.clone(),
+        jackpot: settings.jackpot.clone(),
+        charity: CharityRates {
+            away: args.away_charity,
+            sleeping: args.sleeping_charity,
+            missing: args.missing_charity,
+        },
+    };
+
+    let mut rng = match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    };
+
+    let weeks = simulation::simulate(&players, &model, start, &mut rng).await;
+
+    println!("week,players,removed,total,median,p10,p90,max,gini,house,jackpot");
+    for week in weeks {
+        println!(
+            "{},{},{},{},{},{},{},{},{:.4},{},{}",
+            week.week,
+            week.players,
+            week.removed,
+            week.total,
+            week.median,
+            week.p10,
+            week.p90,
+            week.max,
+            week.gini,
+            week.house,
+            week.jackpot
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use chrono::DateTime;
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::database::achievements::load_achievements;
+use crate::internal::achievements::{Achievement, ALL_ACHIEVEMENTS};
+use crate::internal::data::{Context, Error};
+
+/// Shows unlocked achievements.
+///
+/// Usage: `!achievements [@<mention>]`
+///
+/// Example: `!achievements @jaxx`
+#[poise::command(
+    prefix_command,
+    aliases("ach"),
+    broadcast_typing,
+    category = "Potato Game"
+)]
+pub async fn achievements(
+    ctx: Context<'_>,
+    #[description = "User whose achievements to show"] user: Option<serenity::User>,
+) -> Result<(), Error> {
+    let user = user.as_ref().unwrap_or_else(|| ctx.author());
+    let display_name = user.global_name.clone().unwrap_or(user.name.clone());
+
+    let unlocked = load_achievements(&user.id.to_string(), &ctx.data().database).await;
+
+    let embed = ALL_ACHIEVEMENTS.iter().fold(
+        serenity::CreateEmbed::new()
+            .title(format!(":trophy: {} saavutused", display_name))
+            .color(serenity::Color::DARK_GREEN),
+        |embed, achievement| {
+            let unlocked_ts = unlocked
+                .iter()
+                .find(|u| Achievement::from_key(&u.achievement) == Some(*achievement))
+                .map(|u| u.unlocked_ts);
+            let name = match unlocked_ts.and_then(|ts| DateTime::from_timestamp(ts, 0)) {
+                Some(dt) => format!(
+                    ":white_check_mark: {} ({})",
+                    achievement.title(),
+                    dt.format("%d.%m.%Y")
+                ),
+                None => format!(":lock: {}", achievement.title()),
+            };
+            embed.field(name, achievement.description(), false)
+        },
+    );
+
+    let reply = poise::CreateReply::default().embed(embed);
+
+    if let Err(why) = ctx.send(reply).await {
+        error!("Error sending message: {why:?}");
+    }
+
+    Ok(())
+}
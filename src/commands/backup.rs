@@ -0,0 +1,79 @@
+use poise::serenity_prelude as serenity;
+use std::path::Path;
+use tracing::error;
+
+use crate::internal::backups::{self, Backup};
+use crate::internal::data::{Context, Error};
+use crate::internal::discord;
+
+fn describe(backup: &Backup) -> String {
+    format!(
+        "`{}` {}, {} KB",
+        backup
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default(),
+        backup.taken.format("%d.%m.%Y %H:%M"),
+        backup.size.div_ceil(1024)
+    )
+}
+
+/// Lists the database backups.
+///
+/// Usage: `!backup`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    guild_only,
+    required_permissions = "MANAGE_GUILD",
+    subcommands("now")
+)]
+pub async fn backup(ctx: Context<'_>) -> Result<(), Error> {
+    let backups = backups::list_backups(
+        Path::new(&ctx.data().backups.directory),
+        &ctx.data().clock.timezone(),
+    );
+
+    let description = if backups.is_empty() {
+        "Varukoopiaid pole veel tehtud.".to_string()
+    } else {
+        backups.iter().map(describe).collect::<Vec<_>>().join("\n")
+    };
+
+    let embed = serenity::CreateEmbed::new()
+        .title(":floppy_disk: Varukoopiad")
+        .description(description)
+        .color(serenity::Color::BLUE);
+
+    let reply = poise::CreateReply::default().embed(embed).ephemeral(true);
+
+    if let Err(why) = ctx.send(reply).await {
+        error!("Error sending message: {why:?}");
+    }
+
+    Ok(())
+}
+
+/// Backs up the database right away.
+///
+/// Usage: `!backup now`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn now(ctx: Context<'_>) -> Result<(), Error> {
+    let data = ctx.data();
+
+    match backups::backup_now(&data.database, &data.backups, data.clock.as_ref()).await {
+        Some(backup) => {
+            discord::success_message(&ctx, format!("Varukoopia tehtud: {}", describe(&backup)))
+                .await
+        }
+        None => discord::failure_message(&ctx, "Varukoopia tegemine ebaõnnestus.").await,
+    }
+
+    Ok(())
+}
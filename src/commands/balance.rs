@@ -1,6 +1,5 @@
 use poise::serenity_prelude as serenity;
 
-use crate::database::players::find_player;
 use crate::internal::data::{Context, Error};
 use crate::internal::discord;
 use crate::internal::shared;
@@ -16,7 +15,7 @@ pub async fn balance(ctx: Context<'_>) -> Result<(), Error> {
     let user_id = ctx.author().id.to_string();
     let user_mention = serenity::Mention::from(ctx.author().id);
 
-    let player = find_player(&user_id, &ctx.data().database).await;
+    let player = ctx.data().players.find_player(&user_id).await;
 
     match player {
         Some(player) => {
@@ -27,7 +26,7 @@ pub async fn balance(ctx: Context<'_>) -> Result<(), Error> {
             .await;
         }
         None => {
-            shared::create_new_player(&ctx, &ctx.author().id, &ctx.data().database).await?;
+            shared::create_new_player(&ctx, &ctx.author().id).await?;
         }
     }
 
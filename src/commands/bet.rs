@@ -0,0 +1,487 @@
+use poise::serenity_prelude as serenity;
+use std::str::FromStr;
+use tracing::{error, warn};
+
+use crate::database::markets::{
+    create_market, find_market, load_market_outcomes, load_market_positions,
+    load_unsettled_markets, place_market_position, settle_market, update_market, Market,
+    MarketOutcome, MarketPosition, STATUS_CANCELLED, STATUS_CLOSED, STATUS_OPEN, STATUS_RESOLVED,
+};
+use crate::internal::betting::BetAmount;
+use crate::internal::cooldowns;
+use crate::internal::data::{Context, Error};
+use crate::internal::discord;
+use crate::internal::errors::PotatoGameError;
+use crate::internal::jackpot::JackpotRoller;
+use crate::internal::limits;
+use crate::internal::markets;
+use crate::internal::metrics;
+use crate::internal::shared;
+
+const MAX_OUTCOMES: usize = 10;
+
+/// Prediction markets - bet on the outcome of real world events.
+///
+/// Usage: `!bet [place <market> <outcome> all|half|some|<amount>[%]]`
+///
+/// `!bet` lists the open markets. The pool minus the house take is split
+/// between everyone who bet on the right outcome.
+///
+/// Example: `!bet place 3 yes 500`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    category = "Potato Game",
+    subcommands("place", "create", "close", "resolve", "cancel")
+)]
+pub async fn bet(ctx: Context<'_>) -> Result<(), Error> {
+    let markets = load_unsettled_markets(&ctx.data().database).await;
+
+    let description = if markets.is_empty() {
+        "Ühtegi turgu pole avatud.".to_string()
+    } else {
+        markets
+            .iter()
+            .map(|market| {
+                let status = if market.status == STATUS_CLOSED {
+                    " (suletud)"
+                } else {
+                    ""
+                };
+                format!("`#{}` {}{}", market.id, market.question, status)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let embed = serenity::CreateEmbed::new()
+        .title(":crystal_ball: Ennustusturud")
+        .description(description)
+        .color(serenity::Color::GOLD);
+
+    let reply = poise::CreateReply::default().embed(embed);
+
+    if let Err(why) = ctx.send(reply).await {
+        error!("Error sending message: {why:?}");
+    }
+
+    Ok(())
+}
+
+/// Bets on an outcome of the market.
+#[poise::command(prefix_command, broadcast_typing)]
+pub async fn place(
+    ctx: Context<'_>,
+    #[description = "Number of the market"] market_id: i64,
+    #[description = "Number or name of the outcome"] outcome: String,
+    #[description = "The amount you want to bet"] bet_amount_str: String,
+) -> Result<(), Error> {
+    let user_id = ctx.author().id.to_string();
+    let user_mention = serenity::Mention::from(ctx.author().id);
+    let database = &ctx.data().database;
+
+    let Ok(bet_amount) = BetAmount::from_str(&bet_amount_str) else {
+        cooldowns::refuse(&ctx).await;
+        let reply = poise::CreateReply::default().content("Ei saa aru, mida sa teha tahad!");
+        ctx.send(reply).await?;
+        return Ok(());
+    };
+
+    let Some(market) = find_market(market_id, database).await else {
+        discord::failure_message(
+            &ctx,
+            format!("{} Turgu `#{}` pole.", user_mention, market_id),
+        )
+        .await;
+        return Ok(());
+    };
+
+    if market.status != STATUS_OPEN {
+        discord::failure_message(
+            &ctx,
+            format!("{} Sellele turule panuseid enam ei võeta.", user_mention),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let outcomes = load_market_outcomes(market.id, database).await;
+    let Some(outcome) = markets::find_outcome(&outcomes, &outcome) else {
+        discord::failure_message(
+            &ctx,
+            format!(
+                "{} Sellist valikut pole, vali {}.",
+                user_mention,
+                outcomes
+                    .iter()
+                    .map(|outcome| format!("`{}`", outcome.name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        )
+        .await;
+        return Ok(());
+    };
+
+    let mut player = match ctx.data().players.find_player(&user_id).await {
+        Some(player) => player,
+        None => shared::create_new_player(&ctx, &ctx.author().id).await?,
+    };
+
+    let Some(amount) = shared::stake(&ctx, &bet_amount, &player).await else {
+        return Ok(());
+    };
+
+    if !limits::allows_bet(&ctx, amount).await {
+        return Ok(());
+    }
+
+    let now = ctx.data().clock.timestamp();
+
+    player.balance -= amount;
+    player.idle_since_ts = now;
+
+    let mut position = MarketPosition {
+        id: 0,
+        discord_user_id: user_id.clone(),
+        outcome: outcome.outcome,
+        amount,
+        created_ts: now,
+        payout: None,
+    };
+
+    if !place_market_position(
+        &mut player,
+        market.id,
+        &mut position,
+        ctx.data().clock.day(),
+        database,
+    )
+    .await
+    {
+        if find_market(market.id, database)
+            .await
+            .is_some_and(|current| current.status == STATUS_OPEN)
+        {
+            return Err(Box::new(PotatoGameError::ConcurrencyError));
+        }
+        discord::failure_message(
+            &ctx,
+            format!("{} Sellele turule panuseid enam ei võeta.", user_mention),
+        )
+        .await;
+        return Ok(());
+    }
+
+    metrics::bet("markets", amount);
+
+    discord::success_message(
+        &ctx,
+        format!(
+            "{} panustas {} :potato: valikule **{}**.",
+            user_mention, amount, outcome.name
+        ),
+    )
+    .await;
+
+    refresh_message(&ctx, &market).await;
+
+    Ok(())
+}
+
+/// Opens a new market.
+///
+/// Usage: `!bet create "<question>" <outcome>/<outcome>[/...]`
+///
+/// Example: `!bet create "Kas Eesti võidab?" jah/ei`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn create(
+    ctx: Context<'_>,
+    #[description = "The question to bet on"] question: String,
+    #[description = "Possible outcomes separated by /"] outcomes: String,
+) -> Result<(), Error> {
+    let database = &ctx.data().database;
+    let question = question.trim().to_string();
+
+    let names = outcomes
+        .split('/')
+        .map(|name| name.trim().to_string())
+        .collect::<Vec<_>>();
+
+    let is_valid = (2..=MAX_OUTCOMES).contains(&names.len())
+        && names.iter().all(|name| !name.is_empty())
+        && names.iter().enumerate().all(|(i, name)| {
+            names[..i]
+                .iter()
+                .all(|other| other.to_lowercase() != name.to_lowercase())
+        });
+
+    if question.is_empty() || !is_valid {
+        discord::failure_message(
+            &ctx,
+            format!(
+                "Turul peab olema küsimus ja 2 kuni {} erinevat valikut, näiteks `!bet create \"Kas Eesti võidab?\" jah/ei`.",
+                MAX_OUTCOMES
+            ),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let outcomes = names
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| MarketOutcome {
+            outcome: i as i64 + 1,
+            name,
+        })
+        .collect::<Vec<_>>();
+
+    let mut market = Market {
+        id: 0,
+        question,
+        channel_id: ctx.channel_id().to_string(),
+        message_id: None,
+        status: STATUS_OPEN.into(),
+        created_by: ctx.author().id.to_string(),
+        created_ts: ctx.data().clock.timestamp(),
+        closed_ts: None,
+        resolved_by: None,
+        resolved_ts: None,
+        winning_outcome: None,
+        pool: 0,
+        house_take: 0,
+    };
+
+    if !create_market(&mut market, &outcomes, database).await {
+        return Err(Box::new(PotatoGameError::ConcurrencyError));
+    }
+
+    let reply = poise::CreateReply::default().embed(markets::market_embed(
+        &market,
+        &outcomes,
+        &[],
+        ctx.data().markets.house_take,
+    ));
+    let message = ctx.send(reply).await?.into_message().await?;
+
+    market.message_id = Some(message.id.to_string());
+    if !update_market(&market, database).await {
+        warn!("Could not save message of market {}", market.id);
+    }
+
+    Ok(())
+}
+
+/// Stops taking bets on the market until it is resolved.
+///
+/// Usage: `!bet close <market>`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn close(
+    ctx: Context<'_>,
+    #[description = "Number of the market"] market_id: i64,
+) -> Result<(), Error> {
+    let database = &ctx.data().database;
+
+    let Some(mut market) = find_market(market_id, database).await else {
+        discord::failure_message(&ctx, format!("Turgu `#{}` pole.", market_id)).await;
+        return Ok(());
+    };
+
+    if market.status != STATUS_OPEN {
+        discord::failure_message(&ctx, format!("Turg `#{}` pole avatud.", market.id)).await;
+        return Ok(());
+    }
+
+    market.status = STATUS_CLOSED.into();
+    market.closed_ts = Some(ctx.data().clock.timestamp());
+
+    if !update_market(&market, database).await {
+        return Err(Box::new(PotatoGameError::ConcurrencyError));
+    }
+
+    discord::success_message(
+        &ctx,
+        format!("Turule `#{}` panuseid enam ei võeta.", market.id),
+    )
+    .await;
+
+    refresh_message(&ctx, &market).await;
+
+    Ok(())
+}
+
+/// Resolves the market and pays out everybody who bet on the right outcome.
+///
+/// Usage: `!bet resolve <market> <outcome>`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn resolve(
+    ctx: Context<'_>,
+    #[description = "Number of the market"] market_id: i64,
+    #[description = "Number or name of the winning outcome"] outcome: String,
+) -> Result<(), Error> {
+    let database = &ctx.data().database;
+
+    let Some(mut market) = find_market(market_id, database).await else {
+        discord::failure_message(&ctx, format!("Turgu `#{}` pole.", market_id)).await;
+        return Ok(());
+    };
+
+    let outcomes = load_market_outcomes(market.id, database).await;
+    let Some(winning_outcome) = markets::find_outcome(&outcomes, &outcome).map(|o| o.outcome)
+    else {
+        discord::failure_message(&ctx, "Sellist valikut pole.").await;
+        return Ok(());
+    };
+
+    let Some(positions) = close_for_settling(&ctx, &mut market).await? else {
+        return Ok(());
+    };
+
+    let (positions, house_take) =
+        markets::settle(&positions, winning_outcome, ctx.data().markets.house_take);
+
+    market.status = STATUS_RESOLVED.into();
+    market.winning_outcome = Some(winning_outcome);
+    market.house_take = house_take;
+
+    finish(&ctx, &mut market, &outcomes, &positions).await
+}
+
+/// Cancels the market and refunds every bet.
+///
+/// Usage: `!bet cancel <market>`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn cancel(
+    ctx: Context<'_>,
+    #[description = "Number of the market"] market_id: i64,
+) -> Result<(), Error> {
+    let database = &ctx.data().database;
+
+    let Some(mut market) = find_market(market_id, database).await else {
+        discord::failure_message(&ctx, format!("Turgu `#{}` pole.", market_id)).await;
+        return Ok(());
+    };
+
+    let outcomes = load_market_outcomes(market.id, database).await;
+
+    let Some(positions) = close_for_settling(&ctx, &mut market).await? else {
+        return Ok(());
+    };
+
+    let positions = markets::refund(&positions);
+
+    market.status = STATUS_CANCELLED.into();
+
+    finish(&ctx, &mut market, &outcomes, &positions).await
+}
+
+/// Closes the betting before the positions are counted so no bet sneaks in
+/// afterwards. `None` when the market is already settled.
+async fn close_for_settling(
+    ctx: &Context<'_>,
+    market: &mut Market,
+) -> Result<Option<Vec<MarketPosition>>, Error> {
+    let database = &ctx.data().database;
+
+    if market.status != STATUS_OPEN && market.status != STATUS_CLOSED {
+        discord::failure_message(ctx, format!("Turg `#{}` on juba lõppenud.", market.id)).await;
+        return Ok(None);
+    }
+
+    market.status = STATUS_CLOSED.into();
+    market.closed_ts = market.closed_ts.or(Some(ctx.data().clock.timestamp()));
+
+    if !update_market(market, database).await {
+        return Err(Box::new(PotatoGameError::ConcurrencyError));
+    }
+
+    Ok(Some(load_market_positions(market.id, database).await))
+}
+
+async fn finish(
+    ctx: &Context<'_>,
+    market: &mut Market,
+    outcomes: &[MarketOutcome],
+    positions: &[MarketPosition],
+) -> Result<(), Error> {
+    let database = &ctx.data().database;
+
+    market.pool = positions.iter().map(|position| position.amount).sum();
+    market.resolved_by = Some(ctx.author().id.to_string());
+    let now = ctx.data().clock.timestamp();
+    market.resolved_ts = Some(now);
+
+    if !settle_market(market, positions, ctx.data().clock.day(), database).await {
+        return Err(Box::new(PotatoGameError::ConcurrencyError));
+    }
+
+    for position in positions {
+        metrics::payout("markets", position.payout.unwrap_or_default());
+    }
+
+    let embed = markets::market_embed(market, outcomes, positions, ctx.data().markets.house_take);
+
+    markets::update_market_message(ctx.http(), market, embed.clone()).await;
+
+    let reply = poise::CreateReply::default().embed(embed);
+    if let Err(why) = ctx.send(reply).await {
+        error!("Error sending message: {why:?}");
+    }
+
+    if market.status == STATUS_RESOLVED {
+        let jackpot = JackpotRoller::from_data(ctx.data());
+        for position in positions {
+            let lost = if position.payout == Some(0) {
+                position.amount
+            } else {
+                0
+            };
+            jackpot
+                .settle_in(
+                    ctx.http(),
+                    &market.channel_id,
+                    &position.discord_user_id,
+                    lost,
+                    "markets",
+                )
+                .await;
+        }
+    }
+
+    Ok(())
+}
+
+async fn refresh_message(ctx: &Context<'_>, market: &Market) {
+    let database = &ctx.data().database;
+
+    let outcomes = load_market_outcomes(market.id, database).await;
+    let positions = load_market_positions(market.id, database).await;
+
+    markets::update_market_message(
+        ctx.http(),
+        market,
+        markets::market_embed(market, &outcomes, &positions, ctx.data().markets.house_take),
+    )
+    .await;
+}
@@ -0,0 +1,399 @@
+use poise::serenity_prelude as serenity;
+use rand::Rng;
+use std::str::FromStr;
+use tracing::{error, warn};
+
+use crate::database::craps::{
+    find_craps_point, load_craps_bets, place_craps_bet, save_craps_point, settle_craps_bet,
+    update_craps_bet_point, CrapsBet,
+};
+use crate::database::events::TARGET_CRAPS;
+use crate::database::players::IdleStatus;
+use crate::internal::achievements::{self, GameEvent};
+use crate::internal::betting::BetAmount;
+use crate::internal::cooldowns;
+use crate::internal::data::{Context, Error};
+use crate::internal::discord;
+use crate::internal::errors::PotatoGameError;
+use crate::internal::events;
+use crate::internal::house;
+use crate::internal::jackpot;
+use crate::internal::limits;
+use crate::internal::metrics;
+use crate::internal::shared;
+
+const POINT_NUMBERS: [i64; 6] = [4, 5, 6, 8, 9, 10];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BetKind {
+    Pass,
+    DontPass,
+    Come,
+    DontCome,
+}
+
+impl BetKind {
+    fn key(&self) -> &'static str {
+        match self {
+            BetKind::Pass => "pass",
+            BetKind::DontPass => "dontpass",
+            BetKind::Come => "come",
+            BetKind::DontCome => "dontcome",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        [
+            BetKind::Pass,
+            BetKind::DontPass,
+            BetKind::Come,
+            BetKind::DontCome,
+        ]
+        .into_iter()
+        .find(|kind| kind.key() == key)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            BetKind::Pass => "Pass",
+            BetKind::DontPass => "Don't Pass",
+            BetKind::Come => "Come",
+            BetKind::DontCome => "Don't Come",
+        }
+    }
+
+    fn is_dont(&self) -> bool {
+        matches!(self, BetKind::DontPass | BetKind::DontCome)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Win,
+    Lose,
+    Push,
+    /// The bet stays on the table with the given point.
+    Stay(i64),
+}
+
+/// Settles a bet on the roll, come bets follow the same rules as line bets
+/// but with their own point.
+fn resolve(kind: BetKind, point: Option<i64>, roll: i64) -> Outcome {
+    match (kind.is_dont(), point, roll) {
+        (false, None, 7 | 11) => Outcome::Win,
+        (false, None, 2 | 3 | 12) => Outcome::Lose,
+        (true, None, 2 | 3) => Outcome::Win,
+        (true, None, 12) => Outcome::Push,
+        (true, None, 7 | 11) => Outcome::Lose,
+        (_, None, roll) => Outcome::Stay(roll),
+        (false, Some(point), roll) if roll == point => Outcome::Win,
+        (false, Some(_), 7) => Outcome::Lose,
+        (true, Some(_), 7) => Outcome::Win,
+        (true, Some(point), roll) if roll == point => Outcome::Lose,
+        (_, Some(point), _) => Outcome::Stay(point),
+    }
+}
+
+/// Simplified craps against the house.
+///
+/// Usage: `!craps [pass|dontpass|come|dontcome <amount>|roll]`
+///
+/// Line bets are made on the come-out roll, come bets once the point is set.
+///
+/// Example: `!craps pass 500`
+/// Example: `!craps roll`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    category = "Potato Game",
+    subcommands("pass", "dontpass", "come", "dontcome", "roll")
+)]
+pub async fn craps(ctx: Context<'_>) -> Result<(), Error> {
+    show_table(ctx).await
+}
+
+/// Bets that the shooter makes the point.
+#[poise::command(prefix_command, broadcast_typing)]
+pub async fn pass(
+    ctx: Context<'_>,
+    #[description = "The amount you want to bet"] bet_amount_str: String,
+) -> Result<(), Error> {
+    place_bet(ctx, BetKind::Pass, &bet_amount_str).await
+}
+
+/// Bets that the shooter sevens out before making the point.
+#[poise::command(prefix_command, broadcast_typing)]
+pub async fn dontpass(
+    ctx: Context<'_>,
+    #[description = "The amount you want to bet"] bet_amount_str: String,
+) -> Result<(), Error> {
+    place_bet(ctx, BetKind::DontPass, &bet_amount_str).await
+}
+
+/// Like pass, but made after the point is set.
+#[poise::command(prefix_command, broadcast_typing)]
+pub async fn come(
+    ctx: Context<'_>,
+    #[description = "The amount you want to bet"] bet_amount_str: String,
+) -> Result<(), Error> {
+    place_bet(ctx, BetKind::Come, &bet_amount_str).await
+}
+
+/// Like don't pass, but made after the point is set.
+#[poise::command(prefix_command, broadcast_typing)]
+pub async fn dontcome(
+    ctx: Context<'_>,
+    #[description = "The amount you want to bet"] bet_amount_str: String,
+) -> Result<(), Error> {
+    place_bet(ctx, BetKind::DontCome, &bet_amount_str).await
+}
+
+/// Rolls the dice and settles your bets.
+#[poise::command(prefix_command, broadcast_typing)]
+pub async fn roll(ctx: Context<'_>) -> Result<(), Error> {
+    let user_id = ctx.author().id.to_string();
+    let user_mention = serenity::Mention::from(ctx.author().id);
+    let database = &ctx.data().database;
+
+    let bets = load_craps_bets(&user_id, database).await;
+    if bets.is_empty() {
+        discord::failure_message(
+            &ctx,
+            format!("{} Enne veeretamist tee panus.", user_mention),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let table_point = find_craps_point(&user_id, database).await;
+
+    let dice = {
+        let mut rng = rand::rng();
+        (rng.random_range(1..=6i64), rng.random_range(1..=6i64))
+    };
+    let total = dice.0 + dice.1;
+
+    let now = ctx.data().clock.timestamp();
+    let multiplier = events::multiplier(TARGET_CRAPS, now, database).await;
+
+    let mut lines = Vec::new();
+    let mut net = 0i64;
+    let mut lost = 0i64;
+
+    for mut bet in bets {
+        let Some(kind) = BetKind::from_key(&bet.kind) else {
+            warn!("Unknown craps bet kind {}", bet.kind);
+            continue;
+        };
+
+        let outcome = resolve(kind, bet.point, total);
+
+        let (payout, description) = match outcome {
+            Outcome::Stay(point) => {
+                if bet.point.is_none() {
+                    bet.point = Some(point);
+                    if !update_craps_bet_point(&bet, database).await {
+                        warn!("Could not move craps bet {} to point", bet.id);
+                    }
+                    lines.push(format!(
+                        "{} {} :potato: liigub punktile {}",
+                        kind.name(),
+                        bet.amount,
+                        point
+                    ));
+                }
+                continue;
+            }
+            Outcome::Win => {
+                let payout = events::multiply(bet.amount * 2, multiplier);
+                (payout, format!("võitis {} :potato:", payout - bet.amount))
+            }
+            Outcome::Push => (bet.amount, "jäi viiki".to_string()),
+            Outcome::Lose => (0, format!("kaotas {} :potato:", bet.amount)),
+        };
+
+        // Somebody else rolled at the same time and already settled the bet.
+        if !settle_craps_bet(&bet, payout, ctx.data().clock.day(), database).await {
+            continue;
+        }
+        metrics::payout("craps", payout);
+
+        if outcome == Outcome::Lose {
+            lost += bet.amount;
+        }
+        net += payout - bet.amount;
+        lines.push(format!("{} {}", kind.name(), description));
+    }
+
+    let next_point = match table_point {
+        None if POINT_NUMBERS.contains(&total) => Some(total),
+        Some(point) if total == point || total == 7 => None,
+        point => point,
+    };
+
+    if next_point != table_point && !save_craps_point(&user_id, next_point, database).await {
+        return Err(Box::new(PotatoGameError::ConcurrencyError));
+    }
+
+    let mut message = format!(
+        "{} :game_die: {} + {} = **{}**\n{}",
+        user_mention,
+        dice.0,
+        dice.1,
+        total,
+        lines.join("\n")
+    );
+    match next_point {
+        Some(point) => message.push_str(&format!("\nPunkt on {}.", point)),
+        None => message.push_str("\nJärgmine on come-out vise."),
+    }
+
+    if net >= 0 {
+        discord::success_message(&ctx, message).await;
+    } else {
+        discord::loss_message(&ctx, message).await;
+    }
+
+    jackpot::roll(&ctx, lost, "craps").await;
+
+    Ok(())
+}
+
+async fn place_bet(ctx: Context<'_>, kind: BetKind, bet_amount_str: &str) -> Result<(), Error> {
+    let user_id = ctx.author().id.to_string();
+    let user_mention = serenity::Mention::from(ctx.author().id);
+    let database = &ctx.data().database;
+
+    let Ok(bet_amount) = BetAmount::from_str(bet_amount_str) else {
+        cooldowns::refuse(&ctx).await;
+        let reply = poise::CreateReply::default().content("Ei saa aru, mida sa teha tahad!");
+        ctx.send(reply).await?;
+        return Ok(());
+    };
+
+    let table_point = find_craps_point(&user_id, database).await;
+
+    match (kind, table_point) {
+        (BetKind::Pass | BetKind::DontPass, Some(point)) => {
+            discord::failure_message(
+                &ctx,
+                format!(
+                    "{} Punkt on juba {}, tee come või don't come panus.",
+                    user_mention, point
+                ),
+            )
+            .await;
+            return Ok(());
+        }
+        (BetKind::Come | BetKind::DontCome, None) => {
+            discord::failure_message(
+                &ctx,
+                format!(
+                    "{} Punkti pole veel, tee pass või don't pass panus.",
+                    user_mention
+                ),
+            )
+            .await;
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let mut player = match ctx.data().players.find_player(&user_id).await {
+        Some(player) => player,
+        None => shared::create_new_player(&ctx, &ctx.author().id).await?,
+    };
+
+    let Some(amount) = shared::stake(&ctx, &bet_amount, &player).await else {
+        return Ok(());
+    };
+
+    if !limits::allows_bet(&ctx, amount).await {
+        return Ok(());
+    }
+
+    let now = ctx.data().clock.timestamp();
+    let multiplier = events::multiplier(TARGET_CRAPS, now, database).await;
+
+    if !house::covers(&ctx, events::multiply(amount * 2, multiplier) - amount).await {
+        return Ok(());
+    }
+
+    let was_dead = matches!(player.idle_status(now), IdleStatus::Dead);
+
+    player.balance -= amount;
+    player.idle_since_ts = now;
+
+    let mut bet = CrapsBet {
+        id: 0,
+        discord_user_id: user_id.clone(),
+        kind: kind.key().into(),
+        amount,
+        point: None,
+    };
+
+    if !place_craps_bet(&mut player, &mut bet, ctx.data().clock.day(), database).await {
+        return Err(Box::new(PotatoGameError::ConcurrencyError));
+    }
+
+    metrics::bet("craps", amount);
+
+    discord::success_message(
+        &ctx,
+        format!(
+            "{} tegi {} panuse {} :potato:.",
+            user_mention,
+            kind.name(),
+            amount
+        ),
+    )
+    .await;
+
+    if was_dead {
+        achievements::record(&ctx, ctx.author().id, &[GameEvent::ReturnedFromDead]).await;
+    }
+
+    Ok(())
+}
+
+async fn show_table(ctx: Context<'_>) -> Result<(), Error> {
+    let user_id = ctx.author().id.to_string();
+    let database = &ctx.data().database;
+
+    let point = find_craps_point(&user_id, database).await;
+    let bets = load_craps_bets(&user_id, database).await;
+
+    let description = if bets.is_empty() {
+        "Panuseid pole. Alusta käsuga `!craps pass <panus>`.".to_string()
+    } else {
+        bets.iter()
+            .filter_map(|bet| {
+                BetKind::from_key(&bet.kind).map(|kind| match bet.point {
+                    Some(point) => {
+                        format!("{} {} :potato: (punkt {})", kind.name(), bet.amount, point)
+                    }
+                    None => format!("{} {} :potato:", kind.name(), bet.amount),
+                })
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let embed = serenity::CreateEmbed::new()
+        .title(":game_die: Craps")
+        .description(description)
+        .field(
+            "Punkt",
+            point.map_or_else(|| "come-out".to_string(), |p| p.to_string()),
+            false,
+        )
+        .color(serenity::Color::DARK_GREEN);
+
+    let reply = poise::CreateReply::default().embed(embed);
+
+    if let Err(why) = ctx.send(reply).await {
+        error!("Error sending message: {why:?}");
+    }
+
+    Ok(())
+}
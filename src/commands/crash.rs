@@ -0,0 +1,247 @@
+use poise::serenity_prelude as serenity;
+use std::str::FromStr;
+use tracing::{error, warn};
+
+use crate::database::crash::{
+    find_open_crash_round, load_crash_bets, open_crash_round, place_crash_bet, update_crash_round,
+    CrashBet, CrashRound, STATUS_BETTING,
+};
+use crate::database::events::TARGET_CRASH;
+use crate::database::players::{IdleStatus, Player};
+use crate::internal::achievements::{self, GameEvent};
+use crate::internal::betting::BetAmount;
+use crate::internal::cooldowns;
+use crate::internal::crash;
+use crate::internal::data::{Context, Error};
+use crate::internal::discord;
+use crate::internal::errors::PotatoGameError;
+use crate::internal::events;
+use crate::internal::house;
+use crate::internal::jackpot::JackpotRoller;
+use crate::internal::limits;
+use crate::internal::metrics;
+use crate::internal::shared;
+
+/// Crash - cash out before the rocket crashes.
+///
+/// Usage: `!crash all|half|some|<amount>[%]`
+///
+/// The first bet opens a new round, others can join until the betting time
+/// runs out. Press the button to take your bet out before the multiplier crashes.
+///
+/// Example: `!crash 500`
+/// Example: `!crash 10%`
+#[poise::command(broadcast_typing, category = "Potato Game", prefix_command)]
+pub async fn crash(
+    ctx: Context<'_>,
+    #[description = "The amount you want to bet"] bet_amount_str: String,
+) -> Result<(), Error> {
+    let user_id = ctx.author().id.to_string();
+    let user_mention = serenity::Mention::from(ctx.author().id);
+    let channel_id = ctx.channel_id().to_string();
+    let database = &ctx.data().database;
+
+    let Ok(bet_amount) = BetAmount::from_str(&bet_amount_str) else {
+        cooldowns::refuse(&ctx).await;
+        let reply = poise::CreateReply::default().content("Ei saa aru, mida sa teha tahad!");
+        ctx.send(reply).await?;
+        return Ok(());
+    };
+
+    let open_round = find_open_crash_round(&channel_id, database).await;
+
+    if let Some(round) = &open_round {
+        if round.status != STATUS_BETTING {
+            discord::failure_message(
+                &ctx,
+                format!("{} Mäng juba käib, oota järgmist.", user_mention),
+            )
+            .await;
+            return Ok(());
+        }
+    }
+
+    let mut player = match ctx.data().players.find_player(&user_id).await {
+        Some(player) => player,
+        None => shared::create_new_player(&ctx, &ctx.author().id).await?,
+    };
+
+    let Some(amount) = shared::stake(&ctx, &bet_amount, &player).await else {
+        return Ok(());
+    };
+
+    if !limits::allows_bet(&ctx, amount).await {
+        return Ok(());
+    }
+
+    // The win is only known at cash-out, the bet is cashed out automatically
+    // at the most the house can cover.
+    let Some(max_win) = house::win_cap(&ctx, (amount + 99) / 100).await else {
+        return Ok(());
+    };
+    let now = ctx.data().clock.timestamp();
+    let boost = match &open_round {
+        Some(round) => round.boost,
+        None => events::multiplier(TARGET_CRASH, now, database).await,
+    };
+    let max_cashout = crash::max_cashout(amount, max_win, boost);
+
+    let was_dead = matches!(player.idle_status(now), IdleStatus::Dead);
+
+    player.balance -= amount;
+    player.idle_since_ts = now;
+
+    let bet = CrashBet {
+        round_id: open_round.as_ref().map_or(0, |round| round.id),
+        discord_user_id: user_id.clone(),
+        amount,
+        cashout: None,
+        max_cashout: Some(max_cashout),
+    };
+
+    let placed = match open_round {
+        Some(round) => join_round(ctx, round, player, bet).await?,
+        None => open_new_round(ctx, player, bet, boost).await?,
+    };
+
+    if placed && was_dead {
+        achievements::record(&ctx, ctx.author().id, &[GameEvent::ReturnedFromDead]).await;
+    }
+
+    Ok(())
+}
+
+/// Places the bet in the round taking bets, returns whether it was placed.
+async fn join_round(
+    ctx: Context<'_>,
+    round: CrashRound,
+    mut player: Player,
+    bet: CrashBet,
+) -> Result<bool, Error> {
+    let database = &ctx.data().database;
+    let amount = bet.amount;
+
+    if !place_crash_bet(&mut player, &bet, ctx.data().clock.day(), database).await {
+        let has_bet = load_crash_bets(round.id, database)
+            .await
+            .iter()
+            .any(|placed| placed.discord_user_id == bet.discord_user_id);
+        if !has_bet {
+            return Err(Box::new(PotatoGameError::ConcurrencyError));
+        }
+        discord::failure_message(
+            &ctx,
+            format!(
+                "{} Sul on selles mängus juba panus.",
+                serenity::Mention::from(ctx.author().id)
+            ),
+        )
+        .await;
+        return Ok(false);
+    }
+
+    metrics::bet("crash", amount);
+
+    let (Ok(channel_id), Some(Ok(message_id))) = (
+        round.channel_id.parse::<u64>(),
+        round.message_id.as_ref().map(|id| id.parse::<u64>()),
+    ) else {
+        return Ok(true);
+    };
+
+    let bets = load_crash_bets(round.id, database).await;
+    let edit =
+        serenity::EditMessage::new().embed(crash::betting_embed(&round, &bets, &ctx.data().crash));
+
+    if let Err(why) = serenity::ChannelId::new(channel_id)
+        .edit_message(ctx, serenity::MessageId::new(message_id), edit)
+        .await
+    {
+        error!("Error editing message: {why:?}");
+    }
+
+    discord::success_message(
+        &ctx,
+        format!(
+            "{} panustas {} :potato:.",
+            serenity::Mention::from(ctx.author().id),
+            amount
+        ),
+    )
+    .await;
+
+    Ok(true)
+}
+
+/// Opens a new round with the bet in it, returns whether it was placed.
+async fn open_new_round(
+    ctx: Context<'_>,
+    mut player: Player,
+    mut bet: CrashBet,
+    boost: f64,
+) -> Result<bool, Error> {
+    let database = &ctx.data().database;
+    let settings = &ctx.data().crash;
+    let now = ctx.data().clock.timestamp();
+    let amount = bet.amount;
+
+    let mut round = CrashRound {
+        id: 0,
+        channel_id: ctx.channel_id().to_string(),
+        message_id: None,
+        crash_point: crash::generate_crash_point(settings.house_edge),
+        status: STATUS_BETTING.into(),
+        created_ts: now,
+        started_ms: None,
+        boost,
+    };
+
+    if !open_crash_round(
+        &mut round,
+        &mut player,
+        &mut bet,
+        ctx.data().clock.day(),
+        database,
+    )
+    .await
+    {
+        if find_open_crash_round(&round.channel_id, database)
+            .await
+            .is_none()
+        {
+            return Err(Box::new(PotatoGameError::ConcurrencyError));
+        }
+        // Somebody else opened a round in this channel at the same time.
+        discord::failure_message(
+            &ctx,
+            format!(
+                "{} Mäng juba käib, proovi uuesti.",
+                serenity::Mention::from(ctx.author().id)
+            ),
+        )
+        .await;
+        return Ok(false);
+    }
+
+    metrics::bet("crash", amount);
+
+    let reply = poise::CreateReply::default().embed(crash::betting_embed(&round, &[bet], settings));
+    let message = ctx.send(reply).await?.into_message().await?;
+
+    round.message_id = Some(message.id.to_string());
+    if !update_crash_round(&round, database).await {
+        warn!("Could not save message of crash round {}", round.id);
+    }
+
+    tokio::spawn(crash::run_round(
+        ctx.serenity_context().clone(),
+        database.clone(),
+        settings.clone(),
+        ctx.data().clock.clone(),
+        JackpotRoller::from_data(ctx.data()),
+        round,
+        message,
+    ));
+
+    Ok(true)
+}
@@ -0,0 +1,171 @@
+use poise::serenity_prelude as serenity;
+use rand::Rng;
+use std::str::FromStr;
+
+use crate::database::events::TARGET_DICE;
+use crate::database::house::settle_player_bet;
+use crate::database::players::IdleStatus;
+use crate::internal::achievements::{self, GameEvent};
+use crate::internal::betting::BetAmount;
+use crate::internal::cooldowns;
+use crate::internal::data::{Context, Error};
+use crate::internal::discord;
+use crate::internal::errors::PotatoGameError;
+use crate::internal::events;
+use crate::internal::house;
+use crate::internal::jackpot;
+use crate::internal::limits;
+use crate::internal::metrics;
+use crate::internal::shared;
+
+const DICE_SIDES: i64 = 100;
+
+#[derive(Debug, PartialEq, poise::ChoiceParameter)]
+enum Direction {
+    #[name = "o"]
+    #[name = "over"]
+    Over,
+    #[name = "u"]
+    #[name = "under"]
+    Under,
+}
+
+/// Chance of winning in percents when betting on the roll of a d100.
+fn win_chance(direction: &Direction, target: i64) -> i64 {
+    match direction {
+        Direction::Over => DICE_SIDES - target,
+        Direction::Under => target - 1,
+    }
+}
+
+/// Total amount returned for a winning bet, fair odds minus the house edge.
+fn winning_payout(amount: i64, chance: i64, house_edge: f64) -> i64 {
+    (amount as f64 * (1.0 - house_edge) * DICE_SIDES as f64 / chance as f64).floor() as i64
+}
+
+/// Roll a d100 - win if the roll is over or under your target.
+///
+/// Usage: `all|half|some|<amount>[%] o|over|u|under <target>`
+///
+/// The lower the chance, the bigger the payout.
+///
+/// Example: `!dice 1000 over 50`
+/// Example: `!dice 10% under 10`
+#[poise::command(broadcast_typing, category = "Potato Game", prefix_command)]
+pub async fn dice(
+    ctx: Context<'_>,
+    #[description = "The amount you want to bet on"] bet_amount_str: String,
+    #[description = "Whether the roll has to be over or under the target"] direction: Direction,
+    #[description = "The target number between 1 and 100"] target: i64,
+) -> Result<(), Error> {
+    let user_mention = serenity::Mention::from(ctx.author().id);
+
+    let Ok(bet_amount) = BetAmount::from_str(&bet_amount_str) else {
+        cooldowns::refuse(&ctx).await;
+        let reply = poise::CreateReply::default().content("Ei saa aru, mida sa teha tahad!");
+        ctx.send(reply).await?;
+        return Ok(());
+    };
+
+    let chance = win_chance(&direction, target);
+    if !(1..=95).contains(&chance) {
+        discord::failure_message(
+            &ctx,
+            format!(
+                "{} Võidu tõenäosus peab jääma 1% ja 95% vahele.",
+                user_mention
+            ),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let user_id = ctx.author().id.to_string();
+    let database = &ctx.data().database;
+
+    let mut player = match ctx.data().players.find_player(&user_id).await {
+        Some(player) => player,
+        None => shared::create_new_player(&ctx, &ctx.author().id).await?,
+    };
+
+    let Some(amount) = shared::stake(&ctx, &bet_amount, &player).await else {
+        return Ok(());
+    };
+
+    let house_edge = ctx.data().dice.house_edge;
+
+    if !limits::allows_bet(&ctx, amount).await {
+        return Ok(());
+    }
+
+    let now = ctx.data().clock.timestamp();
+    let multiplier = events::multiplier(TARGET_DICE, now, database).await;
+    let win_payout = events::multiply(winning_payout(amount, chance, house_edge), multiplier);
+
+    if !house::covers(&ctx, win_payout - amount).await {
+        return Ok(());
+    }
+
+    let roll = rand::rng().random_range(1..=DICE_SIDES);
+    let is_win = match direction {
+        Direction::Over => roll > target,
+        Direction::Under => roll < target,
+    };
+
+    let payout = if is_win { win_payout } else { 0 };
+
+    let was_dead = matches!(player.idle_status(now), IdleStatus::Dead);
+
+    player.balance += payout - amount;
+    player.idle_since_ts = now;
+
+    let contribution = if is_win {
+        0
+    } else {
+        jackpot::contribution(amount, &ctx.data().jackpot)
+    };
+
+    if !settle_player_bet(
+        &mut player,
+        amount - payout - contribution,
+        contribution,
+        "dice",
+        ctx.data().clock.day(),
+        database,
+    )
+    .await
+    {
+        return Err(Box::new(PotatoGameError::ConcurrencyError));
+    }
+
+    metrics::bet("dice", amount);
+    metrics::payout("dice", payout);
+
+    if is_win {
+        discord::success_message(
+            &ctx,
+            format!(
+                "{} :game_die: {}! Palju õnne! Võitsid {} :potato:",
+                user_mention, roll, payout
+            ),
+        )
+        .await;
+    } else {
+        discord::loss_message(
+            &ctx,
+            format!(
+                "{} :game_die: {}. Seekord läks halvasti, oled {} :potato: võrra vaesem.",
+                user_mention, roll, amount
+            ),
+        )
+        .await;
+    }
+
+    if was_dead {
+        achievements::record(&ctx, ctx.author().id, &[GameEvent::ReturnedFromDead]).await;
+    }
+
+    jackpot::roll(&ctx, 0, "dice").await;
+
+    Ok(())
+}
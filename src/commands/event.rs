@@ -0,0 +1,302 @@
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::database::events::{
+    create_event, load_upcoming_events, remove_event, Event, TARGET_CRAPS, TARGET_CRASH,
+    TARGET_DICE, TARGET_FEEDER, TARGET_FLIP, TARGET_MINES, TARGET_RACE,
+};
+use crate::internal::data::{Context, Error};
+use crate::internal::discord;
+use crate::internal::events;
+
+#[derive(Debug, PartialEq, poise::ChoiceParameter)]
+enum EventTarget {
+    #[name = "flip"]
+    Flip,
+    #[name = "dice"]
+    Dice,
+    #[name = "craps"]
+    Craps,
+    #[name = "crash"]
+    Crash,
+    #[name = "mines"]
+    Mines,
+    #[name = "race"]
+    Race,
+    #[name = "feeder"]
+    Feeder,
+}
+
+impl EventTarget {
+    fn as_str(&self) -> &'static str {
+        match self {
+            EventTarget::Flip => TARGET_FLIP,
+            EventTarget::Dice => TARGET_DICE,
+            EventTarget::Craps => TARGET_CRAPS,
+            EventTarget::Crash => TARGET_CRASH,
+            EventTarget::Mines => TARGET_MINES,
+            EventTarget::Race => TARGET_RACE,
+            EventTarget::Feeder => TARGET_FEEDER,
+        }
+    }
+}
+
+/// Lists the scheduled events multiplying payouts.
+///
+/// Usage: `!event`
+#[poise::command(
+    prefix_command,
+    category = "Potato Game",
+    broadcast_typing,
+    subcommands("add", "weekly", "remove")
+)]
+pub async fn event(ctx: Context<'_>) -> Result<(), Error> {
+    let now = ctx.data().clock.timestamp();
+    let tz = ctx.data().clock.timezone();
+    let upcoming = load_upcoming_events(now, &ctx.data().database).await;
+
+    let description = if upcoming.is_empty() {
+        "Ühtegi sündmust pole plaanis.".to_string()
+    } else {
+        upcoming
+            .iter()
+            .map(|event| {
+                let status = match (event.starts_ts <= now, event.weekly) {
+                    (true, true) => " (käib, iga nädal)",
+                    (true, false) => " (käib)",
+                    (false, true) => " (iga nädal)",
+                    (false, false) => "",
+                };
+                format!(
+                    "`#{}` **{}**{}: {} x{}, {} kuni {}",
+                    event.id,
+                    event.name,
+                    status,
+                    events::describe_target(&event.target),
+                    event.multiplier,
+                    events::format_time(event.starts_ts, &tz),
+                    events::format_time(event.ends_ts, &tz)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let embed = serenity::CreateEmbed::new()
+        .title(":tada: Sündmused")
+        .description(description)
+        .color(serenity::Color::GOLD);
+
+    let reply = poise::CreateReply::default().embed(embed);
+
+    if let Err(why) = ctx.send(reply).await {
+        error!("Error sending message: {why:?}");
+    }
+
+    Ok(())
+}
+
+/// Schedules an event multiplying the payouts of a game.
+///
+/// Usage: `!event add flip|dice|craps|crash|mines|race|feeder <multiplier> <dd.mm.yyyy> <hh:mm> <duration> [name]`
+///
+/// Example: `!event add flip 2 23.10.2026 20:00 2h Reedene õnnetund`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "Payouts to multiply"] target: EventTarget,
+    #[description = "How many times the payouts are multiplied"] multiplier: f64,
+    #[description = "Start date, dd.mm.yyyy"] date: String,
+    #[description = "Start time, hh:mm"] time: String,
+    #[description = "How long the event lasts"] duration: String,
+    #[description = "Name of the event"]
+    #[rest]
+    name: Option<String>,
+) -> Result<(), Error> {
+    let starts_at = NaiveDate::parse_from_str(&date, "%d.%m.%Y")
+        .ok()
+        .zip(NaiveTime::parse_from_str(&time, "%H:%M").ok())
+        .and_then(|(date, time)| {
+            ctx.data()
+                .clock
+                .timezone()
+                .from_local_datetime(&date.and_time(time))
+                .single()
+        });
+
+    schedule(ctx, target, multiplier, starts_at, &duration, name, false).await
+}
+
+/// Schedules an event multiplying the payouts of a game every week.
+///
+/// Usage: `!event weekly flip|dice|craps|crash|mines|race|feeder <multiplier> <weekday> <hh:mm> <duration> [name]`
+///
+/// Example: `!event weekly flip 2 reede 20:00 2h Reedene õnnetund`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn weekly(
+    ctx: Context<'_>,
+    #[description = "Payouts to multiply"] target: EventTarget,
+    #[description = "How many times the payouts are multiplied"] multiplier: f64,
+    #[description = "Day of the week"] weekday: String,
+    #[description = "Start time, hh:mm"] time: String,
+    #[description = "How long the event lasts"] duration: String,
+    #[description = "Name of the event"]
+    #[rest]
+    name: Option<String>,
+) -> Result<(), Error> {
+    let now = ctx.data().clock.now();
+
+    let starts_at = parse_weekday(&weekday)
+        .zip(NaiveTime::parse_from_str(&time, "%H:%M").ok())
+        .and_then(|(weekday, time)| this_week(now, weekday, time));
+
+    schedule(ctx, target, multiplier, starts_at, &duration, name, true).await
+}
+
+/// Day of the week in Estonian or English.
+fn parse_weekday(weekday: &str) -> Option<Weekday> {
+    match weekday.to_lowercase().as_str() {
+        "esmaspäev" | "e" => Some(Weekday::Mon),
+        "teisipäev" | "t" => Some(Weekday::Tue),
+        "kolmapäev" | "k" => Some(Weekday::Wed),
+        "neljapäev" | "n" => Some(Weekday::Thu),
+        "reede" | "r" => Some(Weekday::Fri),
+        "laupäev" | "l" => Some(Weekday::Sat),
+        "pühapäev" | "p" => Some(Weekday::Sun),
+        weekday => weekday.parse().ok(),
+    }
+}
+
+/// The given day and time of the current week, weeks start on Monday.
+fn this_week(now: DateTime<Tz>, weekday: Weekday, time: NaiveTime) -> Option<DateTime<Tz>> {
+    let monday = now
+        .date_naive()
+        .checked_sub_days(Days::new(now.weekday().num_days_from_monday() as u64))?;
+    let date = monday.checked_add_days(Days::new(weekday.num_days_from_monday() as u64))?;
+
+    now.timezone()
+        .from_local_datetime(&date.and_time(time))
+        .earliest()
+}
+
+async fn schedule(
+    ctx: Context<'_>,
+    target: EventTarget,
+    multiplier: f64,
+    starts_at: Option<DateTime<Tz>>,
+    duration: &str,
+    name: Option<String>,
+    weekly: bool,
+) -> Result<(), Error> {
+    if !multiplier.is_finite() || multiplier <= 1.0 || multiplier > 10.0 {
+        discord::failure_message(&ctx, "Kordaja peab olema suurem kui 1 ja kuni 10.").await;
+        return Ok(());
+    }
+
+    let Some(starts_at) = starts_at else {
+        discord::failure_message(&ctx, "Ei saa aru, millal sündmus algab.").await;
+        return Ok(());
+    };
+
+    let duration_secs = match duration_str::parse(duration) {
+        Ok(duration) if duration.as_secs() > 0 => duration.as_secs() as i64,
+        _ => {
+            discord::failure_message(&ctx, "Ei saa aru, kui kaua sündmus kestab.").await;
+            return Ok(());
+        }
+    };
+
+    if weekly && duration_secs >= 7 * 86_400 {
+        discord::failure_message(&ctx, "Iganädalane sündmus peab kestma alla nädala.").await;
+        return Ok(());
+    }
+
+    let now = ctx.data().clock.timestamp();
+    let mut starts_ts = starts_at.timestamp();
+    let mut ends_ts = starts_ts + duration_secs;
+
+    if weekly {
+        (starts_ts, ends_ts) =
+            events::next_occurrence(&starts_at.timezone(), starts_ts, ends_ts, now);
+    }
+
+    if ends_ts <= now {
+        discord::failure_message(&ctx, "Sündmus oleks juba läbi.").await;
+        return Ok(());
+    }
+
+    let mut event = Event {
+        id: 0,
+        name: name
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| "Õnnetund".to_string()),
+        target: target.as_str().to_string(),
+        multiplier,
+        starts_ts,
+        ends_ts,
+        created_by: ctx.author().id.to_string(),
+        start_announced: false,
+        end_announced: false,
+        weekly,
+    };
+
+    if !create_event(&mut event, &ctx.data().database).await {
+        discord::failure_message(&ctx, "Sündmuse lisamine ebaõnnestus.").await;
+        return Ok(());
+    }
+
+    let repeat = if weekly { ", kordub iga nädal" } else { "" };
+
+    discord::success_message(
+        &ctx,
+        format!(
+            "Sündmus `#{}` **{}** on plaanis: {} x{}, {} kuni {}{}.",
+            event.id,
+            event.name,
+            events::describe_target(&event.target),
+            event.multiplier,
+            events::format_time(event.starts_ts, &starts_at.timezone()),
+            events::format_time(event.ends_ts, &starts_at.timezone()),
+            repeat
+        ),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Cancels a scheduled event.
+///
+/// Usage: `!event remove <id>`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "Number of the event"] id: i64,
+) -> Result<(), Error> {
+    if !remove_event(id, &ctx.data().database).await {
+        discord::failure_message(&ctx, format!("Sündmust `#{}` pole.", id)).await;
+        return Ok(());
+    }
+
+    discord::success_message(&ctx, format!("Sündmus `#{}` on tühistatud.", id)).await;
+
+    Ok(())
+}
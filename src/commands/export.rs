@@ -0,0 +1,49 @@
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::internal::data::{Context, Error};
+use crate::internal::snapshot::{self, Format};
+
+/// Exports the players, the house ledger and the statistics as files.
+///
+/// Usage: `!export [json|csv]`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn export(
+    ctx: Context<'_>,
+    #[description = "Format of the files"] format: Option<Format>,
+) -> Result<(), Error> {
+    let data = ctx.data();
+    let snapshot = snapshot::take(
+        &data.database,
+        data.players.as_ref(),
+        data.clock.timestamp(),
+    )
+    .await;
+
+    let files = match format.unwrap_or_default() {
+        Format::Json => vec![("etbot.json", snapshot.to_json()?)],
+        Format::Csv => snapshot.to_csv(),
+    };
+
+    let mut reply = poise::CreateReply::default().content(format!(
+        "{} mängijat, {} :potato: ringluses.",
+        snapshot.statistics.players, snapshot.statistics.circulation
+    ));
+    for (name, content) in files {
+        reply = reply.attachment(serenity::CreateAttachment::bytes(
+            content.into_bytes(),
+            name,
+        ));
+    }
+
+    if let Err(why) = ctx.send(reply).await {
+        error!("Error sending message: {why:?}");
+    }
+
+    Ok(())
+}
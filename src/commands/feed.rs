@@ -0,0 +1,110 @@
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::database::players::Player;
+use crate::internal::data::{Context, Error};
+use crate::internal::events;
+
+/// Longest description Discord accepts in an embed.
+const MAX_DESCRIPTION: usize = 4096;
+
+/// Potato feeding.
+#[poise::command(prefix_command, subcommands("preview"), subcommand_required)]
+pub async fn feed(_: Context<'_>) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Shows what the next feeding would do without feeding anybody.
+///
+/// Usage: `!feed preview`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn preview(ctx: Context<'_>) -> Result<(), Error> {
+    let (at, feeding) = ctx.data().feeder.preview().await;
+
+    let mention = |player: &Player| match player.discord_user_id.parse::<u64>() {
+        Ok(id) => serenity::Mention::from(serenity::UserId::new(id)).to_string(),
+        Err(_) => player.discord_user_id.clone(),
+    };
+
+    let mut sections = vec![format!(
+        "**Toitmine:** {}",
+        events::format_time(at.timestamp(), &at.timezone())
+    )];
+
+    if !feeding.fed.is_empty() {
+        sections.push(format!(
+            "**Saavad {} :potato:** ({} heategevusest):\n{}",
+            feeding.share,
+            feeding.charity_share(),
+            feeding
+                .fed
+                .iter()
+                .map(mention)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    if !feeding.taxed.is_empty() {
+        let taxed = feeding
+            .taxed
+            .iter()
+            .map(|(player, charity)| {
+                format!(
+                    "{} -{} :potato: (jääb {})",
+                    mention(player),
+                    charity,
+                    player.balance
+                )
+            })
+            .collect::<Vec<_>>();
+        sections.push(format!("**Annavad heategevusse:**\n{}", taxed.join("\n")));
+    }
+
+    if !feeding.removed.is_empty() {
+        sections.push(format!(
+            "**Visatakse välja:**\n{}",
+            feeding
+                .removed
+                .iter()
+                .map(mention)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    if feeding.leftover > 0 {
+        sections.push(format!("**Majale:** {} :potato:", feeding.leftover));
+    }
+
+    if feeding.fed.is_empty() && feeding.taxed.is_empty() && feeding.removed.is_empty() {
+        sections.push("Kedagi pole toita.".to_string());
+    }
+
+    let mut description = sections.join("\n\n");
+    if description.chars().count() > MAX_DESCRIPTION {
+        description = description
+            .chars()
+            .take(MAX_DESCRIPTION - 1)
+            .collect::<String>()
+            + "…";
+    }
+
+    let embed = serenity::CreateEmbed::new()
+        .title(":potato: Toitmise eelvaade")
+        .description(description)
+        .color(serenity::Color::GOLD);
+
+    let reply = poise::CreateReply::default().embed(embed).ephemeral(true);
+
+    if let Err(why) = ctx.send(reply).await {
+        error!("Error sending message: {why:?}");
+    }
+
+    Ok(())
+}
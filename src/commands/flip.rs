@@ -1,64 +1,21 @@
-use chrono::Utc;
 use poise::serenity_prelude as serenity;
-use rand::Rng;
 use std::str::FromStr;
 
-use crate::database::players::{find_player, update_player, Player};
+use crate::database::events::TARGET_FLIP;
+use crate::engine::flip::{self, CoinSide, Flip};
+use crate::internal::achievements::{self, GameEvent};
+use crate::internal::betting::BetAmount;
+use crate::internal::cooldowns;
 use crate::internal::data::{Context, Error};
 use crate::internal::discord;
 use crate::internal::errors::PotatoGameError;
+use crate::internal::events;
+use crate::internal::house;
+use crate::internal::jackpot;
+use crate::internal::limits;
+use crate::internal::metrics;
 use crate::internal::shared;
 
-#[derive(Debug)]
-enum BetAmount {
-    Specific(i64),
-    Percentage(i8),
-    Half,
-    All,
-    Some,
-}
-
-#[derive(Debug)]
-struct ParseBetAmountError;
-
-impl FromStr for BetAmount {
-    type Err = ParseBetAmountError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.to_lowercase().trim() {
-            "all" => Ok(BetAmount::All),
-            "half" => Ok(BetAmount::Half),
-            "some" => Ok(BetAmount::Some),
-            val if val.ends_with('%') => {
-                val.trim_end_matches('%')
-                    .parse::<i8>()
-                    .ok()
-                    .map_or(Err(ParseBetAmountError), |v| match v {
-                        x if (0..=100).contains(&x) => Ok(BetAmount::Percentage(x)),
-                        _ => Err(ParseBetAmountError),
-                    })
-            }
-            val => val
-                .parse::<i64>()
-                .ok()
-                .map_or(Err(ParseBetAmountError), |v| match v {
-                    x if x >= 0 => Ok(BetAmount::Specific(v)),
-                    _ => Err(ParseBetAmountError),
-                }),
-        }
-    }
-}
-
-#[derive(Debug, PartialEq, poise::ChoiceParameter)]
-enum CoinSide {
-    #[name = "h"]
-    #[name = "heads"]
-    Heads,
-    #[name = "t"]
-    #[name = "tails"]
-    Tails,
-}
-
 /// Flip a coin - game for fun.
 ///
 /// Usage: `all|half|some|<amount>[%] h|heads|t|tails`
@@ -77,6 +34,7 @@ pub async fn flip(
             process(ctx, &bet_amount, &coin_side).await?;
         }
         _ => {
+            cooldowns::refuse(&ctx).await;
             let reply = poise::CreateReply::default().content("Ei saa aru, mida sa teha tahad!");
 
             ctx.send(reply).await?;
@@ -94,53 +52,75 @@ async fn process(
     let user_id = ctx.author().id.to_string();
     let user_mention = serenity::Mention::from(ctx.author().id);
 
-    let player = find_player(&user_id, &ctx.data().database).await;
+    let player = ctx.data().players.find_player(&user_id).await;
 
     let mut player = match player {
         Some(player) => player,
-        None => shared::create_new_player(&ctx, &ctx.author().id, &ctx.data().database).await?,
+        None => shared::create_new_player(&ctx, &ctx.author().id).await?,
     };
 
-    let amount = calculate_amount(bet_amount, &player);
+    let Some(amount) = shared::stake(&ctx, bet_amount, &player).await else {
+        return Ok(());
+    };
 
-    if amount < 2 {
-        discord::failure_message(
-            &ctx,
-            format!("{} Minimaalne panus on 2 :potato:.", user_mention),
-        )
-        .await;
+    if !limits::allows_bet(&ctx, amount).await {
         return Ok(());
     }
 
-    if amount > player.balance {
-        discord::failure_message(
-            &ctx,
-            format!(
-                "{} Sul pole panuse tegemiseks piisavalt :potato:.",
-                user_mention
-            ),
-        )
-        .await;
+    let multiplier = events::multiplier(
+        TARGET_FLIP,
+        ctx.data().clock.timestamp(),
+        &ctx.data().database,
+    )
+    .await;
+
+    if !house::covers(&ctx, flip::win(amount, multiplier)).await {
         return Ok(());
     }
 
-    let toss_result = if rand::rng().random::<bool>() {
-        CoinSide::Heads
-    } else {
-        CoinSide::Tails
-    };
+    let Flip {
+        is_win,
+        win,
+        house_amount,
+        contribution,
+        was_dead,
+        ..
+    } = flip::toss(
+        &mut player,
+        amount,
+        coin_side,
+        multiplier,
+        &ctx.data().jackpot,
+        &mut rand::rng(),
+        ctx.data().clock.as_ref(),
+    );
+
+    if !ctx
+        .data()
+        .players
+        .settle_bet(
+            &mut player,
+            house_amount,
+            contribution,
+            "flip",
+            ctx.data().clock.day(),
+        )
+        .await
+    {
+        return Err(Box::new(PotatoGameError::ConcurrencyError));
+    }
 
-    let is_win = toss_result == *coin_side;
+    metrics::bet("flip", amount);
     if is_win {
-        player.balance += amount;
-    } else {
-        player.balance -= amount;
+        metrics::payout("flip", amount + win);
     }
 
-    player.idle_since_ts = Utc::now().timestamp();
-
-    if !update_player(&mut player, &ctx.data().database).await {
-        return Err(Box::new(PotatoGameError::ConcurrencyError));
+    let mut events = vec![GameEvent::Flip {
+        bet_all: matches!(bet_amount, BetAmount::All),
+        is_win,
+    }];
+    if was_dead {
+        events.push(GameEvent::ReturnedFromDead);
     }
 
     if !is_win {
@@ -155,7 +135,9 @@ async fn process(
                 user_mention, amount
             )
         };
-        discord::failure_message(&ctx, message).await;
+        discord::loss_message(&ctx, message).await;
+        achievements::record(&ctx, ctx.author().id, &events).await;
+        jackpot::roll(&ctx, 0, "flip").await;
         return Ok(());
     }
 
@@ -164,20 +146,13 @@ async fn process(
         format!(
             "{} Palju õnne! Võitsid {} :potato:",
             user_mention,
-            (amount * 2)
+            (amount + win)
         ),
     )
     .await;
 
-    Ok(())
-}
+    achievements::record(&ctx, ctx.author().id, &events).await;
+    jackpot::roll(&ctx, 0, "flip").await;
 
-fn calculate_amount(bet_amount: &BetAmount, player: &Player) -> i64 {
-    match bet_amount {
-        BetAmount::All => player.balance,
-        BetAmount::Half => player.balance / 2,
-        BetAmount::Some => rand::rng().random_range(2i64..=player.balance),
-        BetAmount::Specific(v) => *v,
-        BetAmount::Percentage(v) => (*v) as i64 * player.balance / 100i64,
-    }
+    Ok(())
 }
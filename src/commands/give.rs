@@ -1,17 +1,34 @@
-use chrono::Utc;
 use poise::serenity_prelude as serenity;
 
-use crate::database::players::{find_player, update_player};
+use tracing::error;
+
+use crate::database::transfers::{
+    load_transfers_since, sum_received_since, sum_sent_since, transfer_potatoes,
+};
+use crate::engine::give::{self, Gift};
+use crate::internal::achievements::{self, GameEvent};
 use crate::internal::data::{Context, Error};
 use crate::internal::discord;
+use crate::internal::errors::PotatoGameError;
 use crate::internal::shared;
+use crate::internal::transfers;
+
+const DAY: i64 = 86_400;
 
 /// Gives potatoes to another user.
 ///
 /// Usage: `!give <amount> @<mention>`
 ///
+/// A small fee is taken from every gift and there are daily limits on how
+/// much can be given and received.
+///
 /// Example: `!give 1500 @jaxx`
-#[poise::command(broadcast_typing, category = "Potato Game", prefix_command)]
+#[poise::command(
+    broadcast_typing,
+    category = "Potato Game",
+    prefix_command,
+    subcommands("report")
+)]
 pub async fn give(
     ctx: Context<'_>,
     #[description = "The amount you want to give to another user"] amount: i64,
@@ -38,50 +55,187 @@ pub async fn give(
         return Ok(());
     }
 
-    let mut sending_user =
-        match find_player(&ctx.author().id.to_string(), &ctx.data().database).await {
-            Some(player) => player,
-            None => shared::create_new_player(&ctx, &ctx.author().id, &ctx.data().database).await?,
-        };
-
-    let mut receiving_user = match find_player(&user.id.to_string(), &ctx.data().database).await {
-        Some(player) => player,
-        None => shared::create_new_player(&ctx, &user.id, &ctx.data().database).await?,
-    };
+    let settings = &ctx.data().give;
+    let now = ctx.data().clock.timestamp();
 
-    if sending_user.balance < amount {
+    let min_age = settings.min_receiver_age.as_secs() as i64;
+    if now - user.id.created_at().unix_timestamp() < min_age {
         discord::failure_message(
             &ctx,
             format!(
-                "{} Sul pole kinkimiseks piisavalt :potato:.",
-                serenity::Mention::from(ctx.author().id)
+                "Kasutaja {} konto on liiga uus, kinkida saab vähemalt {} vanustele kontodele.",
+                user_name,
+                shared::format_duration(min_age)
             ),
         )
         .await;
         return Ok(());
     }
 
-    sending_user.balance -= amount;
-    sending_user.idle_since_ts = Utc::now().timestamp();
+    let sender_id = ctx.author().id.to_string();
+    let receiver_id = user.id.to_string();
 
-    receiving_user.balance += amount;
+    if settings.daily_limit > 0 {
+        let sent = sum_sent_since(&sender_id, now - DAY, &ctx.data().database).await;
+        if sent + amount > settings.daily_limit {
+            discord::failure_message(
+                &ctx,
+                format!(
+                    "{} Päevas saab kinkida kuni {} :potato:, praegu saad kinkida veel {} :potato:.",
+                    serenity::Mention::from(ctx.author().id),
+                    settings.daily_limit,
+                    (settings.daily_limit - sent).max(0)
+                ),
+            )
+            .await;
+            return Ok(());
+        }
+    }
 
-    let tx = ctx.data().database.begin().await?;
+    if settings.recipient_daily_limit > 0 {
+        let received = sum_received_since(&receiver_id, now - DAY, &ctx.data().database).await;
+        if received + amount > settings.recipient_daily_limit {
+            discord::failure_message(
+                &ctx,
+                format!(
+                    "Kasutaja {} saab päevas vastu võtta kuni {} :potato:, praegu veel {} :potato:.",
+                    user_name,
+                    settings.recipient_daily_limit,
+                    (settings.recipient_daily_limit - received).max(0)
+                ),
+            )
+            .await;
+            return Ok(());
+        }
+    }
 
-    if update_player(&mut sending_user, &ctx.data().database).await
-        && update_player(&mut receiving_user, &ctx.data().database).await
+    let mut sending_user = match ctx
+        .data()
+        .players
+        .find_player(&ctx.author().id.to_string())
+        .await
     {
-        tx.commit().await?;
-        discord::success_message(
+        Some(player) => player,
+        None => shared::create_new_player(&ctx, &ctx.author().id).await?,
+    };
+
+    let mut receiving_user = match ctx.data().players.find_player(&user.id.to_string()).await {
+        Some(player) => player,
+        None => shared::create_new_player(&ctx, &user.id).await?,
+    };
+
+    let Ok(Gift { transfer, was_dead }) = give::give(
+        &mut sending_user,
+        &mut receiving_user,
+        amount,
+        settings.fee,
+        ctx.data().clock.as_ref(),
+    ) else {
+        discord::failure_message(
             &ctx,
             format!(
-                "{} kinkis kasutajale {} {} :potato:.",
-                serenity::Mention::from(ctx.author().id),
-                serenity::Mention::from(user.id),
-                amount
+                "{} Sul pole kinkimiseks piisavalt :potato:.",
+                serenity::Mention::from(ctx.author().id)
             ),
         )
         .await;
+        return Ok(());
+    };
+    let fee = transfer.fee;
+
+    if !transfer_potatoes(
+        &mut sending_user,
+        &mut receiving_user,
+        &transfer,
+        &settings.fee_to,
+        ctx.data().clock.day(),
+        &ctx.data().database,
+    )
+    .await
+    {
+        return Err(Box::new(PotatoGameError::ConcurrencyError));
+    }
+
+    let message = if fee > 0 {
+        format!(
+            "{} kinkis kasutajale {} {} :potato: (tasu {} :potato:).",
+            serenity::Mention::from(ctx.author().id),
+            serenity::Mention::from(user.id),
+            amount - fee,
+            fee
+        )
+    } else {
+        format!(
+            "{} kinkis kasutajale {} {} :potato:.",
+            serenity::Mention::from(ctx.author().id),
+            serenity::Mention::from(user.id),
+            amount
+        )
+    };
+    discord::success_message(&ctx, message).await;
+
+    let mut events = vec![GameEvent::Give { amount }];
+    if was_dead {
+        events.push(GameEvent::ReturnedFromDead);
+    }
+    achievements::record(&ctx, ctx.author().id, &events).await;
+
+    Ok(())
+}
+
+/// Lists players sending potatoes around in circles.
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    guild_only,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn report(ctx: Context<'_>) -> Result<(), Error> {
+    let window = ctx.data().give.report_window.as_secs() as i64;
+    let since_ts = ctx.data().clock.timestamp() - window;
+
+    let transfers = load_transfers_since(since_ts, &ctx.data().database).await;
+    let cycles = transfers::find_cycles(&transfers);
+
+    let mention = |user_id: &String| {
+        user_id
+            .parse::<u64>()
+            .map(|id| serenity::Mention::from(serenity::UserId::new(id)).to_string())
+            .unwrap_or_else(|_| user_id.clone())
+    };
+
+    let description = if cycles.is_empty() {
+        "Kahtlaseid ülekandeid ei leitud.".to_string()
+    } else {
+        cycles
+            .iter()
+            .map(|cycle| {
+                let steps = cycle
+                    .players
+                    .iter()
+                    .zip(cycle.amounts.iter())
+                    .map(|(player, amount)| format!("{} →({}) ", mention(player), amount))
+                    .collect::<String>();
+                format!("{}{}", steps, mention(&cycle.players[0]))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let embed = serenity::CreateEmbed::new()
+        .title(":mag: Ringlevad kingitused")
+        .description(description)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "Viimased {}, {} ülekannet",
+            shared::format_duration(window),
+            transfers.len()
+        )))
+        .color(serenity::Color::ORANGE);
+
+    let reply = poise::CreateReply::default().embed(embed).ephemeral(true);
+
+    if let Err(why) = ctx.send(reply).await {
+        error!("Error sending message: {why:?}");
     }
 
     Ok(())
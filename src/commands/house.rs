@@ -0,0 +1,56 @@
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::database::house::{find_house_balance, load_house_results, HouseResult};
+use crate::internal::data::{Context, Error};
+
+fn total(results: &[HouseResult]) -> String {
+    format!(
+        "{:+} :potato:",
+        results.iter().map(|result| result.amount).sum::<i64>()
+    )
+}
+
+/// Shows the balance of the house and how much it has won or lost.
+///
+/// The house pays out the wins and keeps the lost bets, fees and whatever is
+/// left over from charity.
+#[poise::command(prefix_command, category = "Potato Game", broadcast_typing)]
+pub async fn house(ctx: Context<'_>) -> Result<(), Error> {
+    let database = &ctx.data().database;
+    let today = ctx.data().clock.day();
+
+    let balance = find_house_balance(database).await;
+    let today_results = load_house_results(today, database).await;
+    let week_results = load_house_results(today - 6, database).await;
+    let month_results = load_house_results(today - 29, database).await;
+    let all_results = load_house_results(0, database).await;
+
+    let by_source = if month_results.is_empty() {
+        "Viimase 30 päeva jooksul pole midagi juhtunud.".to_string()
+    } else {
+        month_results
+            .iter()
+            .map(|result| format!("{}: {:+} :potato:", result.source, result.amount))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+
+    let embed = serenity::CreateEmbed::new()
+        .title(":bank: Maja")
+        .description(format!("Maja kontol on **{}** :potato:.", balance))
+        .field("Täna", total(&today_results), true)
+        .field("7 päeva", total(&week_results), true)
+        .field("30 päeva", total(&month_results), true)
+        .field("Kokku", total(&all_results), true)
+        .field("30 päeva allikate kaupa", by_source, false)
+        .color(serenity::Color::DARK_GOLD);
+
+    let reply = poise::CreateReply::default().embed(embed);
+
+    if let Err(why) = ctx.send(reply).await {
+        error!("Error sending message: {why:?}");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,55 @@
+use chrono::DateTime;
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::database::jackpot::find_last_jackpot_win;
+use crate::internal::data::{Context, Error};
+
+/// Shows the progressive jackpot.
+///
+/// A part of every losing bet goes into the pool and every bet has a tiny
+/// chance to win all of it.
+#[poise::command(prefix_command, category = "Potato Game", broadcast_typing)]
+pub async fn jackpot(ctx: Context<'_>) -> Result<(), Error> {
+    let database = &ctx.data().database;
+    let settings = &ctx.data().jackpot;
+
+    let pool = ctx.data().players.find_jackpot_pool().await;
+
+    let last_win = match find_last_jackpot_win(database).await {
+        Some(win) => {
+            let mention = win
+                .discord_user_id
+                .parse::<u64>()
+                .map(|id| serenity::Mention::from(serenity::UserId::new(id)).to_string())
+                .unwrap_or_else(|_| win.discord_user_id.clone());
+            let date = DateTime::from_timestamp(win.won_ts, 0)
+                .map(|date| date.format("%d.%m.%Y").to_string())
+                .unwrap_or_default();
+            format!(
+                "{} võitis {} :potato: ({}, {})",
+                mention, win.amount, win.game, date
+            )
+        }
+        None => "Keegi pole veel jackpotti võitnud.".to_string(),
+    };
+
+    let embed = serenity::CreateEmbed::new()
+        .title(":slot_machine: Jackpot")
+        .description(format!("Potis on **{}** :potato:.", pool))
+        .field("Viimane võitja", last_win, false)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "{}% igast kaotatud panusest läheb potti, iga panus võidab poti tõenäosusega {}%.",
+            settings.contribution * 100.0,
+            settings.win_chance * 100.0
+        )))
+        .color(serenity::Color::GOLD);
+
+    let reply = poise::CreateReply::default().embed(embed);
+
+    if let Err(why) = ctx.send(reply).await {
+        error!("Error sending message: {why:?}");
+    }
+
+    Ok(())
+}
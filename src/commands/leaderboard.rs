@@ -1,8 +1,7 @@
-use chrono::Utc;
 use poise::serenity_prelude as serenity;
 use tracing::error;
 
-use crate::database::players::{get_idle_status, load_leaderboard, IdleStatus};
+use crate::database::players::{get_idle_status, rank_players, IdleStatus};
 use crate::internal::data::{Context, Error};
 
 /// Displays leaderboard.
@@ -13,9 +12,9 @@ use crate::internal::data::{Context, Error};
     category = "Potato Game"
 )]
 pub async fn leaderboard(ctx: Context<'_>) -> Result<(), Error> {
-    let leaderboard = load_leaderboard(&ctx.data().database).await;
+    let leaderboard = rank_players(&ctx.data().players.load_players_by_balance().await);
 
-    let current_ts = Utc::now().timestamp();
+    let current_ts = ctx.data().clock.timestamp();
     let mut display_names = Vec::new();
 
     for &(user_id, _, _, _, idle_ts) in leaderboard.iter() {
@@ -0,0 +1,264 @@
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::database::limits::{
+    find_player_limits, find_player_loss, save_player_limits, PlayerLimits,
+};
+use crate::internal::data::{Context, Error};
+use crate::internal::discord;
+use crate::internal::errors::PotatoGameError;
+use crate::internal::shared;
+
+#[derive(Debug, PartialEq, poise::ChoiceParameter)]
+enum LimitKind {
+    #[name = "daily-loss"]
+    DailyLoss,
+}
+
+async fn load_limits(user_id: &String, now: i64, ctx: &Context<'_>) -> PlayerLimits {
+    let mut limits = find_player_limits(user_id, &ctx.data().database)
+        .await
+        .unwrap_or_else(|| PlayerLimits::new(user_id));
+    limits.apply_due_changes(now);
+    limits
+}
+
+/// Shows the limits you have set on your own playing.
+///
+/// Usage: `!limits`
+///
+/// Making the limits stricter applies right away, loosening them only after
+/// a delay.
+#[poise::command(
+    prefix_command,
+    category = "Potato Game",
+    broadcast_typing,
+    subcommands("set")
+)]
+pub async fn limits(ctx: Context<'_>) -> Result<(), Error> {
+    let user_id = ctx.author().id.to_string();
+    let now = ctx.data().clock.timestamp();
+
+    let limits = load_limits(&user_id, now, &ctx).await;
+
+    let daily_loss = if limits.daily_loss_limit > 0 {
+        let loss = find_player_loss(&user_id, ctx.data().clock.day(), &ctx.data().database).await;
+        format!(
+            "{} :potato:, täna kaotatud {} :potato:",
+            limits.daily_loss_limit,
+            loss.max(0)
+        )
+    } else {
+        "puudub".to_string()
+    };
+
+    let exclusion = if limits.is_excluded(now) {
+        format!(
+            "veel {}",
+            shared::format_duration(limits.excluded_until_ts - now)
+        )
+    } else {
+        "puudub".to_string()
+    };
+
+    let mut pending = vec![];
+    if let (Some(limit), Some(change_ts)) = (
+        limits.pending_daily_loss_limit,
+        limits.daily_loss_limit_change_ts,
+    ) {
+        pending.push(format!(
+            "Päevane kaotuslimiit muutub {} pärast: {}",
+            shared::format_duration(change_ts - now),
+            describe_limit(limit)
+        ));
+    }
+    if let (Some(until_ts), Some(change_ts)) = (
+        limits.pending_excluded_until_ts,
+        limits.excluded_until_change_ts,
+    ) {
+        pending.push(format!(
+            "Väljaarvamine lüheneb {} pärast: {}",
+            shared::format_duration(change_ts - now),
+            describe_exclusion(until_ts, change_ts)
+        ));
+    }
+
+    let mut embed = serenity::CreateEmbed::new()
+        .title(":shield: Limiidid")
+        .field("Päevane kaotuslimiit", daily_loss, false)
+        .field("Väljaarvamine", exclusion, false)
+        .color(serenity::Color::DARK_BLUE);
+
+    if !pending.is_empty() {
+        embed = embed.field("Ootel muudatused", pending.join("\n"), false);
+    }
+
+    let reply = poise::CreateReply::default().embed(embed);
+
+    if let Err(why) = ctx.send(reply).await {
+        error!("Error sending message: {why:?}");
+    }
+
+    Ok(())
+}
+
+/// Sets a limit on your own playing, 0 removes the limit.
+///
+/// Usage: `!limits set daily-loss <amount>`
+///
+/// Example: `!limits set daily-loss 10000`
+#[poise::command(prefix_command, broadcast_typing)]
+pub async fn set(
+    ctx: Context<'_>,
+    #[description = "The limit to set"] _kind: LimitKind,
+    #[description = "The new limit, 0 for no limit"] amount: i64,
+) -> Result<(), Error> {
+    let user_mention = serenity::Mention::from(ctx.author().id);
+
+    if amount < 0 {
+        discord::failure_message(
+            &ctx,
+            format!("{} Limiit ei saa olla negatiivne.", user_mention),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let user_id = ctx.author().id.to_string();
+    let now = ctx.data().clock.timestamp();
+
+    let mut limits = load_limits(&user_id, now, &ctx).await;
+
+    let current = limits.daily_loss_limit;
+    let is_stricter = amount > 0 && (current == 0 || amount < current);
+
+    let message = if amount == current {
+        limits.pending_daily_loss_limit = None;
+        limits.daily_loss_limit_change_ts = None;
+        format!(
+            "{} Päevane kaotuslimiit jääb samaks: {}.",
+            user_mention,
+            describe_limit(amount)
+        )
+    } else if is_stricter {
+        limits.daily_loss_limit = amount;
+        limits.pending_daily_loss_limit = None;
+        limits.daily_loss_limit_change_ts = None;
+        format!(
+            "{} Päevane kaotuslimiit on nüüd {}.",
+            user_mention,
+            describe_limit(amount)
+        )
+    } else {
+        let delay = ctx.data().limits.change_delay.as_secs() as i64;
+        limits.pending_daily_loss_limit = Some(amount);
+        limits.daily_loss_limit_change_ts = Some(now + delay);
+        format!(
+            "{} Päevane kaotuslimiit muutub {} pärast: {}.",
+            user_mention,
+            shared::format_duration(delay),
+            describe_limit(amount)
+        )
+    };
+
+    if !save_player_limits(&mut limits, &ctx.data().database).await {
+        return Err(Box::new(PotatoGameError::ConcurrencyError));
+    }
+
+    discord::success_message(&ctx, message).await;
+
+    Ok(())
+}
+
+/// Excludes yourself from all betting for a while.
+///
+/// Usage: `!selfexclude <duration>`
+///
+/// The exclusion can be made longer right away, shortening it only takes
+/// effect after a delay.
+///
+/// Example: `!selfexclude 7d`
+#[poise::command(prefix_command, category = "Potato Game", broadcast_typing)]
+pub async fn selfexclude(
+    ctx: Context<'_>,
+    #[description = "How long to stay away from betting"] duration: String,
+) -> Result<(), Error> {
+    let user_mention = serenity::Mention::from(ctx.author().id);
+    let settings = &ctx.data().limits;
+
+    let duration_secs = match duration_str::parse(&duration) {
+        Ok(duration) if duration <= settings.max_self_exclusion => duration.as_secs() as i64,
+        Ok(_) => {
+            discord::failure_message(
+                &ctx,
+                format!(
+                    "{} Pikim väljaarvamine on {}.",
+                    user_mention,
+                    shared::format_duration(settings.max_self_exclusion.as_secs() as i64)
+                ),
+            )
+            .await;
+            return Ok(());
+        }
+        Err(_) => {
+            discord::failure_message(&ctx, format!("{} Ei saa aru, kui kauaks.", user_mention))
+                .await;
+            return Ok(());
+        }
+    };
+
+    let user_id = ctx.author().id.to_string();
+    let now = ctx.data().clock.timestamp();
+    let until_ts = now + duration_secs;
+
+    let mut limits = load_limits(&user_id, now, &ctx).await;
+
+    let message = if until_ts >= limits.excluded_until_ts {
+        limits.excluded_until_ts = until_ts;
+        limits.pending_excluded_until_ts = None;
+        limits.excluded_until_change_ts = None;
+        format!(
+            "{} Oled end panustamisest välja arvanud {} ajaks.",
+            user_mention,
+            shared::format_duration(duration_secs)
+        )
+    } else {
+        let change_ts = now + settings.change_delay.as_secs() as i64;
+        limits.pending_excluded_until_ts = Some(until_ts);
+        limits.excluded_until_change_ts = Some(change_ts);
+        format!(
+            "{} Väljaarvamine lüheneb {} pärast: {}.",
+            user_mention,
+            shared::format_duration(change_ts - now),
+            describe_exclusion(until_ts, change_ts)
+        )
+    };
+
+    if !save_player_limits(&mut limits, &ctx.data().database).await {
+        return Err(Box::new(PotatoGameError::ConcurrencyError));
+    }
+
+    discord::success_message(&ctx, message).await;
+
+    Ok(())
+}
+
+fn describe_limit(limit: i64) -> String {
+    if limit > 0 {
+        format!("{} :potato:", limit)
+    } else {
+        "limiidita".to_string()
+    }
+}
+
+/// Exclusion left once the pending shortening applies.
+fn describe_exclusion(until_ts: i64, change_ts: i64) -> String {
+    if until_ts > change_ts {
+        format!(
+            "siis veel {}",
+            shared::format_duration(until_ts - change_ts)
+        )
+    } else {
+        "lõpeb kohe".to_string()
+    }
+}
@@ -0,0 +1,187 @@
+use poise::serenity_prelude as serenity;
+use std::str::FromStr;
+use tracing::warn;
+
+use crate::database::events::TARGET_MINES;
+use crate::database::mines::{
+    find_active_mines_game, save_mines_game, start_mines_game, MinesGame, STATUS_ACTIVE,
+};
+use crate::database::players::IdleStatus;
+use crate::internal::achievements::{self, GameEvent};
+use crate::internal::betting::BetAmount;
+use crate::internal::cooldowns;
+use crate::internal::data::{Context, Error};
+use crate::internal::discord;
+use crate::internal::errors::PotatoGameError;
+use crate::internal::events;
+use crate::internal::house;
+use crate::internal::jackpot;
+use crate::internal::limits;
+use crate::internal::metrics;
+use crate::internal::mines;
+use crate::internal::shared;
+
+/// Mines - open tiles without stepping on a mine.
+///
+/// Usage: `!mines all|half|some|<amount>[%] <mines>`
+///
+/// Every safe tile raises the multiplier, the more mines the faster it grows.
+/// Take your winnings with `!mines cashout` before you hit a mine.
+///
+/// Example: `!mines 500 3`
+/// Example: `!mines cashout`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    category = "Potato Game",
+    subcommands("cashout")
+)]
+pub async fn mines(
+    ctx: Context<'_>,
+    #[description = "The amount you want to bet"] bet_amount_str: String,
+    #[description = "Number of mines on the grid"] mines: i64,
+) -> Result<(), Error> {
+    let user_id = ctx.author().id.to_string();
+    let user_mention = serenity::Mention::from(ctx.author().id);
+    let database = &ctx.data().database;
+    let settings = &ctx.data().mines;
+
+    let Ok(bet_amount) = BetAmount::from_str(&bet_amount_str) else {
+        cooldowns::refuse(&ctx).await;
+        let reply = poise::CreateReply::default().content("Ei saa aru, mida sa teha tahad!");
+        ctx.send(reply).await?;
+        return Ok(());
+    };
+
+    if !(1..mines::TILES).contains(&mines) {
+        discord::failure_message(
+            &ctx,
+            format!(
+                "{} Miine peab olema 1 kuni {}.",
+                user_mention,
+                mines::TILES - 1
+            ),
+        )
+        .await;
+        return Ok(());
+    }
+
+    if find_active_mines_game(&user_id, database).await.is_some() {
+        discord::failure_message(
+            &ctx,
+            format!(
+                "{} Sul on juba pooleli mäng, lõpeta see enne uue alustamist.",
+                user_mention
+            ),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let mut player = match ctx.data().players.find_player(&user_id).await {
+        Some(player) => player,
+        None => shared::create_new_player(&ctx, &ctx.author().id).await?,
+    };
+
+    let Some(amount) = shared::stake(&ctx, &bet_amount, &player).await else {
+        return Ok(());
+    };
+
+    if !limits::allows_bet(&ctx, amount).await {
+        return Ok(());
+    }
+
+    // The win grows with every tile, cashing out pays at most what the house
+    // can cover now.
+    let now = ctx.data().clock.timestamp();
+    let boost = events::multiplier(TARGET_MINES, now, database).await;
+    let first_win = mines::first_win(amount, mines, settings.house_edge, boost);
+    let Some(max_win) = house::win_cap(&ctx, first_win).await else {
+        return Ok(());
+    };
+
+    let was_dead = matches!(player.idle_status(now), IdleStatus::Dead);
+
+    player.balance -= amount;
+    player.idle_since_ts = now;
+
+    let mut game = MinesGame {
+        id: 0,
+        discord_user_id: user_id.clone(),
+        channel_id: ctx.channel_id().to_string(),
+        message_id: None,
+        amount,
+        mines,
+        mine_tiles: mines::encode_tiles(&mines::place_mines(mines)),
+        revealed_tiles: String::new(),
+        status: STATUS_ACTIVE.into(),
+        payout: None,
+        max_payout: Some(amount + max_win),
+        boost,
+        created_ts: now,
+        updated_ts: now,
+        version: 0,
+    };
+
+    if !start_mines_game(&mut player, &mut game, ctx.data().clock.day(), database).await {
+        // Another game was started or the player changed at the same time.
+        return Err(Box::new(PotatoGameError::ConcurrencyError));
+    }
+
+    metrics::bet("mines", amount);
+
+    let reply = poise::CreateReply::default()
+        .embed(mines::game_embed(&game, settings))
+        .components(mines::game_components(&game));
+    let message = ctx.send(reply).await?.into_message().await?;
+
+    game.message_id = Some(message.id.to_string());
+    if !save_mines_game(&mut game, database).await {
+        warn!("Could not save message of mines game {}", game.id);
+    }
+
+    if was_dead {
+        achievements::record(&ctx, ctx.author().id, &[GameEvent::ReturnedFromDead]).await;
+    }
+
+    Ok(())
+}
+
+/// Takes out your winnings from the current game.
+#[poise::command(prefix_command, broadcast_typing)]
+pub async fn cashout(ctx: Context<'_>) -> Result<(), Error> {
+    let user_id = ctx.author().id.to_string();
+    let user_mention = serenity::Mention::from(ctx.author().id);
+    let database = &ctx.data().database;
+    let settings = &ctx.data().mines;
+
+    let Some(game) = find_active_mines_game(&user_id, database).await else {
+        discord::failure_message(
+            &ctx,
+            format!("{} Sul pole pooleliolevat mängu.", user_mention),
+        )
+        .await;
+        return Ok(());
+    };
+
+    match mines::cash_out(database, settings, game, ctx.data().clock.as_ref()).await {
+        Ok(game) => {
+            mines::update_game_message(ctx.serenity_context(), &game, settings).await;
+            discord::success_message(
+                &ctx,
+                format!(
+                    "{} võttis välja {} :potato:.",
+                    user_mention,
+                    game.payout.unwrap_or_default()
+                ),
+            )
+            .await;
+            jackpot::roll(&ctx, 0, "mines").await;
+        }
+        Err(message) => {
+            discord::failure_message(&ctx, format!("{} {}", user_mention, message)).await;
+        }
+    }
+
+    Ok(())
+}
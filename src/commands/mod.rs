@@ -1,6 +1,22 @@
+pub mod achievements;
+pub mod backup;
 pub mod balance;
+pub mod bet;
+pub mod craps;
+pub mod crash;
+pub mod dice;
+pub mod event;
+pub mod export;
+pub mod feed;
 pub mod flip;
 pub mod give;
 pub mod help;
+pub mod house;
+pub mod jackpot;
 pub mod leaderboard;
+pub mod limits;
+pub mod mines;
 pub mod ping;
+pub mod poker;
+pub mod race;
+pub mod roles;
@@ -0,0 +1,229 @@
+use poise::serenity_prelude as serenity;
+use tracing::warn;
+
+use crate::database::poker::{
+    find_open_poker_table, open_poker_table, save_poker_table, PokerSeat, PokerTable,
+    STATUS_WAITING,
+};
+use crate::internal::data::{Context, Error};
+use crate::internal::discord;
+use crate::internal::errors::PotatoGameError;
+use crate::internal::limits;
+use crate::internal::metrics;
+use crate::internal::poker::{self, TableCommand};
+use crate::internal::shared;
+
+/// Texas Hold'em poker table.
+///
+/// Usage: `!poker open|join|leave|deal|raise|close`
+///
+/// Example: `!poker open 1000`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    category = "Potato Game",
+    guild_only,
+    subcommands("open", "join", "leave", "deal", "raise", "close")
+)]
+pub async fn poker(ctx: Context<'_>) -> Result<(), Error> {
+    discord::failure_message(&ctx, "Kasuta `!poker open <sisseost>`, et uus laud avada.").await;
+    Ok(())
+}
+
+/// Opens a new poker table in this channel.
+///
+/// Usage: `!poker open <buy-in>`
+///
+/// Example: `!poker open 1000`
+#[poise::command(prefix_command, broadcast_typing, guild_only)]
+pub async fn open(
+    ctx: Context<'_>,
+    #[description = "Potatoes every player brings to the table"] buy_in: i64,
+) -> Result<(), Error> {
+    let user_id = ctx.author().id.to_string();
+    let user_mention = serenity::Mention::from(ctx.author().id);
+    let database = &ctx.data().database;
+
+    if buy_in < 100 {
+        discord::failure_message(
+            &ctx,
+            format!("{} Minimaalne sisseost on 100 :potato:.", user_mention),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let channel_id = ctx.channel_id().to_string();
+    if find_open_poker_table(&channel_id, database).await.is_some() {
+        discord::failure_message(
+            &ctx,
+            format!("{} Selles kanalis on juba pokkerilaud.", user_mention),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let mut player = match ctx.data().players.find_player(&user_id).await {
+        Some(player) => player,
+        None => shared::create_new_player(&ctx, &ctx.author().id).await?,
+    };
+
+    if player.balance < buy_in {
+        discord::failure_message(
+            &ctx,
+            format!("{} Sul pole sisseostuks piisavalt :potato:.", user_mention),
+        )
+        .await;
+        return Ok(());
+    }
+
+    if !limits::allows_bet(&ctx, buy_in).await {
+        return Ok(());
+    }
+
+    let big_blind = (buy_in / 100).max(2);
+    let mut table = PokerTable {
+        id: 0,
+        channel_id,
+        message_id: None,
+        owner_id: user_id.clone(),
+        buy_in,
+        small_blind: big_blind / 2,
+        big_blind,
+        status: STATUS_WAITING.into(),
+        hand_no: 0,
+        street: String::new(),
+        dealer_seat: 0,
+        to_act: None,
+        current_bet: 0,
+        min_raise: 0,
+        deck: String::new(),
+        board: String::new(),
+        last_result: None,
+        version: 1,
+    };
+
+    let now = ctx.data().clock.timestamp();
+
+    player.balance -= buy_in;
+    player.idle_since_ts = now;
+
+    let seats = vec![PokerSeat {
+        seat: 0,
+        discord_user_id: user_id.clone(),
+        stack: buy_in,
+        hole_cards: String::new(),
+        bet: 0,
+        committed: 0,
+        in_hand: false,
+        folded: false,
+        all_in: false,
+        acted: false,
+        leaving: false,
+    }];
+
+    if !open_poker_table(
+        &mut table,
+        &seats,
+        &mut player,
+        ctx.data().clock.day(),
+        database,
+    )
+    .await
+    {
+        if find_open_poker_table(&table.channel_id, database)
+            .await
+            .is_none()
+        {
+            return Err(Box::new(PotatoGameError::ConcurrencyError));
+        }
+        discord::failure_message(
+            &ctx,
+            format!("{} Selles kanalis on juba pokkerilaud.", user_mention),
+        )
+        .await;
+        return Ok(());
+    }
+
+    metrics::bet("poker", buy_in);
+
+    let reply = poise::CreateReply::default()
+        .embed(poker::table_embed(&table, &seats))
+        .components(poker::table_components(&table, &seats));
+    let message = ctx.send(reply).await?.into_message().await?;
+
+    table.message_id = Some(message.id.to_string());
+    if !save_poker_table(&mut table, &seats, database).await {
+        warn!("Could not save poker table {}", table.id);
+        return Err(Box::new(PotatoGameError::ConcurrencyError));
+    }
+
+    Ok(())
+}
+
+/// Takes a seat at the poker table.
+#[poise::command(prefix_command, broadcast_typing, guild_only)]
+pub async fn join(ctx: Context<'_>) -> Result<(), Error> {
+    run(ctx, TableCommand::Join, "istus lauda").await
+}
+
+/// Leaves the poker table, chips are returned once the hand is over.
+#[poise::command(prefix_command, broadcast_typing, guild_only)]
+pub async fn leave(ctx: Context<'_>) -> Result<(), Error> {
+    run(ctx, TableCommand::Leave, "lahkus lauast").await
+}
+
+/// Deals the next hand.
+#[poise::command(prefix_command, broadcast_typing, guild_only)]
+pub async fn deal(ctx: Context<'_>) -> Result<(), Error> {
+    run(ctx, TableCommand::Deal, "jagas uue käe").await
+}
+
+/// Raises the bet to the given amount.
+///
+/// Usage: `!poker raise <amount>`
+///
+/// Example: `!poker raise 120`
+#[poise::command(prefix_command, broadcast_typing, guild_only)]
+pub async fn raise(
+    ctx: Context<'_>,
+    #[description = "Total bet you want to raise to"] amount: i64,
+) -> Result<(), Error> {
+    run(ctx, TableCommand::Raise(Some(amount)), "tõstis").await
+}
+
+/// Closes the poker table and returns all chips.
+#[poise::command(prefix_command, broadcast_typing, guild_only)]
+pub async fn close(ctx: Context<'_>) -> Result<(), Error> {
+    run(ctx, TableCommand::Close, "sulges laua").await
+}
+
+async fn run(ctx: Context<'_>, command: TableCommand, action: &str) -> Result<(), Error> {
+    let user_mention = serenity::Mention::from(ctx.author().id);
+
+    let Some(table) =
+        find_open_poker_table(&ctx.channel_id().to_string(), &ctx.data().database).await
+    else {
+        discord::failure_message(
+            &ctx,
+            format!("{} Selles kanalis pole pokkerilauda.", user_mention),
+        )
+        .await;
+        return Ok(());
+    };
+
+    match poker::execute(ctx.data(), table.id, ctx.author().id, command).await {
+        Ok(update) => {
+            poker::update_table_message(ctx.serenity_context(), &update.table, &update.seats).await;
+            if update.hand_started {
+                poker::send_hole_cards(ctx.serenity_context(), &update.table, &update.seats).await;
+            }
+            discord::success_message(&ctx, format!("{} {}.", user_mention, action)).await;
+        }
+        Err(message) => {
+            discord::failure_message(&ctx, format!("{} {}", user_mention, message)).await;
+        }
+    }
+
+    Ok(())
+}
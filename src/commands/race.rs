@@ -0,0 +1,232 @@
+use poise::serenity_prelude as serenity;
+use std::str::FromStr;
+use tracing::{error, warn};
+
+use crate::database::players::IdleStatus;
+use crate::database::races::{
+    create_race, find_open_race, load_race_bets, load_racers, place_race_bet, update_race, Race,
+    RaceBet, STATUS_BETTING,
+};
+use crate::internal::achievements::{self, GameEvent};
+use crate::internal::betting::BetAmount;
+use crate::internal::cooldowns;
+use crate::internal::data::{Context, Error};
+use crate::internal::discord;
+use crate::internal::errors::PotatoGameError;
+use crate::internal::limits;
+use crate::internal::metrics;
+use crate::internal::race::{self, RaceHandles};
+use crate::internal::shared;
+
+/// Potato race - bet on the racer you think crosses the finish line first.
+///
+/// Usage: `!race [bet <lane> all|half|some|<amount>[%]]`
+///
+/// `!race` lines up the racers and opens the betting. The pool minus the
+/// house take is split between everyone who bet on the winner.
+///
+/// Example: `!race`
+/// Example: `!race bet 3 500`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    category = "Potato Game",
+    subcommands("bet")
+)]
+pub async fn race(ctx: Context<'_>) -> Result<(), Error> {
+    let user_mention = serenity::Mention::from(ctx.author().id);
+    let database = &ctx.data().database;
+    let settings = &ctx.data().race;
+
+    if find_open_race(&ctx.channel_id().to_string(), database)
+        .await
+        .is_some()
+    {
+        discord::failure_message(
+            &ctx,
+            format!("{} Võistlus juba käib, oota järgmist.", user_mention),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let racers = race::new_racers(settings.racers);
+
+    let mut race = Race {
+        id: 0,
+        channel_id: ctx.channel_id().to_string(),
+        message_id: None,
+        status: STATUS_BETTING.into(),
+        created_ts: ctx.data().clock.timestamp(),
+        finished_ts: None,
+        winner_lane: None,
+        pool: 0,
+        house_take: 0,
+    };
+
+    if !create_race(&mut race, &racers, database).await {
+        // Somebody else opened a race in this channel at the same time.
+        discord::failure_message(
+            &ctx,
+            format!("{} Võistlus juba käib, oota järgmist.", user_mention),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let reply =
+        poise::CreateReply::default().embed(race::betting_embed(&race, &racers, &[], settings));
+    let message = ctx.send(reply).await?.into_message().await?;
+
+    race.message_id = Some(message.id.to_string());
+    if !update_race(&race, database).await {
+        warn!("Could not save message of race {}", race.id);
+    }
+
+    tokio::spawn(race::run_race(
+        ctx.serenity_context().clone(),
+        RaceHandles::from_data(ctx.data()),
+        settings.clone(),
+        race,
+        message,
+    ));
+
+    Ok(())
+}
+
+/// Bets on the racer running on the given lane.
+#[poise::command(prefix_command, broadcast_typing)]
+pub async fn bet(
+    ctx: Context<'_>,
+    #[description = "The lane of the racer"] lane: i64,
+    #[description = "The amount you want to bet"] bet_amount_str: String,
+) -> Result<(), Error> {
+    let user_id = ctx.author().id.to_string();
+    let user_mention = serenity::Mention::from(ctx.author().id);
+    let database = &ctx.data().database;
+
+    let Ok(bet_amount) = BetAmount::from_str(&bet_amount_str) else {
+        cooldowns::refuse(&ctx).await;
+        let reply = poise::CreateReply::default().content("Ei saa aru, mida sa teha tahad!");
+        ctx.send(reply).await?;
+        return Ok(());
+    };
+
+    let Some(race) = find_open_race(&ctx.channel_id().to_string(), database).await else {
+        discord::failure_message(
+            &ctx,
+            format!(
+                "{} Praegu pole ühtegi võistlust. Alusta käsuga `!race`.",
+                user_mention
+            ),
+        )
+        .await;
+        return Ok(());
+    };
+
+    if race.status != STATUS_BETTING {
+        discord::failure_message(
+            &ctx,
+            format!(
+                "{} Võistlus juba käib, panuseid enam ei võeta.",
+                user_mention
+            ),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let racers = load_racers(race.id, database).await;
+    let Some(racer) = racers.iter().find(|racer| racer.lane == lane) else {
+        discord::failure_message(
+            &ctx,
+            format!(
+                "{} Sellist rada pole, vali 1 kuni {}.",
+                user_mention,
+                racers.len()
+            ),
+        )
+        .await;
+        return Ok(());
+    };
+
+    let mut player = match ctx.data().players.find_player(&user_id).await {
+        Some(player) => player,
+        None => shared::create_new_player(&ctx, &ctx.author().id).await?,
+    };
+
+    let Some(amount) = shared::stake(&ctx, &bet_amount, &player).await else {
+        return Ok(());
+    };
+
+    if !limits::allows_bet(&ctx, amount).await {
+        return Ok(());
+    }
+
+    let now = ctx.data().clock.timestamp();
+    let was_dead = matches!(player.idle_status(now), IdleStatus::Dead);
+
+    player.balance -= amount;
+    player.idle_since_ts = now;
+
+    let bet = RaceBet {
+        discord_user_id: user_id.clone(),
+        lane,
+        amount,
+        payout: None,
+    };
+
+    if !place_race_bet(&mut player, race.id, &bet, ctx.data().clock.day(), database).await {
+        if find_open_race(&race.channel_id, database)
+            .await
+            .is_some_and(|open| open.id == race.id && open.status == STATUS_BETTING)
+        {
+            return Err(Box::new(PotatoGameError::ConcurrencyError));
+        }
+        discord::failure_message(
+            &ctx,
+            format!("{} Panuste tegemise aeg sai just läbi.", user_mention),
+        )
+        .await;
+        return Ok(());
+    }
+
+    metrics::bet("race", amount);
+
+    discord::success_message(
+        &ctx,
+        format!(
+            "{} panustas {} :potato: jooksjale {}.",
+            user_mention, amount, racer.name
+        ),
+    )
+    .await;
+
+    if was_dead {
+        achievements::record(&ctx, ctx.author().id, &[GameEvent::ReturnedFromDead]).await;
+    }
+
+    let (Ok(channel_id), Some(Ok(message_id))) = (
+        race.channel_id.parse::<u64>(),
+        race.message_id.as_ref().map(|id| id.parse::<u64>()),
+    ) else {
+        return Ok(());
+    };
+
+    let bets = load_race_bets(race.id, database).await;
+    let edit = serenity::EditMessage::new().embed(race::betting_embed(
+        &race,
+        &racers,
+        &bets,
+        &ctx.data().race,
+    ));
+
+    if let Err(why) = serenity::ChannelId::new(channel_id)
+        .edit_message(ctx, serenity::MessageId::new(message_id), edit)
+        .await
+    {
+        error!("Error editing message: {why:?}");
+    }
+
+    Ok(())
+}
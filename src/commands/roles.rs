@@ -0,0 +1,345 @@
+use poise::serenity_prelude as serenity;
+use tracing::{error, warn};
+
+use crate::database::players::IdleStatus;
+use crate::database::roles::{
+    buy_role, find_active_role_purchase, find_purchasable_role, load_purchasable_roles,
+    refund_role_purchase, remove_purchasable_role, save_purchasable_role, PurchasableRole,
+    RolePurchase,
+};
+use crate::internal::achievements::{self, GameEvent};
+use crate::internal::data::{Context, Error};
+use crate::internal::discord;
+use crate::internal::errors::PotatoGameError;
+use crate::internal::shared;
+
+/// Roles that can be bought with :potato:.
+///
+/// Usage: `!role [list|buy|add|remove]`
+///
+/// Example: `!role buy @Potato Baron`
+#[poise::command(
+    prefix_command,
+    aliases("roles"),
+    broadcast_typing,
+    category = "Potato Game",
+    guild_only,
+    subcommands("list", "buy", "add", "remove")
+)]
+pub async fn role(ctx: Context<'_>) -> Result<(), Error> {
+    show_roles(ctx).await
+}
+
+/// Lists roles that can be bought with :potato:.
+#[poise::command(prefix_command, broadcast_typing, guild_only)]
+pub async fn list(ctx: Context<'_>) -> Result<(), Error> {
+    show_roles(ctx).await
+}
+
+/// Buys a role with :potato:.
+///
+/// Usage: `!role buy <role>`
+///
+/// Example: `!role buy @Potato Baron`
+#[poise::command(prefix_command, broadcast_typing, guild_only)]
+pub async fn buy(
+    ctx: Context<'_>,
+    #[description = "The role you want to buy"]
+    #[rest]
+    role: serenity::Role,
+) -> Result<(), Error> {
+    let guild_id = role.guild_id.to_string();
+    let role_id = role.id.to_string();
+    let user_id = ctx.author().id.to_string();
+    let user_mention = serenity::Mention::from(ctx.author().id);
+    let role_mention = serenity::Mention::from(role.id);
+    let database = &ctx.data().database;
+
+    let Some(offer) = find_purchasable_role(&guild_id, &role_id, database).await else {
+        discord::failure_message(&ctx, format!("Rolli {} ei saa osta.", role_mention)).await;
+        return Ok(());
+    };
+
+    let now = ctx.data().clock.timestamp();
+
+    let existing = find_active_role_purchase(&guild_id, &role_id, &user_id, now, database).await;
+    if let Some(RolePurchase {
+        expires_ts: None, ..
+    }) = existing
+    {
+        discord::failure_message(
+            &ctx,
+            format!("{} Sul on roll {} juba olemas.", user_mention, role_mention),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let mut player = match ctx.data().players.find_player(&user_id).await {
+        Some(player) => player,
+        None => shared::create_new_player(&ctx, &ctx.author().id).await?,
+    };
+
+    if player.balance < offer.price {
+        discord::failure_message(
+            &ctx,
+            format!(
+                "{} Sul pole rolli {} ostmiseks piisavalt :potato:.",
+                user_mention, role_mention
+            ),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let was_dead = matches!(player.idle_status(now), IdleStatus::Dead);
+
+    player.balance -= offer.price;
+    player.idle_since_ts = now;
+
+    if let Some(mut purchase) = existing {
+        purchase.expires_ts = offer
+            .duration_secs
+            .map(|duration| purchase.expires_ts.unwrap_or(now).max(now) + duration);
+
+        if !buy_role(
+            &mut player,
+            &mut purchase,
+            offer.price,
+            ctx.data().clock.day(),
+            database,
+        )
+        .await
+        {
+            return Err(Box::new(PotatoGameError::ConcurrencyError));
+        }
+
+        if was_dead {
+            achievements::record(&ctx, ctx.author().id, &[GameEvent::ReturnedFromDead]).await;
+        }
+
+        discord::success_message(
+            &ctx,
+            format!(
+                "{} pikendas rolli {} {} :potato: eest.",
+                user_mention, role_mention, offer.price
+            ),
+        )
+        .await;
+        return Ok(());
+    }
+
+    let mut purchase = RolePurchase {
+        id: 0,
+        guild_id,
+        role_id,
+        discord_user_id: user_id.clone(),
+        price: offer.price,
+        purchased_ts: now,
+        expires_ts: offer.duration_secs.map(|duration| now + duration),
+    };
+
+    if !buy_role(
+        &mut player,
+        &mut purchase,
+        offer.price,
+        ctx.data().clock.day(),
+        database,
+    )
+    .await
+    {
+        return Err(Box::new(PotatoGameError::ConcurrencyError));
+    }
+
+    if was_dead {
+        achievements::record(&ctx, ctx.author().id, &[GameEvent::ReturnedFromDead]).await;
+    }
+
+    let result = ctx
+        .http()
+        .add_member_role(
+            role.guild_id,
+            ctx.author().id,
+            role.id,
+            Some("Role purchased with potatoes"),
+        )
+        .await;
+
+    if let Err(why) = result {
+        error!("Could not add role {}: {why:?}", role.id);
+
+        if !refund_role_purchase(&purchase, ctx.data().clock.day(), database).await {
+            warn!(
+                "Could not refund {} potatoes to user {}",
+                purchase.price, user_id
+            );
+        }
+
+        let reason = match why {
+            serenity::Error::Http(serenity::HttpError::UnsuccessfulRequest(response))
+                if response.status_code.as_u16() == 403 =>
+            {
+                format!("Mul pole õigust rolli {} jagada.", role_mention)
+            }
+            _ => format!("Rolli {} andmine ebaõnnestus.", role_mention),
+        };
+
+        discord::failure_message(
+            &ctx,
+            format!(
+                "{} {} Said oma {} :potato: tagasi.",
+                user_mention, reason, offer.price
+            ),
+        )
+        .await;
+        return Ok(());
+    }
+
+    discord::success_message(
+        &ctx,
+        format!(
+            "{} ostis rolli {} {} :potato: eest.",
+            user_mention, role_mention, offer.price
+        ),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Offers a role for sale.
+///
+/// Usage: `!role add <role> <price> [duration]`
+///
+/// Example: `!role add @Potato Baron 50000 7d`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    guild_only,
+    required_permissions = "MANAGE_ROLES"
+)]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "The role to sell"] role: serenity::Role,
+    #[description = "The price of the role"] price: i64,
+    #[description = "How long the role is kept, forever if missing"] duration: Option<String>,
+) -> Result<(), Error> {
+    if price < 1 {
+        discord::failure_message(&ctx, "Rolli hind peab olema vähemalt 1 :potato:.").await;
+        return Ok(());
+    }
+
+    let duration_secs = match duration.as_deref().map(duration_str::parse) {
+        None => None,
+        Some(Ok(duration)) if duration.as_secs() > 0 => Some(duration.as_secs() as i64),
+        Some(_) => {
+            discord::failure_message(&ctx, "Ei saa aru, kui kauaks roll antakse.").await;
+            return Ok(());
+        }
+    };
+
+    let offer = PurchasableRole {
+        guild_id: role.guild_id.to_string(),
+        role_id: role.id.to_string(),
+        price,
+        duration_secs,
+    };
+
+    if !save_purchasable_role(&offer, &ctx.data().database).await {
+        return Err(Box::new(PotatoGameError::ConcurrencyError));
+    }
+
+    discord::success_message(
+        &ctx,
+        format!(
+            "Rolli {} saab nüüd osta {} :potato: eest ({}).",
+            serenity::Mention::from(role.id),
+            price,
+            describe_duration(offer.duration_secs)
+        ),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Removes a role from sale.
+///
+/// Usage: `!role remove <role>`
+#[poise::command(
+    prefix_command,
+    broadcast_typing,
+    guild_only,
+    required_permissions = "MANAGE_ROLES"
+)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "The role to remove from sale"]
+    #[rest]
+    role: serenity::Role,
+) -> Result<(), Error> {
+    let removed = remove_purchasable_role(
+        &role.guild_id.to_string(),
+        &role.id.to_string(),
+        &ctx.data().database,
+    )
+    .await;
+
+    let role_mention = serenity::Mention::from(role.id);
+
+    if removed {
+        discord::success_message(&ctx, format!("Rolli {} enam osta ei saa.", role_mention)).await;
+    } else {
+        discord::failure_message(&ctx, format!("Rolli {} polnud müügis.", role_mention)).await;
+    }
+
+    Ok(())
+}
+
+async fn show_roles(ctx: Context<'_>) -> Result<(), Error> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(());
+    };
+
+    let offers = load_purchasable_roles(&guild_id.to_string(), &ctx.data().database).await;
+
+    if offers.is_empty() {
+        discord::failure_message(&ctx, "Hetkel pole ühtegi rolli müügis.").await;
+        return Ok(());
+    }
+
+    let description = offers
+        .iter()
+        .filter_map(|offer| {
+            offer.role_id.parse::<u64>().ok().map(|role_id| {
+                format!(
+                    "{} - {} :potato: ({})",
+                    serenity::Mention::from(serenity::RoleId::new(role_id)),
+                    offer.price,
+                    describe_duration(offer.duration_secs)
+                )
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let embed = serenity::CreateEmbed::new()
+        .title(":potato: Rollid")
+        .description(description)
+        .color(serenity::Color::DARK_GREEN);
+
+    let reply = poise::CreateReply::default().embed(embed);
+
+    if let Err(why) = ctx.send(reply).await {
+        error!("Error sending message: {why:?}");
+    }
+
+    Ok(())
+}
+
+fn describe_duration(duration_secs: Option<i64>) -> String {
+    match duration_secs {
+        Some(secs) => shared::format_duration(secs),
+        None => "igavesti".into(),
+    }
+}
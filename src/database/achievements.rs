@@ -0,0 +1,87 @@
+use sqlx::{Pool, Sqlite};
+use tracing::instrument;
+
+#[derive(Clone, Debug)]
+pub struct AchievementProgress {
+    pub discord_user_id: String,
+    pub flip_win_streak: i64,
+    pub total_given: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct UnlockedAchievement {
+    pub achievement: String,
+    pub unlocked_ts: i64,
+}
+
+#[instrument]
+pub async fn find_achievement_progress(
+    user_id: &String,
+    database: &Pool<Sqlite>,
+) -> AchievementProgress {
+    sqlx::query_as!(
+        AchievementProgress,
+        "SELECT discord_user_id, flip_win_streak, total_given FROM achievement_progress WHERE discord_user_id = ?",
+        user_id
+    )
+    .fetch_optional(database)
+    .await
+    .unwrap_or(None)
+    .unwrap_or_else(|| AchievementProgress {
+        discord_user_id: user_id.to_string(),
+        flip_win_streak: 0,
+        total_given: 0,
+    })
+}
+
+#[instrument]
+pub async fn save_achievement_progress(
+    progress: &AchievementProgress,
+    database: &Pool<Sqlite>,
+) -> bool {
+    sqlx::query!(
+        "INSERT INTO achievement_progress (discord_user_id, flip_win_streak, total_given) VALUES (?, ?, ?)
+         ON CONFLICT (discord_user_id) DO UPDATE SET flip_win_streak = excluded.flip_win_streak, total_given = excluded.total_given",
+        progress.discord_user_id,
+        progress.flip_win_streak,
+        progress.total_given
+    )
+    .execute(database)
+    .await
+    .is_ok()
+}
+
+/// Returns `true` only when the achievement was not unlocked before.
+#[instrument]
+pub async fn unlock_achievement(
+    user_id: &String,
+    achievement: &str,
+    ts: i64,
+    database: &Pool<Sqlite>,
+) -> bool {
+    sqlx::query!(
+        "INSERT OR IGNORE INTO player_achievements (discord_user_id, achievement, unlocked_ts) VALUES (?, ?, ?)",
+        user_id,
+        achievement,
+        ts
+    )
+    .execute(database)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+#[instrument]
+pub async fn load_achievements(
+    user_id: &String,
+    database: &Pool<Sqlite>,
+) -> Vec<UnlockedAchievement> {
+    sqlx::query_as!(
+        UnlockedAchievement,
+        "SELECT achievement, unlocked_ts FROM player_achievements WHERE discord_user_id = ? ORDER BY unlocked_ts",
+        user_id
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
@@ -0,0 +1,155 @@
+use libsqlite3_sys::{
+    sqlite3, sqlite3_backup_finish, sqlite3_backup_init, sqlite3_backup_step, sqlite3_errcode,
+    sqlite3_sleep, SQLITE_BUSY, SQLITE_DONE, SQLITE_LOCKED, SQLITE_OK,
+};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection};
+use sqlx::{ConnectOptions, Connection, Pool, Sqlite};
+use std::ffi::c_int;
+use std::ops::DerefMut;
+use std::path::Path;
+use tracing::{error, instrument};
+
+/// How many times a busy database is retried before giving up.
+const MAX_ATTEMPTS: u32 = 100;
+
+/// Copies the database to `path` with SQLite's online backup API, the bot
+/// keeps using the database meanwhile.
+#[instrument]
+pub async fn backup_database(database: &Pool<Sqlite>, path: &Path) -> bool {
+    let Ok(source) = database.acquire().await else {
+        return false;
+    };
+    let Some(destination) = open(path, true).await else {
+        return false;
+    };
+
+    let Some((copied, _, destination)) = copy(source, Box::new(destination)).await else {
+        return false;
+    };
+    let _ = destination.close().await;
+    copied
+}
+
+/// Overwrites the database with the backup at `path`.
+#[instrument]
+pub async fn restore_database(path: &Path, database: &Pool<Sqlite>) -> bool {
+    let Some(source) = open(path, false).await else {
+        return false;
+    };
+    let Ok(destination) = database.acquire().await else {
+        return false;
+    };
+
+    let Some((copied, source, _)) = copy(Box::new(source), destination).await else {
+        return false;
+    };
+    let _ = source.close().await;
+    copied
+}
+
+async fn open(path: &Path, create: bool) -> Option<SqliteConnection> {
+    SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(create)
+        .connect()
+        .await
+        .inspect_err(|why| error!("Could not open {}: {why:?}", path.display()))
+        .ok()
+}
+
+/// Runs the copy on a blocking thread, it waits on busy databases. The
+/// connections go along and come back when it is done, so they can't be
+/// used by anything else in the meantime.
+async fn copy<S, D>(source: S, destination: D) -> Option<(bool, S, D)>
+where
+    S: DerefMut<Target = SqliteConnection> + Send + 'static,
+    D: DerefMut<Target = SqliteConnection> + Send + 'static,
+{
+    let runtime = tokio::runtime::Handle::current();
+
+    let copied = tokio::task::spawn_blocking(move || {
+        let (mut source, mut destination) = (source, destination);
+        let code = runtime.block_on(async {
+            let (Ok(mut source), Ok(mut destination)) =
+                (source.lock_handle().await, destination.lock_handle().await)
+            else {
+                return None;
+            };
+
+            // Both connections are locked for the duration of the copy,
+            // nothing else can use the handles.
+            Some(unsafe {
+                step_all(
+                    source.as_raw_handle().as_ptr(),
+                    destination.as_raw_handle().as_ptr(),
+                )
+            })
+        });
+        (code, source, destination)
+    })
+    .await;
+
+    let (code, source, destination) = match copied {
+        Ok(copied) => copied,
+        Err(why) => {
+            error!("Database backup did not finish: {why:?}");
+            return None;
+        }
+    };
+
+    match code {
+        Some(SQLITE_OK) => Some((true, source, destination)),
+        Some(code) => {
+            error!("Database backup failed with code {code}");
+            Some((false, source, destination))
+        }
+        None => Some((false, source, destination)),
+    }
+}
+
+unsafe fn step_all(source: *mut sqlite3, destination: *mut sqlite3) -> c_int {
+    let backup = sqlite3_backup_init(destination, c"main".as_ptr(), source, c"main".as_ptr());
+    if backup.is_null() {
+        return sqlite3_errcode(destination);
+    }
+
+    // Copies everything in one step, a busy source is retried until the
+    // writers are done with it.
+    let mut step = SQLITE_BUSY;
+    for _ in 0..MAX_ATTEMPTS {
+        step = sqlite3_backup_step(backup, -1);
+        if step != SQLITE_BUSY && step != SQLITE_LOCKED {
+            break;
+        }
+        sqlite3_sleep(100);
+    }
+
+    let finish = sqlite3_backup_finish(backup);
+    match step {
+        SQLITE_DONE => finish,
+        step => step,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::players::{create_player, find_player};
+
+    #[tokio::test]
+    async fn backups_restore_into_another_database() {
+        let path = std::env::temp_dir().join(format!("etbot-backup-{}.sqlite", std::process::id()));
+        let database = crate::database::in_memory().await;
+        create_player(&"1001".to_string(), 0, &database).await;
+
+        let backed_up = backup_database(&database, &path).await;
+
+        let restored = crate::database::in_memory().await;
+        let restored_ok = restore_database(&path, &restored).await;
+        let _ = std::fs::remove_file(&path);
+
+        assert!(backed_up);
+        assert!(restored_ok);
+        assert!(find_player(&"1001".to_string(), &restored).await.is_some());
+    }
+}
@@ -0,0 +1,43 @@
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tracing::instrument;
+
+pub async fn change_charity_pool(amount: i64, connection: &mut SqliteConnection) -> bool {
+    sqlx::query!(
+        "UPDATE charity_pool SET balance = balance + ? WHERE id = 1",
+        amount
+    )
+    .execute(&mut *connection)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+/// Takes `amount` from the pool, fails when the pool holds less.
+pub async fn take_from_charity_pool(amount: i64, connection: &mut SqliteConnection) -> bool {
+    sqlx::query!(
+        "UPDATE charity_pool SET balance = balance - ? WHERE id = 1 AND balance >= ?",
+        amount,
+        amount
+    )
+    .execute(&mut *connection)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+pub async fn set_charity_pool(balance: i64, connection: &mut SqliteConnection) -> bool {
+    sqlx::query!("UPDATE charity_pool SET balance = ? WHERE id = 1", balance)
+        .execute(&mut *connection)
+        .await
+        .ok()
+        .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+#[instrument]
+pub async fn find_charity_pool(database: &Pool<Sqlite>) -> i64 {
+    sqlx::query_scalar!("SELECT balance FROM charity_pool WHERE id = 1")
+        .fetch_optional(database)
+        .await
+        .unwrap_or(None)
+        .unwrap_or_default()
+}
@@ -0,0 +1,222 @@
+use sqlx::{Pool, Sqlite};
+use tracing::instrument;
+
+use crate::database::house::{record_player_bet, transfer_from_house};
+use crate::database::players::Player;
+
+#[derive(Clone, Debug)]
+pub struct CrapsBet {
+    pub id: i64,
+    pub discord_user_id: String,
+    pub kind: String,
+    pub amount: i64,
+    /// Point of the bet once it has travelled past its come-out roll.
+    pub point: Option<i64>,
+}
+
+#[instrument]
+pub async fn find_craps_point(user_id: &String, database: &Pool<Sqlite>) -> Option<i64> {
+    sqlx::query_scalar!(
+        "SELECT point FROM craps_tables WHERE discord_user_id = ?",
+        user_id
+    )
+    .fetch_optional(database)
+    .await
+    .unwrap_or(None)
+    .flatten()
+}
+
+#[instrument]
+pub async fn save_craps_point(
+    user_id: &String,
+    point: Option<i64>,
+    database: &Pool<Sqlite>,
+) -> bool {
+    sqlx::query!(
+        "INSERT INTO craps_tables (discord_user_id, point) VALUES (?, ?)
+         ON CONFLICT (discord_user_id) DO UPDATE SET point = excluded.point",
+        user_id,
+        point
+    )
+    .execute(database)
+    .await
+    .is_ok()
+}
+
+#[instrument]
+pub async fn load_craps_bets(user_id: &String, database: &Pool<Sqlite>) -> Vec<CrapsBet> {
+    sqlx::query_as!(
+        CrapsBet,
+        "SELECT id as \"id!\", discord_user_id, kind, amount, point FROM craps_bets WHERE discord_user_id = ? ORDER BY id",
+        user_id
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+/// Saves the player with the bet taken from the balance, hands the bet to
+/// the house and puts it on the table in one transaction.
+#[instrument]
+pub async fn place_craps_bet(
+    player: &mut Player,
+    bet: &mut CrapsBet,
+    day: i64,
+    database: &Pool<Sqlite>,
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    if !record_player_bet(player, bet.amount, 0, "craps", day, &mut tx).await {
+        return false;
+    }
+
+    let Ok(result) = sqlx::query!(
+        "INSERT INTO craps_bets (discord_user_id, kind, amount, point) VALUES (?, ?, ?, ?)",
+        bet.discord_user_id,
+        bet.kind,
+        bet.amount,
+        bet.point
+    )
+    .execute(&mut *tx)
+    .await
+    else {
+        return false;
+    };
+
+    if tx.commit().await.is_err() {
+        return false;
+    }
+
+    bet.id = result.last_insert_rowid();
+    player.version += 1;
+    true
+}
+
+#[instrument]
+pub async fn update_craps_bet_point(bet: &CrapsBet, database: &Pool<Sqlite>) -> bool {
+    sqlx::query!(
+        "UPDATE craps_bets SET point = ? WHERE id = ? AND point IS NULL",
+        bet.point,
+        bet.id
+    )
+    .execute(database)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+/// Takes the bet off the table and pays `payout` from the house in one
+/// transaction. Returns `false` when the bet has already been settled by
+/// someone else.
+#[instrument]
+pub async fn settle_craps_bet(
+    bet: &CrapsBet,
+    payout: i64,
+    day: i64,
+    database: &Pool<Sqlite>,
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    let removed = sqlx::query!("DELETE FROM craps_bets WHERE id = ?", bet.id)
+        .execute(&mut *tx)
+        .await
+        .ok()
+        .map_or_else(|| false, |result| result.rows_affected() > 0);
+
+    if !removed {
+        return false;
+    }
+
+    if payout > 0 && !transfer_from_house(&bet.discord_user_id, payout, "craps", day, &mut tx).await
+    {
+        return false;
+    }
+
+    tx.commit().await.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::house::find_house_balance;
+    use crate::database::players::{create_player, find_player};
+
+    const TS: i64 = 1_760_000_000;
+    const DAY: i64 = 20_370;
+
+    async fn place(user_id: &str, amount: i64, database: &Pool<Sqlite>) -> Option<CrapsBet> {
+        let mut player = match find_player(&user_id.to_string(), database).await {
+            Some(player) => player,
+            None => create_player(&user_id.to_string(), TS, database).await?,
+        };
+        player.balance -= amount;
+        let mut bet = CrapsBet {
+            id: 0,
+            discord_user_id: user_id.to_string(),
+            kind: "pass".to_string(),
+            amount,
+            point: None,
+        };
+
+        place_craps_bet(&mut player, &mut bet, DAY, database)
+            .await
+            .then_some(bet)
+    }
+
+    async fn balance(user_id: &str, database: &Pool<Sqlite>) -> i64 {
+        find_player(&user_id.to_string(), database)
+            .await
+            .unwrap()
+            .balance
+    }
+
+    #[tokio::test]
+    async fn bet_goes_to_the_house_with_the_table() {
+        let database = crate::database::in_memory().await;
+        let house = find_house_balance(&database).await;
+
+        let bet = place("1", 500, &database).await.unwrap();
+
+        assert_eq!(balance("1", &database).await, 4500);
+        assert_eq!(find_house_balance(&database).await, house + 500);
+        assert_eq!(
+            load_craps_bets(&"1".to_string(), &database).await[0].id,
+            bet.id
+        );
+    }
+
+    #[tokio::test]
+    async fn won_bet_is_paid_once() {
+        let database = crate::database::in_memory().await;
+        let house = find_house_balance(&database).await;
+
+        let bet = place("1", 500, &database).await.unwrap();
+        assert!(settle_craps_bet(&bet, 1000, DAY, &database).await);
+        assert!(!settle_craps_bet(&bet, 1000, DAY, &database).await);
+
+        assert_eq!(balance("1", &database).await, 5500);
+        assert_eq!(find_house_balance(&database).await, house - 500);
+        assert!(load_craps_bets(&"1".to_string(), &database)
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn lost_bet_leaves_the_table_without_payout() {
+        let database = crate::database::in_memory().await;
+        let house = find_house_balance(&database).await;
+
+        let bet = place("1", 500, &database).await.unwrap();
+        assert!(settle_craps_bet(&bet, 0, DAY, &database).await);
+
+        assert_eq!(balance("1", &database).await, 4500);
+        assert_eq!(find_house_balance(&database).await, house + 500);
+        assert!(load_craps_bets(&"1".to_string(), &database)
+            .await
+            .is_empty());
+    }
+}
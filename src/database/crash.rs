@@ -0,0 +1,450 @@
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tracing::instrument;
+
+use crate::database::house::{record_player_bet, transfer_from_house};
+use crate::database::players::Player;
+
+pub const STATUS_BETTING: &str = "betting";
+pub const STATUS_RUNNING: &str = "running";
+pub const STATUS_CRASHED: &str = "crashed";
+pub const STATUS_CANCELLED: &str = "cancelled";
+
+/// Potatoes paid for a bet cashed out at `multiplier`, in hundredths, with
+/// the `boost` of the round on top.
+pub fn payout(amount: i64, multiplier: i64, boost: f64) -> i64 {
+    let payout = amount * multiplier / 100;
+    ((payout as f64 * boost).floor() as i64).max(payout)
+}
+
+#[derive(Clone, Debug)]
+pub struct CrashRound {
+    pub id: i64,
+    pub channel_id: String,
+    pub message_id: Option<String>,
+    /// Multiplier the round crashes at, in hundredths (`250` is `2.50x`).
+    pub crash_point: i64,
+    pub status: String,
+    pub created_ts: i64,
+    pub started_ms: Option<i64>,
+    /// Multiplier of the payouts from the events running when the round was
+    /// opened.
+    pub boost: f64,
+}
+
+#[derive(Clone, Debug)]
+pub struct CrashBet {
+    pub round_id: i64,
+    pub discord_user_id: String,
+    pub amount: i64,
+    /// Multiplier the bet was cashed out at, in hundredths.
+    pub cashout: Option<i64>,
+    /// Multiplier the bet is cashed out at automatically, the most the house
+    /// could cover when the bet was placed.
+    pub max_cashout: Option<i64>,
+}
+
+async fn insert_crash_round(round: &mut CrashRound, connection: &mut SqliteConnection) -> bool {
+    sqlx::query!(
+        "INSERT INTO crash_rounds (channel_id, message_id, crash_point, status, created_ts, started_ms, boost) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        round.channel_id,
+        round.message_id,
+        round.crash_point,
+        round.status,
+        round.created_ts,
+        round.started_ms,
+        round.boost
+    )
+    .execute(&mut *connection)
+    .await
+    .ok()
+    .map_or_else(
+        || false,
+        |result| {
+            round.id = result.last_insert_rowid();
+            true
+        },
+    )
+}
+
+#[instrument]
+pub async fn update_crash_round(round: &CrashRound, database: &Pool<Sqlite>) -> bool {
+    let Ok(mut connection) = database.acquire().await else {
+        return false;
+    };
+
+    save_crash_round(round, &mut connection).await
+}
+
+async fn save_crash_round(round: &CrashRound, connection: &mut SqliteConnection) -> bool {
+    sqlx::query!(
+        "UPDATE crash_rounds SET message_id = ?, status = ?, started_ms = ? WHERE id = ?",
+        round.message_id,
+        round.status,
+        round.started_ms,
+        round.id
+    )
+    .execute(&mut *connection)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+#[instrument]
+pub async fn find_open_crash_round(
+    channel_id: &String,
+    database: &Pool<Sqlite>,
+) -> Option<CrashRound> {
+    sqlx::query_as!(
+        CrashRound,
+        "SELECT id as \"id!\", channel_id, message_id, crash_point, status, created_ts, started_ms, boost FROM crash_rounds
+         WHERE channel_id = ? AND status IN (?, ?)",
+        channel_id,
+        STATUS_BETTING,
+        STATUS_RUNNING
+    )
+    .fetch_optional(database)
+    .await
+    .unwrap_or(None)
+}
+
+#[instrument]
+pub async fn find_unfinished_crash_rounds(database: &Pool<Sqlite>) -> Vec<CrashRound> {
+    sqlx::query_as!(
+        CrashRound,
+        "SELECT id as \"id!\", channel_id, message_id, crash_point, status, created_ts, started_ms, boost FROM crash_rounds
+         WHERE status IN (?, ?)",
+        STATUS_BETTING,
+        STATUS_RUNNING
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+/// Adds the bet to the round, fails when the user has a bet in the round
+/// already or the round has left the betting phase.
+async fn insert_crash_bet(bet: &CrashBet, connection: &mut SqliteConnection) -> bool {
+    sqlx::query!(
+        "INSERT INTO crash_bets (round_id, discord_user_id, amount, cashout, max_cashout)
+         SELECT ?, ?, ?, ?, ? WHERE EXISTS (SELECT 1 FROM crash_rounds WHERE id = ? AND status = ?)",
+        bet.round_id,
+        bet.discord_user_id,
+        bet.amount,
+        bet.cashout,
+        bet.max_cashout,
+        bet.round_id,
+        STATUS_BETTING
+    )
+    .execute(&mut *connection)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+/// Saves the player with the bet taken from the balance, hands the bet to
+/// the house and adds it to the round in one transaction.
+#[instrument]
+pub async fn place_crash_bet(
+    player: &mut Player,
+    bet: &CrashBet,
+    day: i64,
+    database: &Pool<Sqlite>,
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    if !record_player_bet(player, bet.amount, 0, "crash", day, &mut tx).await
+        || !insert_crash_bet(bet, &mut tx).await
+        || tx.commit().await.is_err()
+    {
+        return false;
+    }
+
+    player.version += 1;
+    true
+}
+
+/// Opens the round with the first bet in it, like `place_crash_bet`. Fails
+/// when the channel has an unfinished round already.
+#[instrument]
+pub async fn open_crash_round(
+    round: &mut CrashRound,
+    player: &mut Player,
+    bet: &mut CrashBet,
+    day: i64,
+    database: &Pool<Sqlite>,
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    if !insert_crash_round(round, &mut tx).await {
+        return false;
+    }
+
+    bet.round_id = round.id;
+
+    if !record_player_bet(player, bet.amount, 0, "crash", day, &mut tx).await
+        || !insert_crash_bet(bet, &mut tx).await
+        || tx.commit().await.is_err()
+    {
+        return false;
+    }
+
+    player.version += 1;
+    true
+}
+
+/// Refunds the bets that were not cashed out from the house and saves the
+/// cancelled round in one transaction.
+#[instrument]
+pub async fn cancel_crash_round(round: &CrashRound, day: i64, database: &Pool<Sqlite>) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    let bets = sqlx::query_as!(
+        CrashBet,
+        "SELECT round_id, discord_user_id, amount, cashout, max_cashout FROM crash_bets WHERE round_id = ? AND cashout IS NULL",
+        round.id
+    )
+    .fetch_all(&mut *tx)
+    .await;
+
+    let Ok(bets) = bets else {
+        return false;
+    };
+
+    for bet in bets.iter() {
+        if !transfer_from_house(&bet.discord_user_id, bet.amount, "crash", day, &mut tx).await {
+            return false;
+        }
+    }
+
+    save_crash_round(round, &mut tx).await && tx.commit().await.is_ok()
+}
+
+#[instrument]
+pub async fn load_crash_bets(round_id: i64, database: &Pool<Sqlite>) -> Vec<CrashBet> {
+    sqlx::query_as!(
+        CrashBet,
+        "SELECT round_id, discord_user_id, amount, cashout, max_cashout FROM crash_bets WHERE round_id = ? ORDER BY amount DESC",
+        round_id
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+/// Marks the bet as cashed out and pays it from the house in the same
+/// transaction, fails when the bet does not exist or has already been cashed
+/// out. Bets are never cashed out above their `max_cashout`.
+#[instrument]
+pub async fn cash_out_crash_bet(
+    round: &CrashRound,
+    user_id: &String,
+    cashout: i64,
+    day: i64,
+    database: &Pool<Sqlite>,
+) -> Option<CrashBet> {
+    let mut tx = database.begin().await.ok()?;
+
+    let bet = sqlx::query_as!(
+        CrashBet,
+        "UPDATE crash_bets SET cashout = MIN(?, COALESCE(max_cashout, ?)) WHERE round_id = ? AND discord_user_id = ? AND cashout IS NULL
+         RETURNING round_id, discord_user_id, amount, cashout, max_cashout",
+        cashout,
+        cashout,
+        round.id,
+        user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await
+    .ok()??;
+
+    let amount = payout(bet.amount, bet.cashout.unwrap_or(cashout), round.boost);
+    if !transfer_from_house(user_id, amount, "crash", day, &mut tx).await {
+        return None;
+    }
+
+    tx.commit().await.ok()?;
+
+    Some(bet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::house::find_house_balance;
+    use crate::database::players::{create_player, find_player};
+
+    const TS: i64 = 1_760_000_000;
+    const DAY: i64 = 20_370;
+
+    fn new_round() -> CrashRound {
+        CrashRound {
+            id: 0,
+            channel_id: "1".to_string(),
+            message_id: None,
+            crash_point: 250,
+            status: STATUS_BETTING.into(),
+            created_ts: TS,
+            started_ms: None,
+            boost: 1.0,
+        }
+    }
+
+    fn bet(round_id: i64, user_id: &str, amount: i64) -> CrashBet {
+        CrashBet {
+            round_id,
+            discord_user_id: user_id.to_string(),
+            amount,
+            cashout: None,
+            max_cashout: None,
+        }
+    }
+
+    async fn debited(user_id: &str, amount: i64, database: &Pool<Sqlite>) -> Player {
+        let mut player = match find_player(&user_id.to_string(), database).await {
+            Some(player) => player,
+            None => create_player(&user_id.to_string(), TS, database)
+                .await
+                .unwrap(),
+        };
+        player.balance -= amount;
+        player
+    }
+
+    async fn balance(user_id: &str, database: &Pool<Sqlite>) -> i64 {
+        find_player(&user_id.to_string(), database)
+            .await
+            .unwrap()
+            .balance
+    }
+
+    #[tokio::test]
+    async fn second_bet_in_the_round_is_not_taken() {
+        let database = crate::database::in_memory().await;
+        let house = find_house_balance(&database).await;
+
+        let mut round = new_round();
+        let mut player = debited("1", 500, &database).await;
+        let mut first = bet(0, "1", 500);
+        assert!(open_crash_round(&mut round, &mut player, &mut first, DAY, &database).await);
+        assert_eq!(first.round_id, round.id);
+
+        player.balance -= 300;
+        assert!(!place_crash_bet(&mut player, &bet(round.id, "1", 300), DAY, &database).await);
+
+        // Another round can't be opened in the channel meanwhile.
+        let mut player = debited("2", 200, &database).await;
+        assert!(
+            !open_crash_round(
+                &mut new_round(),
+                &mut player,
+                &mut bet(0, "2", 200),
+                DAY,
+                &database
+            )
+            .await
+        );
+
+        assert_eq!(balance("1", &database).await, 4500);
+        assert_eq!(balance("2", &database).await, 5000);
+        assert_eq!(find_house_balance(&database).await, house + 500);
+    }
+
+    #[tokio::test]
+    async fn bet_is_not_taken_once_the_round_runs() {
+        let database = crate::database::in_memory().await;
+
+        let mut round = new_round();
+        let mut player = debited("1", 500, &database).await;
+        assert!(
+            open_crash_round(
+                &mut round,
+                &mut player,
+                &mut bet(0, "1", 500),
+                DAY,
+                &database
+            )
+            .await
+        );
+        round.status = STATUS_RUNNING.into();
+        assert!(update_crash_round(&round, &database).await);
+
+        let mut player = debited("2", 300, &database).await;
+        assert!(!place_crash_bet(&mut player, &bet(round.id, "2", 300), DAY, &database).await);
+
+        assert_eq!(balance("2", &database).await, 5000);
+        assert_eq!(load_crash_bets(round.id, &database).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn cash_out_pays_once_from_the_house() {
+        let database = crate::database::in_memory().await;
+        let house = find_house_balance(&database).await;
+
+        let mut round = new_round();
+        let mut player = debited("1", 500, &database).await;
+        assert!(
+            open_crash_round(
+                &mut round,
+                &mut player,
+                &mut bet(0, "1", 500),
+                DAY,
+                &database
+            )
+            .await
+        );
+
+        let user_id = "1".to_string();
+        assert!(cash_out_crash_bet(&round, &user_id, 200, DAY, &database)
+            .await
+            .is_some());
+        assert!(cash_out_crash_bet(&round, &user_id, 220, DAY, &database)
+            .await
+            .is_none());
+
+        assert_eq!(balance("1", &database).await, 5500);
+        assert_eq!(find_house_balance(&database).await, house - 500);
+    }
+
+    #[tokio::test]
+    async fn cancelled_round_refunds_the_bets_left_once() {
+        let database = crate::database::in_memory().await;
+        let house = find_house_balance(&database).await;
+
+        let mut round = new_round();
+        let mut player = debited("1", 500, &database).await;
+        assert!(
+            open_crash_round(
+                &mut round,
+                &mut player,
+                &mut bet(0, "1", 500),
+                DAY,
+                &database
+            )
+            .await
+        );
+        let mut player = debited("2", 300, &database).await;
+        assert!(place_crash_bet(&mut player, &bet(round.id, "2", 300), DAY, &database).await);
+
+        round.status = STATUS_RUNNING.into();
+        assert!(update_crash_round(&round, &database).await);
+        assert!(
+            cash_out_crash_bet(&round, &"1".to_string(), 150, DAY, &database)
+                .await
+                .is_some()
+        );
+
+        round.status = STATUS_CANCELLED.into();
+        assert!(cancel_crash_round(&round, DAY, &database).await);
+        assert!(find_unfinished_crash_rounds(&database).await.is_empty());
+
+        assert_eq!(balance("1", &database).await, 5250);
+        assert_eq!(balance("2", &database).await, 5000);
+        assert_eq!(find_house_balance(&database).await, house - 250);
+    }
+}
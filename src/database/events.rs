@@ -0,0 +1,122 @@
+use sqlx::{Pool, Sqlite};
+use tracing::instrument;
+
+pub const TARGET_FLIP: &str = "flip";
+pub const TARGET_DICE: &str = "dice";
+pub const TARGET_CRAPS: &str = "craps";
+pub const TARGET_CRASH: &str = "crash";
+pub const TARGET_MINES: &str = "mines";
+pub const TARGET_RACE: &str = "race";
+pub const TARGET_FEEDER: &str = "feeder";
+
+/// Time window during which payouts of the target are multiplied. Weekly
+/// events keep the times of their next occurrence and move on a week once it
+/// is over.
+#[derive(Clone, Debug)]
+pub struct Event {
+    pub id: i64,
+    pub name: String,
+    pub target: String,
+    pub multiplier: f64,
+    pub starts_ts: i64,
+    pub ends_ts: i64,
+    pub created_by: String,
+    pub start_announced: bool,
+    pub end_announced: bool,
+    pub weekly: bool,
+}
+
+#[instrument]
+pub async fn create_event(event: &mut Event, database: &Pool<Sqlite>) -> bool {
+    sqlx::query!(
+        "INSERT INTO events (name, target, multiplier, starts_ts, ends_ts, created_by, weekly) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        event.name,
+        event.target,
+        event.multiplier,
+        event.starts_ts,
+        event.ends_ts,
+        event.created_by,
+        event.weekly
+    )
+    .execute(database)
+    .await
+    .ok()
+    .map_or_else(
+        || false,
+        |result| {
+            event.id = result.last_insert_rowid();
+            true
+        },
+    )
+}
+
+#[instrument]
+pub async fn remove_event(id: i64, database: &Pool<Sqlite>) -> bool {
+    sqlx::query!("DELETE FROM events WHERE id = ?", id)
+        .execute(database)
+        .await
+        .ok()
+        .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+/// Events that have not ended yet, the earliest first.
+#[instrument]
+pub async fn load_upcoming_events(ts: i64, database: &Pool<Sqlite>) -> Vec<Event> {
+    sqlx::query_as!(
+        Event,
+        "SELECT id as \"id!\", name, target, multiplier, starts_ts, ends_ts, created_by, start_announced, end_announced, weekly
+         FROM events WHERE ends_ts > ? ORDER BY starts_ts",
+        ts
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+#[instrument]
+pub async fn load_active_events(target: &str, ts: i64, database: &Pool<Sqlite>) -> Vec<Event> {
+    sqlx::query_as!(
+        Event,
+        "SELECT id as \"id!\", name, target, multiplier, starts_ts, ends_ts, created_by, start_announced, end_announced, weekly
+         FROM events WHERE target = ? AND starts_ts <= ? AND ends_ts > ?",
+        target,
+        ts,
+        ts
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+/// Events whose start or end has not been announced although it is time.
+#[instrument]
+pub async fn find_unannounced_events(ts: i64, database: &Pool<Sqlite>) -> Vec<Event> {
+    sqlx::query_as!(
+        Event,
+        "SELECT id as \"id!\", name, target, multiplier, starts_ts, ends_ts, created_by, start_announced, end_announced, weekly
+         FROM events WHERE (starts_ts <= ? AND NOT start_announced) OR (ends_ts <= ? AND NOT end_announced)
+         ORDER BY starts_ts",
+        ts,
+        ts
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+/// Saves the announcements and the times of the next occurrence.
+#[instrument]
+pub async fn update_event(event: &Event, database: &Pool<Sqlite>) -> bool {
+    sqlx::query!(
+        "UPDATE events SET starts_ts = ?, ends_ts = ?, start_announced = ?, end_announced = ? WHERE id = ?",
+        event.starts_ts,
+        event.ends_ts,
+        event.start_announced,
+        event.end_announced,
+        event.id
+    )
+    .execute(database)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
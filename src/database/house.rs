@@ -0,0 +1,303 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tracing::instrument;
+
+use crate::database::limits::record_player_loss;
+use crate::database::players::{credit_player, save_player, Player};
+
+/// Profit (or loss when negative) of the house from one source of income.
+#[derive(Clone, Debug)]
+pub struct HouseResult {
+    pub source: String,
+    pub amount: i64,
+}
+
+/// What the house made from one source in a day.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct LedgerEntry {
+    pub day: i64,
+    pub source: String,
+    pub amount: i64,
+}
+
+/// Changes the balance of the house and books it in the ledger on `day`, the
+/// local day of the clock.
+pub async fn change_house_balance(
+    amount: i64,
+    source: &str,
+    day: i64,
+    connection: &mut SqliteConnection,
+) -> bool {
+    let updated = sqlx::query!(
+        "UPDATE house SET balance = balance + ? WHERE id = 1",
+        amount
+    )
+    .execute(&mut *connection)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0);
+
+    if !updated {
+        return false;
+    }
+
+    sqlx::query!(
+        "INSERT INTO house_ledger (day, source, amount) VALUES (?, ?, ?)
+         ON CONFLICT (day, source) DO UPDATE SET amount = amount + excluded.amount",
+        day,
+        source,
+        amount
+    )
+    .execute(&mut *connection)
+    .await
+    .is_ok()
+}
+
+#[instrument]
+pub async fn find_house_balance(database: &Pool<Sqlite>) -> i64 {
+    sqlx::query_scalar!("SELECT balance FROM house WHERE id = 1")
+        .fetch_optional(database)
+        .await
+        .unwrap_or(None)
+        .unwrap_or_default()
+}
+
+/// Results of the house per source since the given day.
+#[instrument]
+pub async fn load_house_results(since_day: i64, database: &Pool<Sqlite>) -> Vec<HouseResult> {
+    sqlx::query_as!(
+        HouseResult,
+        "SELECT source, SUM(amount) as \"amount!: i64\" FROM house_ledger WHERE day >= ? GROUP BY source ORDER BY source",
+        since_day
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+#[instrument]
+pub async fn load_house_ledger(database: &Pool<Sqlite>) -> Vec<LedgerEntry> {
+    sqlx::query_as!(
+        LedgerEntry,
+        "SELECT day, source, amount FROM house_ledger ORDER BY day, source"
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+/// Replaces the ledger entry, the balance of the house stays as it is.
+pub async fn save_ledger_entry(entry: &LedgerEntry, connection: &mut SqliteConnection) -> bool {
+    sqlx::query!(
+        "INSERT INTO house_ledger (day, source, amount) VALUES (?, ?, ?)
+         ON CONFLICT (day, source) DO UPDATE SET amount = excluded.amount",
+        entry.day,
+        entry.source,
+        entry.amount
+    )
+    .execute(&mut *connection)
+    .await
+    .is_ok()
+}
+
+/// Changes the balance of the house without booking it in the ledger, for
+/// amounts the ledger has already.
+pub async fn adjust_house_balance(amount: i64, connection: &mut SqliteConnection) -> bool {
+    sqlx::query!(
+        "UPDATE house SET balance = balance + ? WHERE id = 1",
+        amount
+    )
+    .execute(&mut *connection)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+pub async fn set_house_balance(balance: i64, connection: &mut SqliteConnection) -> bool {
+    sqlx::query!("UPDATE house SET balance = ? WHERE id = 1", balance)
+        .execute(&mut *connection)
+        .await
+        .ok()
+        .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+/// Saves the player like `save_player` and settles the bet with the house
+/// and the jackpot within the transaction of the caller, for games saving
+/// the bet along with it. `house_amount` is what the house wins, negative
+/// when it pays out. The daily loss of the player is kept up to date along
+/// with it.
+pub async fn record_player_bet(
+    player: &Player,
+    house_amount: i64,
+    jackpot_amount: i64,
+    source: &str,
+    day: i64,
+    connection: &mut SqliteConnection,
+) -> bool {
+    if !save_player(player, &mut *connection).await {
+        return false;
+    }
+
+    if house_amount != 0 && !change_house_balance(house_amount, source, day, &mut *connection).await
+    {
+        return false;
+    }
+
+    let loss = house_amount + jackpot_amount;
+    if loss != 0 && !record_player_loss(&player.discord_user_id, loss, day, &mut *connection).await
+    {
+        return false;
+    }
+
+    jackpot_amount <= 0
+        || sqlx::query!(
+            "UPDATE jackpot SET pool = pool + ? WHERE id = 1",
+            jackpot_amount
+        )
+        .execute(&mut *connection)
+        .await
+        .is_ok()
+}
+
+/// Saves the player like `update_player` and settles the bet with the house
+/// and the jackpot in the same transaction, see `record_player_bet`.
+#[instrument]
+pub async fn settle_player_bet(
+    player: &mut Player,
+    house_amount: i64,
+    jackpot_amount: i64,
+    source: &str,
+    day: i64,
+    database: &Pool<Sqlite>,
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    if !record_player_bet(player, house_amount, jackpot_amount, source, day, &mut tx).await {
+        return false;
+    }
+
+    if tx.commit().await.is_err() {
+        return false;
+    }
+
+    player.version += 1;
+    true
+}
+
+/// Moves the jackpot contribution of a lost bet the house has already taken
+/// from the house into the jackpot pool.
+#[instrument]
+pub async fn contribute_to_jackpot(
+    amount: i64,
+    source: &str,
+    day: i64,
+    database: &Pool<Sqlite>,
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    if !change_house_balance(-amount, source, day, &mut tx).await {
+        return false;
+    }
+
+    let added = sqlx::query!("UPDATE jackpot SET pool = pool + ? WHERE id = 1", amount)
+        .execute(&mut *tx)
+        .await
+        .ok()
+        .map_or_else(|| false, |result| result.rows_affected() > 0);
+
+    added && tx.commit().await.is_ok()
+}
+
+/// Moves `amount` from the house to the player within the transaction of the
+/// caller, for games saving the outcome of the bet along with the payout.
+pub async fn transfer_from_house(
+    user_id: &str,
+    amount: i64,
+    source: &str,
+    day: i64,
+    connection: &mut SqliteConnection,
+) -> bool {
+    if !credit_player(user_id, amount, &mut *connection).await
+        || !change_house_balance(-amount, source, day, &mut *connection).await
+    {
+        return false;
+    }
+
+    record_player_loss(user_id, -amount, day, &mut *connection).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::jackpot::find_jackpot_pool;
+    use crate::database::limits::find_player_loss;
+    use crate::database::players::{create_player, find_player};
+
+    const TS: i64 = 1_760_000_000;
+    const DAY: i64 = 20_370;
+
+    #[tokio::test]
+    async fn won_bet_is_paid_by_the_house() {
+        let database = crate::database::in_memory().await;
+        let user_id = "1".to_string();
+        let mut player = create_player(&user_id, TS, &database).await.unwrap();
+        let house = find_house_balance(&database).await;
+
+        player.balance += 800;
+        assert!(settle_player_bet(&mut player, -800, 0, "dice", DAY, &database).await);
+
+        assert_eq!(
+            find_player(&user_id, &database).await.unwrap().balance,
+            5800
+        );
+        assert_eq!(find_house_balance(&database).await, house - 800);
+        assert_eq!(find_player_loss(&user_id, DAY, &database).await, -800);
+    }
+
+    #[tokio::test]
+    async fn lost_bet_is_split_with_the_jackpot() {
+        let database = crate::database::in_memory().await;
+        let user_id = "1".to_string();
+        let mut player = create_player(&user_id, TS, &database).await.unwrap();
+        let house = find_house_balance(&database).await;
+        let jackpot = find_jackpot_pool(&database).await;
+
+        player.balance -= 500;
+        assert!(settle_player_bet(&mut player, 490, 10, "dice", DAY, &database).await);
+
+        assert_eq!(
+            find_player(&user_id, &database).await.unwrap().balance,
+            4500
+        );
+        assert_eq!(find_house_balance(&database).await, house + 490);
+        assert_eq!(find_jackpot_pool(&database).await, jackpot + 10);
+        assert_eq!(find_player_loss(&user_id, DAY, &database).await, 500);
+    }
+
+    #[tokio::test]
+    async fn stale_player_settles_nothing() {
+        let database = crate::database::in_memory().await;
+        let user_id = "1".to_string();
+        let player = create_player(&user_id, TS, &database).await.unwrap();
+        let house = find_house_balance(&database).await;
+
+        let mut first = player.clone();
+        first.balance -= 500;
+        assert!(settle_player_bet(&mut first, 500, 0, "dice", DAY, &database).await);
+
+        let mut stale = player;
+        stale.balance -= 700;
+        assert!(!settle_player_bet(&mut stale, 700, 0, "dice", DAY, &database).await);
+
+        assert_eq!(
+            find_player(&user_id, &database).await.unwrap().balance,
+            4500
+        );
+        assert_eq!(find_house_balance(&database).await, house + 500);
+        assert_eq!(find_player_loss(&user_id, DAY, &database).await, 500);
+    }
+}
@@ -0,0 +1,93 @@
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tracing::instrument;
+
+#[derive(Clone, Debug)]
+pub struct JackpotWin {
+    pub discord_user_id: String,
+    pub amount: i64,
+    pub game: String,
+    pub won_ts: i64,
+}
+
+#[instrument]
+pub async fn find_jackpot_pool(database: &Pool<Sqlite>) -> i64 {
+    sqlx::query_scalar!("SELECT pool FROM jackpot WHERE id = 1")
+        .fetch_optional(database)
+        .await
+        .unwrap_or(None)
+        .unwrap_or_default()
+}
+
+pub async fn set_jackpot_pool(pool: i64, connection: &mut SqliteConnection) -> bool {
+    sqlx::query!("UPDATE jackpot SET pool = ? WHERE id = 1", pool)
+        .execute(&mut *connection)
+        .await
+        .ok()
+        .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+#[instrument]
+pub async fn find_last_jackpot_win(database: &Pool<Sqlite>) -> Option<JackpotWin> {
+    sqlx::query_as!(
+        JackpotWin,
+        "SELECT discord_user_id, amount, game, won_ts FROM jackpot_wins ORDER BY id DESC LIMIT 1"
+    )
+    .fetch_optional(database)
+    .await
+    .unwrap_or(None)
+}
+
+/// Moves the whole pool to the player, returns the amount won.
+#[instrument]
+pub async fn win_jackpot(
+    user_id: &String,
+    game: &str,
+    won_ts: i64,
+    database: &Pool<Sqlite>,
+) -> Option<i64> {
+    let mut tx = database.begin().await.ok()?;
+
+    let amount = sqlx::query_scalar!("SELECT pool FROM jackpot WHERE id = 1")
+        .fetch_one(&mut *tx)
+        .await
+        .ok()?;
+
+    if amount <= 0 {
+        return None;
+    }
+
+    sqlx::query!("UPDATE jackpot SET pool = 0 WHERE id = 1")
+        .execute(&mut *tx)
+        .await
+        .ok()?;
+
+    let paid = sqlx::query!(
+        "UPDATE players SET balance = balance + ?, version = version + 1 WHERE discord_user_id = ?",
+        amount,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await
+    .ok()?
+    .rows_affected()
+        > 0;
+
+    if !paid {
+        return None;
+    }
+
+    sqlx::query!(
+        "INSERT INTO jackpot_wins (discord_user_id, amount, game, won_ts) VALUES (?, ?, ?, ?)",
+        user_id,
+        amount,
+        game,
+        won_ts
+    )
+    .execute(&mut *tx)
+    .await
+    .ok()?;
+
+    tx.commit().await.ok()?;
+
+    Some(amount)
+}
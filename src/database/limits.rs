@@ -0,0 +1,215 @@
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tracing::instrument;
+
+/// Limits the player has set on themselves. Changes making the limits
+/// stricter apply right away, loosening them waits in the pending columns.
+#[derive(Clone, Debug)]
+pub struct PlayerLimits {
+    pub discord_user_id: String,
+    /// Largest net loss per day, 0 means no limit.
+    pub daily_loss_limit: i64,
+    pub pending_daily_loss_limit: Option<i64>,
+    pub daily_loss_limit_change_ts: Option<i64>,
+    pub excluded_until_ts: i64,
+    pub pending_excluded_until_ts: Option<i64>,
+    pub excluded_until_change_ts: Option<i64>,
+    pub version: i64,
+}
+
+impl PlayerLimits {
+    pub fn new(discord_user_id: &str) -> Self {
+        PlayerLimits {
+            discord_user_id: discord_user_id.to_string(),
+            daily_loss_limit: 0,
+            pending_daily_loss_limit: None,
+            daily_loss_limit_change_ts: None,
+            excluded_until_ts: 0,
+            pending_excluded_until_ts: None,
+            excluded_until_change_ts: None,
+            version: 0,
+        }
+    }
+
+    /// Applies the pending changes whose delay is over.
+    pub fn apply_due_changes(&mut self, ts: i64) {
+        if let (Some(limit), Some(change_ts)) = (
+            self.pending_daily_loss_limit,
+            self.daily_loss_limit_change_ts,
+        ) {
+            if change_ts <= ts {
+                self.daily_loss_limit = limit;
+                self.pending_daily_loss_limit = None;
+                self.daily_loss_limit_change_ts = None;
+            }
+        }
+
+        if let (Some(until_ts), Some(change_ts)) = (
+            self.pending_excluded_until_ts,
+            self.excluded_until_change_ts,
+        ) {
+            if change_ts <= ts {
+                self.excluded_until_ts = until_ts;
+                self.pending_excluded_until_ts = None;
+                self.excluded_until_change_ts = None;
+            }
+        }
+    }
+
+    pub fn is_excluded(&self, ts: i64) -> bool {
+        self.excluded_until_ts > ts
+    }
+}
+
+#[instrument]
+pub async fn find_player_limits(user_id: &String, database: &Pool<Sqlite>) -> Option<PlayerLimits> {
+    sqlx::query_as!(
+        PlayerLimits,
+        "SELECT discord_user_id, daily_loss_limit, pending_daily_loss_limit, daily_loss_limit_change_ts, excluded_until_ts, pending_excluded_until_ts, excluded_until_change_ts, version FROM player_limits WHERE discord_user_id = ?",
+        user_id
+    )
+    .fetch_optional(database)
+    .await
+    .unwrap_or(None)
+}
+
+/// Inserts or updates the limits, fails when they were changed in the
+/// meantime.
+#[instrument]
+pub async fn save_player_limits(limits: &mut PlayerLimits, database: &Pool<Sqlite>) -> bool {
+    let current_version = limits.version;
+    let next_version = current_version + 1;
+
+    let saved = sqlx::query!(
+        "INSERT INTO player_limits (discord_user_id, daily_loss_limit, pending_daily_loss_limit, daily_loss_limit_change_ts, excluded_until_ts, pending_excluded_until_ts, excluded_until_change_ts, version)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT (discord_user_id) DO UPDATE SET
+             daily_loss_limit = excluded.daily_loss_limit,
+             pending_daily_loss_limit = excluded.pending_daily_loss_limit,
+             daily_loss_limit_change_ts = excluded.daily_loss_limit_change_ts,
+             excluded_until_ts = excluded.excluded_until_ts,
+             pending_excluded_until_ts = excluded.pending_excluded_until_ts,
+             excluded_until_change_ts = excluded.excluded_until_change_ts,
+             version = excluded.version
+         WHERE player_limits.version = ?",
+        limits.discord_user_id,
+        limits.daily_loss_limit,
+        limits.pending_daily_loss_limit,
+        limits.daily_loss_limit_change_ts,
+        limits.excluded_until_ts,
+        limits.pending_excluded_until_ts,
+        limits.excluded_until_change_ts,
+        next_version,
+        current_version
+    )
+    .execute(database)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0);
+
+    if saved {
+        limits.version = next_version;
+    }
+
+    saved
+}
+
+/// Net loss of the player on the given day, negative when they are winning.
+#[instrument]
+pub async fn find_player_loss(user_id: &String, day: i64, database: &Pool<Sqlite>) -> i64 {
+    sqlx::query_scalar!(
+        "SELECT amount FROM player_losses WHERE discord_user_id = ? AND day = ?",
+        user_id,
+        day
+    )
+    .fetch_optional(database)
+    .await
+    .unwrap_or(None)
+    .unwrap_or_default()
+}
+
+/// Adds to the net loss of the player on the local `day`, `amount` is
+/// negative for wins and refunds.
+pub async fn record_player_loss(
+    user_id: &str,
+    amount: i64,
+    day: i64,
+    connection: &mut SqliteConnection,
+) -> bool {
+    sqlx::query!(
+        "INSERT INTO player_losses (discord_user_id, day, amount) VALUES (?, ?, ?)
+         ON CONFLICT (discord_user_id, day) DO UPDATE SET amount = amount + excluded.amount",
+        user_id,
+        day,
+        amount
+    )
+    .execute(&mut *connection)
+    .await
+    .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TS: i64 = 1_760_000_000;
+    const DAY: i64 = 20_370;
+
+    #[test]
+    fn pending_changes_wait_for_their_delay() {
+        let mut limits = PlayerLimits::new("1");
+        limits.daily_loss_limit = 1000;
+        limits.pending_daily_loss_limit = Some(0);
+        limits.daily_loss_limit_change_ts = Some(TS + 60);
+        limits.pending_excluded_until_ts = Some(TS + 3600);
+        limits.excluded_until_change_ts = Some(TS);
+
+        limits.apply_due_changes(TS);
+        assert_eq!(limits.daily_loss_limit, 1000);
+        assert_eq!(limits.pending_daily_loss_limit, Some(0));
+        assert!(limits.is_excluded(TS));
+        assert_eq!(limits.pending_excluded_until_ts, None);
+
+        limits.apply_due_changes(TS + 60);
+        assert_eq!(limits.daily_loss_limit, 0);
+        assert_eq!(limits.pending_daily_loss_limit, None);
+        assert_eq!(limits.daily_loss_limit_change_ts, None);
+        assert!(!limits.is_excluded(TS + 3600));
+    }
+
+    #[tokio::test]
+    async fn stale_limits_are_not_saved() {
+        let database = crate::database::in_memory().await;
+
+        let mut limits = PlayerLimits::new("1");
+        assert!(save_player_limits(&mut limits, &database).await);
+        let mut stale = limits.clone();
+
+        limits.daily_loss_limit = 1000;
+        assert!(save_player_limits(&mut limits, &database).await);
+
+        stale.daily_loss_limit = 5000;
+        assert!(!save_player_limits(&mut stale, &database).await);
+
+        let found = find_player_limits(&"1".to_string(), &database)
+            .await
+            .unwrap();
+        assert_eq!(found.daily_loss_limit, 1000);
+        assert_eq!(found.version, 2);
+    }
+
+    #[tokio::test]
+    async fn losses_add_up_per_day() {
+        let database = crate::database::in_memory().await;
+        let mut connection = database.acquire().await.unwrap();
+
+        assert!(record_player_loss("1", 500, DAY, &mut connection).await);
+        assert!(record_player_loss("1", -200, DAY, &mut connection).await);
+        assert!(record_player_loss("1", 100, DAY + 1, &mut connection).await);
+        drop(connection);
+
+        let user_id = "1".to_string();
+        assert_eq!(find_player_loss(&user_id, DAY, &database).await, 300);
+        assert_eq!(find_player_loss(&user_id, DAY + 1, &database).await, 100);
+        assert_eq!(find_player_loss(&user_id, DAY + 2, &database).await, 0);
+    }
+}
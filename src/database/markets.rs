@@ -0,0 +1,413 @@
+use sqlx::{Pool, Sqlite};
+use tracing::instrument;
+
+use crate::database::house::change_house_balance;
+use crate::database::limits::record_player_loss;
+use crate::database::players::{credit_player, save_player, Player};
+
+pub const STATUS_OPEN: &str = "open";
+pub const STATUS_CLOSED: &str = "closed";
+pub const STATUS_RESOLVED: &str = "resolved";
+pub const STATUS_CANCELLED: &str = "cancelled";
+
+#[derive(Clone, Debug)]
+pub struct Market {
+    pub id: i64,
+    pub question: String,
+    pub channel_id: String,
+    pub message_id: Option<String>,
+    pub status: String,
+    pub created_by: String,
+    pub created_ts: i64,
+    pub closed_ts: Option<i64>,
+    /// Administrator who resolved or cancelled the market.
+    pub resolved_by: Option<String>,
+    pub resolved_ts: Option<i64>,
+    pub winning_outcome: Option<i64>,
+    pub pool: i64,
+    pub house_take: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct MarketOutcome {
+    pub outcome: i64,
+    pub name: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct MarketPosition {
+    pub id: i64,
+    pub discord_user_id: String,
+    pub outcome: i64,
+    pub amount: i64,
+    pub created_ts: i64,
+    pub payout: Option<i64>,
+}
+
+/// Creates the market together with its outcomes.
+#[instrument]
+pub async fn create_market(
+    market: &mut Market,
+    outcomes: &[MarketOutcome],
+    database: &Pool<Sqlite>,
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    let Ok(result) = sqlx::query!(
+        "INSERT INTO markets (question, channel_id, message_id, status, created_by, created_ts) VALUES (?, ?, ?, ?, ?, ?)",
+        market.question,
+        market.channel_id,
+        market.message_id,
+        market.status,
+        market.created_by,
+        market.created_ts
+    )
+    .execute(&mut *tx)
+    .await
+    else {
+        return false;
+    };
+
+    market.id = result.last_insert_rowid();
+
+    for outcome in outcomes {
+        let inserted = sqlx::query!(
+            "INSERT INTO market_outcomes (market_id, outcome, name) VALUES (?, ?, ?)",
+            market.id,
+            outcome.outcome,
+            outcome.name
+        )
+        .execute(&mut *tx)
+        .await
+        .is_ok();
+
+        if !inserted {
+            return false;
+        }
+    }
+
+    tx.commit().await.is_ok()
+}
+
+#[instrument]
+pub async fn find_market(id: i64, database: &Pool<Sqlite>) -> Option<Market> {
+    sqlx::query_as!(
+        Market,
+        "SELECT id as \"id!\", question, channel_id, message_id, status, created_by, created_ts, closed_ts, resolved_by, resolved_ts, winning_outcome, pool, house_take
+         FROM markets WHERE id = ?",
+        id
+    )
+    .fetch_optional(database)
+    .await
+    .unwrap_or(None)
+}
+
+/// Markets not resolved or cancelled yet, the oldest first.
+#[instrument]
+pub async fn load_unsettled_markets(database: &Pool<Sqlite>) -> Vec<Market> {
+    sqlx::query_as!(
+        Market,
+        "SELECT id as \"id!\", question, channel_id, message_id, status, created_by, created_ts, closed_ts, resolved_by, resolved_ts, winning_outcome, pool, house_take
+         FROM markets WHERE status IN (?, ?) ORDER BY id",
+        STATUS_OPEN,
+        STATUS_CLOSED
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+/// Saves the message and closes the betting, settling is left to
+/// `settle_market`.
+#[instrument]
+pub async fn update_market(market: &Market, database: &Pool<Sqlite>) -> bool {
+    sqlx::query!(
+        "UPDATE markets SET message_id = ?, status = ?, closed_ts = ? WHERE id = ? AND status IN (?, ?)",
+        market.message_id,
+        market.status,
+        market.closed_ts,
+        market.id,
+        STATUS_OPEN,
+        STATUS_CLOSED
+    )
+    .execute(database)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+#[instrument]
+pub async fn load_market_outcomes(market_id: i64, database: &Pool<Sqlite>) -> Vec<MarketOutcome> {
+    sqlx::query_as!(
+        MarketOutcome,
+        "SELECT outcome, name FROM market_outcomes WHERE market_id = ? ORDER BY outcome",
+        market_id
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+/// Saves the player with the stake taken from the balance and records the
+/// stake in one transaction. Nothing is saved once the market is no longer
+/// open.
+#[instrument]
+pub async fn place_market_position(
+    player: &mut Player,
+    market_id: i64,
+    position: &mut MarketPosition,
+    day: i64,
+    database: &Pool<Sqlite>,
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    if !save_player(player, &mut tx).await
+        || !record_player_loss(&position.discord_user_id, position.amount, day, &mut tx).await
+    {
+        return false;
+    }
+
+    let inserted = sqlx::query!(
+        "INSERT INTO market_positions (market_id, discord_user_id, outcome, amount, created_ts)
+         SELECT ?, ?, ?, ?, ? WHERE EXISTS (SELECT 1 FROM markets WHERE id = ? AND status = ?)",
+        market_id,
+        position.discord_user_id,
+        position.outcome,
+        position.amount,
+        position.created_ts,
+        market_id,
+        STATUS_OPEN
+    )
+    .execute(&mut *tx)
+    .await
+    .ok()
+    .filter(|result| result.rows_affected() > 0);
+
+    let Some(result) = inserted else {
+        return false;
+    };
+
+    if tx.commit().await.is_err() {
+        return false;
+    }
+
+    position.id = result.last_insert_rowid();
+    player.version += 1;
+    true
+}
+
+#[instrument]
+pub async fn load_market_positions(market_id: i64, database: &Pool<Sqlite>) -> Vec<MarketPosition> {
+    sqlx::query_as!(
+        MarketPosition,
+        "SELECT id as \"id!\", discord_user_id, outcome, amount, created_ts, payout FROM market_positions WHERE market_id = ? ORDER BY id",
+        market_id
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+/// Saves the resolution of the market, pays out the positions and gives the
+/// house its take in one transaction. Fails when the market was settled in
+/// the meantime, so nobody gets paid twice.
+#[instrument]
+pub async fn settle_market(
+    market: &Market,
+    positions: &[MarketPosition],
+    day: i64,
+    database: &Pool<Sqlite>,
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    let updated = sqlx::query!(
+        "UPDATE markets SET status = ?, closed_ts = ?, resolved_by = ?, resolved_ts = ?, winning_outcome = ?, pool = ?, house_take = ?
+         WHERE id = ? AND status IN (?, ?)",
+        market.status,
+        market.closed_ts,
+        market.resolved_by,
+        market.resolved_ts,
+        market.winning_outcome,
+        market.pool,
+        market.house_take,
+        market.id,
+        STATUS_OPEN,
+        STATUS_CLOSED
+    )
+    .execute(&mut *tx)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0);
+
+    if !updated {
+        return false;
+    }
+
+    for position in positions {
+        let payout = position.payout.unwrap_or_default();
+
+        let saved = sqlx::query!(
+            "UPDATE market_positions SET payout = ? WHERE id = ?",
+            payout,
+            position.id
+        )
+        .execute(&mut *tx)
+        .await
+        .is_ok();
+
+        if !saved {
+            return false;
+        }
+
+        if payout == 0 {
+            continue;
+        }
+
+        if !credit_player(&position.discord_user_id, payout, &mut tx).await
+            || !record_player_loss(&position.discord_user_id, -payout, day, &mut tx).await
+        {
+            return false;
+        }
+    }
+
+    if market.house_take > 0
+        && !change_house_balance(market.house_take, "markets", day, &mut tx).await
+    {
+        return false;
+    }
+
+    tx.commit().await.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::house::find_house_balance;
+    use crate::database::limits::find_player_loss;
+    use crate::database::players::{create_player, find_player};
+
+    const TS: i64 = 1_760_000_000;
+    const DAY: i64 = 20_370;
+
+    async fn open_market(database: &Pool<Sqlite>) -> Market {
+        let mut market = Market {
+            id: 0,
+            question: "Kas Eesti võidab?".into(),
+            channel_id: "1".into(),
+            message_id: None,
+            status: STATUS_OPEN.into(),
+            created_by: "1".into(),
+            created_ts: TS,
+            closed_ts: None,
+            resolved_by: None,
+            resolved_ts: None,
+            winning_outcome: None,
+            pool: 0,
+            house_take: 0,
+        };
+        assert!(create_market(&mut market, &[], database).await);
+        market
+    }
+
+    async fn place(
+        market: &Market,
+        user_id: &str,
+        outcome: i64,
+        amount: i64,
+        database: &Pool<Sqlite>,
+    ) -> bool {
+        let mut player = match find_player(&user_id.to_string(), database).await {
+            Some(player) => player,
+            None => create_player(&user_id.to_string(), TS, database)
+                .await
+                .unwrap(),
+        };
+        player.balance -= amount;
+        let mut position = MarketPosition {
+            id: 0,
+            discord_user_id: user_id.into(),
+            outcome,
+            amount,
+            created_ts: TS,
+            payout: None,
+        };
+
+        place_market_position(&mut player, market.id, &mut position, DAY, database).await
+    }
+
+    async fn balance(user_id: &str, database: &Pool<Sqlite>) -> i64 {
+        find_player(&user_id.to_string(), database)
+            .await
+            .unwrap()
+            .balance
+    }
+
+    #[tokio::test]
+    async fn stake_is_not_taken_once_the_market_is_closed() {
+        let database = crate::database::in_memory().await;
+        let mut market = open_market(&database).await;
+
+        assert!(place(&market, "1", 1, 500, &database).await);
+        market.status = STATUS_CLOSED.into();
+        assert!(update_market(&market, &database).await);
+        assert!(!place(&market, "1", 1, 300, &database).await);
+
+        assert_eq!(balance("1", &database).await, 4500);
+        assert_eq!(
+            find_player_loss(&"1".to_string(), DAY, &database).await,
+            500
+        );
+    }
+
+    #[tokio::test]
+    async fn resolved_market_pays_the_winners_and_the_house_once() {
+        let database = crate::database::in_memory().await;
+        let mut market = open_market(&database).await;
+        let house = find_house_balance(&database).await;
+
+        assert!(place(&market, "1", 1, 600, &database).await);
+        assert!(place(&market, "2", 2, 400, &database).await);
+
+        let mut positions = load_market_positions(market.id, &database).await;
+        positions[0].payout = Some(950);
+        positions[1].payout = Some(0);
+        market.status = STATUS_RESOLVED.into();
+        market.winning_outcome = Some(1);
+        market.pool = 1000;
+        market.house_take = 50;
+        assert!(settle_market(&market, &positions, DAY, &database).await);
+        assert!(!settle_market(&market, &positions, DAY, &database).await);
+
+        assert_eq!(balance("1", &database).await, 5350);
+        assert_eq!(balance("2", &database).await, 4600);
+        assert_eq!(find_house_balance(&database).await, house + 50);
+        assert_eq!(
+            find_player_loss(&"1".to_string(), DAY, &database).await,
+            -350
+        );
+    }
+
+    #[tokio::test]
+    async fn cancelled_market_refunds_the_stakes() {
+        let database = crate::database::in_memory().await;
+        let mut market = open_market(&database).await;
+        let house = find_house_balance(&database).await;
+
+        assert!(place(&market, "1", 1, 600, &database).await);
+
+        let mut positions = load_market_positions(market.id, &database).await;
+        positions[0].payout = Some(600);
+        market.status = STATUS_CANCELLED.into();
+        assert!(settle_market(&market, &positions, DAY, &database).await);
+
+        assert_eq!(balance("1", &database).await, 5000);
+        assert_eq!(find_house_balance(&database).await, house);
+        assert_eq!(find_player_loss(&"1".to_string(), DAY, &database).await, 0);
+    }
+}
@@ -0,0 +1,302 @@
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tracing::instrument;
+
+use crate::database::house::{record_player_bet, transfer_from_house};
+use crate::database::players::Player;
+
+pub const STATUS_ACTIVE: &str = "active";
+pub const STATUS_CASHED_OUT: &str = "cashed_out";
+pub const STATUS_LOST: &str = "lost";
+pub const STATUS_REFUNDED: &str = "refunded";
+pub const STATUS_FORFEITED: &str = "forfeited";
+
+#[derive(Clone, Debug)]
+pub struct MinesGame {
+    pub id: i64,
+    pub discord_user_id: String,
+    pub channel_id: String,
+    pub message_id: Option<String>,
+    pub amount: i64,
+    pub mines: i64,
+    /// Comma separated indexes of the tiles hiding a mine.
+    pub mine_tiles: String,
+    /// Comma separated indexes of the tiles opened so far, in order.
+    pub revealed_tiles: String,
+    pub status: String,
+    pub payout: Option<i64>,
+    /// Most the game pays out, what the house could cover when the bet was
+    /// placed.
+    pub max_payout: Option<i64>,
+    /// Multiplier of the payouts from the events running when the game
+    /// started.
+    pub boost: f64,
+    pub created_ts: i64,
+    pub updated_ts: i64,
+    pub version: i64,
+}
+
+/// Takes the bet of the player and creates the game in the same
+/// transaction. Fails when the player was changed in the meantime or already
+/// has an active game.
+#[instrument]
+pub async fn start_mines_game(
+    player: &mut Player,
+    game: &mut MinesGame,
+    day: i64,
+    database: &Pool<Sqlite>,
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    if !record_player_bet(player, game.amount, 0, "mines", day, &mut tx).await {
+        return false;
+    }
+
+    let created = sqlx::query!(
+        "INSERT INTO mines_games (discord_user_id, channel_id, message_id, amount, mines, mine_tiles, revealed_tiles, status, max_payout, boost, created_ts, updated_ts)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        game.discord_user_id,
+        game.channel_id,
+        game.message_id,
+        game.amount,
+        game.mines,
+        game.mine_tiles,
+        game.revealed_tiles,
+        game.status,
+        game.max_payout,
+        game.boost,
+        game.created_ts,
+        game.updated_ts
+    )
+    .execute(&mut *tx)
+    .await
+    .ok();
+
+    let Some(created) = created else {
+        return false;
+    };
+
+    if tx.commit().await.is_err() {
+        return false;
+    }
+
+    game.id = created.last_insert_rowid();
+    player.version += 1;
+    true
+}
+
+#[instrument]
+pub async fn find_mines_game(id: i64, database: &Pool<Sqlite>) -> Option<MinesGame> {
+    sqlx::query_as!(
+        MinesGame,
+        "SELECT id as \"id!\", discord_user_id, channel_id, message_id, amount, mines, mine_tiles, revealed_tiles, status, payout, max_payout, boost, created_ts, updated_ts, version
+         FROM mines_games WHERE id = ?",
+        id
+    )
+    .fetch_optional(database)
+    .await
+    .unwrap_or(None)
+}
+
+#[instrument]
+pub async fn find_active_mines_game(
+    user_id: &String,
+    database: &Pool<Sqlite>,
+) -> Option<MinesGame> {
+    sqlx::query_as!(
+        MinesGame,
+        "SELECT id as \"id!\", discord_user_id, channel_id, message_id, amount, mines, mine_tiles, revealed_tiles, status, payout, max_payout, boost, created_ts, updated_ts, version
+         FROM mines_games WHERE discord_user_id = ? AND status = ?",
+        user_id,
+        STATUS_ACTIVE
+    )
+    .fetch_optional(database)
+    .await
+    .unwrap_or(None)
+}
+
+/// Active games nobody has touched since the given timestamp.
+#[instrument]
+pub async fn find_expired_mines_games(before_ts: i64, database: &Pool<Sqlite>) -> Vec<MinesGame> {
+    sqlx::query_as!(
+        MinesGame,
+        "SELECT id as \"id!\", discord_user_id, channel_id, message_id, amount, mines, mine_tiles, revealed_tiles, status, payout, max_payout, boost, created_ts, updated_ts, version
+         FROM mines_games WHERE status = ? AND updated_ts < ?",
+        STATUS_ACTIVE,
+        before_ts
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+async fn update_mines_game(game: &MinesGame, connection: &mut SqliteConnection) -> bool {
+    let next_version = game.version + 1;
+
+    sqlx::query!(
+        "UPDATE mines_games SET message_id = ?, revealed_tiles = ?, status = ?, payout = ?, updated_ts = ?, version = ?
+         WHERE id = ? AND version = ?",
+        game.message_id,
+        game.revealed_tiles,
+        game.status,
+        game.payout,
+        game.updated_ts,
+        next_version,
+        game.id,
+        game.version
+    )
+    .execute(&mut *connection)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+/// Saves the game unless it was changed by someone else in the meantime.
+#[instrument]
+pub async fn save_mines_game(game: &mut MinesGame, database: &Pool<Sqlite>) -> bool {
+    let Ok(mut connection) = database.acquire().await else {
+        return false;
+    };
+
+    if !update_mines_game(game, &mut connection).await {
+        return false;
+    }
+
+    game.version += 1;
+    true
+}
+
+/// Saves the finished game and pays its payout from the house in the same
+/// transaction, unless the game was changed by someone else in the meantime.
+#[instrument]
+pub async fn settle_mines_game(game: &mut MinesGame, day: i64, database: &Pool<Sqlite>) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    if !update_mines_game(game, &mut tx).await {
+        return false;
+    }
+
+    let payout = game.payout.unwrap_or_default();
+    if payout > 0
+        && !transfer_from_house(&game.discord_user_id, payout, "mines", day, &mut tx).await
+    {
+        return false;
+    }
+
+    if tx.commit().await.is_err() {
+        return false;
+    }
+
+    game.version += 1;
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::house::find_house_balance;
+    use crate::database::players::{create_player, find_player};
+
+    const TS: i64 = 1_760_000_000;
+    const DAY: i64 = 20_370;
+
+    fn game(user_id: &str, amount: i64) -> MinesGame {
+        MinesGame {
+            id: 0,
+            discord_user_id: user_id.to_string(),
+            channel_id: "1".to_string(),
+            message_id: None,
+            amount,
+            mines: 3,
+            mine_tiles: "1,2,3".to_string(),
+            revealed_tiles: String::new(),
+            status: STATUS_ACTIVE.into(),
+            payout: None,
+            max_payout: None,
+            boost: 1.0,
+            created_ts: TS,
+            updated_ts: TS,
+            version: 0,
+        }
+    }
+
+    async fn start(user_id: &str, amount: i64, database: &Pool<Sqlite>) -> Option<MinesGame> {
+        let mut player = find_player(&user_id.to_string(), database).await?;
+        player.balance -= amount;
+        let mut game = game(user_id, amount);
+
+        start_mines_game(&mut player, &mut game, DAY, database)
+            .await
+            .then_some(game)
+    }
+
+    #[tokio::test]
+    async fn second_game_takes_no_bet() {
+        let database = crate::database::in_memory().await;
+        let user_id = "1".to_string();
+        create_player(&user_id, TS, &database).await.unwrap();
+        let house = find_house_balance(&database).await;
+
+        assert!(start(&user_id, 500, &database).await.is_some());
+        assert!(start(&user_id, 700, &database).await.is_none());
+
+        assert_eq!(
+            find_player(&user_id, &database).await.unwrap().balance,
+            4500
+        );
+        assert_eq!(find_house_balance(&database).await, house + 500);
+    }
+
+    #[tokio::test]
+    async fn cash_out_pays_once_from_the_house() {
+        let database = crate::database::in_memory().await;
+        let user_id = "1".to_string();
+        create_player(&user_id, TS, &database).await.unwrap();
+        let house = find_house_balance(&database).await;
+
+        let mut game = start(&user_id, 500, &database).await.unwrap();
+        let stale = game.clone();
+        game.status = STATUS_CASHED_OUT.into();
+        game.payout = Some(800);
+        assert!(settle_mines_game(&mut game, DAY, &database).await);
+
+        // The game can't be cashed out or expired again.
+        let mut stale = stale;
+        stale.status = STATUS_REFUNDED.into();
+        stale.payout = Some(500);
+        assert!(!settle_mines_game(&mut stale, DAY, &database).await);
+
+        assert_eq!(
+            find_player(&user_id, &database).await.unwrap().balance,
+            5300
+        );
+        assert_eq!(find_house_balance(&database).await, house - 300);
+        assert_eq!(
+            find_mines_game(game.id, &database).await.unwrap().status,
+            STATUS_CASHED_OUT
+        );
+    }
+
+    #[tokio::test]
+    async fn untouched_game_is_refunded() {
+        let database = crate::database::in_memory().await;
+        let user_id = "1".to_string();
+        create_player(&user_id, TS, &database).await.unwrap();
+        let house = find_house_balance(&database).await;
+
+        let mut game = start(&user_id, 500, &database).await.unwrap();
+        game.status = STATUS_REFUNDED.into();
+        game.payout = Some(game.amount);
+        assert!(settle_mines_game(&mut game, DAY, &database).await);
+
+        assert_eq!(
+            find_player(&user_id, &database).await.unwrap().balance,
+            5000
+        );
+        assert_eq!(find_house_balance(&database).await, house);
+        assert!(find_active_mines_game(&user_id, &database).await.is_none());
+    }
+}
@@ -1,9 +1,26 @@
+use sqlx::migrate::Migrate;
 use sqlx::{Pool, Sqlite};
 use tracing::instrument;
 
 use crate::internal::settings::Settings;
 
+pub mod achievements;
+pub mod backup;
+pub mod charity;
+pub mod craps;
+pub mod crash;
+pub mod events;
+pub mod house;
+pub mod jackpot;
+pub mod limits;
+pub mod markets;
+pub mod mines;
 pub mod players;
+pub mod poker;
+pub mod races;
+pub mod repository;
+pub mod roles;
+pub mod transfers;
 
 #[instrument]
 pub async fn init(settings: &Settings) -> Pool<Sqlite> {
@@ -18,9 +35,51 @@ pub async fn init(settings: &Settings) -> Pool<Sqlite> {
         .expect("Could not connect to database")
 }
 
+/// Whether the database answers a trivial query.
+#[instrument]
+pub async fn is_reachable(database: &Pool<Sqlite>) -> bool {
+    sqlx::query("SELECT 1").execute(database).await.is_ok()
+}
+
+/// Migrations of the binary not applied to the database yet, `None` when the
+/// applied migrations can't be read.
+#[instrument]
+pub async fn pending_migrations(database: &Pool<Sqlite>) -> Option<usize> {
+    let mut connection = database.acquire().await.ok()?;
+    let applied = connection
+        .list_applied_migrations()
+        .await
+        .ok()?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect::<Vec<_>>();
+
+    Some(
+        sqlx::migrate!("./migrations/sqlite")
+            .iter()
+            .filter(|migration| migration.migration_type.is_up_migration())
+            .filter(|migration| !applied.contains(&migration.version))
+            .count(),
+    )
+}
+
+/// Fresh migrated database living only as long as the pool, for tests.
+#[cfg(test)]
+pub async fn in_memory() -> Pool<Sqlite> {
+    let database = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("Could not create database");
+
+    migrate(&database).await;
+
+    database
+}
+
 #[instrument]
 pub async fn migrate(database: &Pool<Sqlite>) {
-    sqlx::migrate!("./migrations")
+    sqlx::migrate!("./migrations/sqlite")
         .run(database)
         .await
         .expect("Could not run database migrations");
@@ -1,10 +1,13 @@
-use chrono::Utc;
 use poise::serenity_prelude as serenity;
-use sqlx::{Pool, Sqlite};
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite, SqliteConnection};
 use std::collections::BTreeMap;
 use tracing::instrument;
 
-#[derive(Clone, Debug)]
+use crate::database::charity::take_from_charity_pool;
+use crate::database::house::change_house_balance;
+
+#[derive(Clone, Debug, Deserialize, Serialize, sqlx::FromRow)]
 pub struct Player {
     pub discord_user_id: String,
     pub balance: i64,
@@ -31,17 +34,47 @@ pub fn get_idle_status(idle_time: i64) -> IdleStatus {
     }
 }
 
+/// Idle players give `balance / rate` to charity at every feeding, the rate
+/// falling the longer they have been idle.
+#[derive(Clone, Debug)]
+pub struct CharityRates {
+    pub away: i64,
+    pub sleeping: i64,
+    pub missing: i64,
+}
+
+impl Default for CharityRates {
+    fn default() -> Self {
+        CharityRates {
+            away: 10,
+            sleeping: 6,
+            missing: 3,
+        }
+    }
+}
+
 impl Player {
+    /// New player with the starting balance.
+    pub fn new(user_id: &str, ts: i64) -> Self {
+        Player {
+            discord_user_id: user_id.to_string(),
+            balance: 5000,
+            last_feed_ts: ts,
+            idle_since_ts: ts,
+            version: 1,
+        }
+    }
+
     pub fn idle_status(&self, ts: i64) -> IdleStatus {
         get_idle_status(ts - self.idle_since_ts)
     }
 
-    pub fn charity_amount(&self, ts: i64) -> i64 {
+    pub fn charity_amount(&self, ts: i64, rates: &CharityRates) -> i64 {
         match self.idle_status(ts) {
             IdleStatus::Active => 0,
-            IdleStatus::Away => self.balance / 10,
-            IdleStatus::Sleeping => self.balance / 6,
-            IdleStatus::Missing => self.balance / 3,
+            IdleStatus::Away => self.balance / rates.away.max(1),
+            IdleStatus::Sleeping => self.balance / rates.sleeping.max(1),
+            IdleStatus::Missing => self.balance / rates.missing.max(1),
             IdleStatus::Dead => self.balance,
         }
     }
@@ -72,16 +105,8 @@ pub async fn find_unfeeded_players(ts: i64, database: &Pool<Sqlite>) -> Vec<Play
 }
 
 #[instrument]
-pub async fn create_player(user_id: &String, database: &Pool<Sqlite>) -> Option<Player> {
-    let current_ts = Utc::now().timestamp();
-
-    let player = Player {
-        discord_user_id: user_id.to_string(),
-        balance: 5000,
-        last_feed_ts: current_ts,
-        idle_since_ts: current_ts,
-        version: 1,
-    };
+pub async fn create_player(user_id: &String, ts: i64, database: &Pool<Sqlite>) -> Option<Player> {
+    let player = Player::new(user_id, ts);
 
     sqlx::query!(
         "INSERT INTO players (discord_user_id, balance, last_feed_ts, idle_since_ts, version) VALUES (?, ?, ?, ?, ?)",
@@ -97,12 +122,16 @@ pub async fn create_player(user_id: &String, database: &Pool<Sqlite>) -> Option<
 }
 
 #[instrument]
-pub async fn remove_player(player: &mut Player, database: &Pool<Sqlite>) -> bool {
+pub async fn update_player(player: &mut Player, database: &Pool<Sqlite>) -> bool {
     let current_version = player.version;
     player.version += 1;
 
     sqlx::query!(
-        "DELETE FROM players WHERE discord_user_id = ? AND version = ?",
+        "UPDATE players SET balance = ?, last_feed_ts = ?, idle_since_ts = ?, version = ? WHERE discord_user_id = ? AND version = ?",
+        player.balance,
+        player.last_feed_ts,
+        player.idle_since_ts,
+        player.version,
         player.discord_user_id,
         current_version
     )
@@ -112,48 +141,161 @@ pub async fn remove_player(player: &mut Player, database: &Pool<Sqlite>) -> bool
     .map_or_else(|| false, |result| result.rows_affected() > 0)
 }
 
-#[instrument]
-pub async fn update_player(player: &mut Player, database: &Pool<Sqlite>) -> bool {
-    let current_version = player.version;
-    player.version += 1;
+/// Saves the player like `update_player` within the transaction of the
+/// caller, who bumps `player.version` once the transaction is committed.
+pub async fn save_player(player: &Player, connection: &mut SqliteConnection) -> bool {
+    let next_version = player.version + 1;
 
     sqlx::query!(
         "UPDATE players SET balance = ?, last_feed_ts = ?, idle_since_ts = ?, version = ? WHERE discord_user_id = ? AND version = ?",
         player.balance,
         player.last_feed_ts,
         player.idle_since_ts,
-        player.version,
+        next_version,
         player.discord_user_id,
-        current_version
+        player.version
     )
-    .execute(database)
+    .execute(&mut *connection)
     .await
     .ok()
     .map_or_else(|| false, |result| result.rows_affected() > 0)
 }
 
-#[instrument]
-pub async fn load_leaderboard(
+/// Saves a feeding in one transaction, nothing is saved when any of the
+/// players was changed in the meantime or the charity pool holds less than
+/// `charity_pool`.
+#[instrument(skip(removed, updated))]
+pub async fn save_feeding(
+    removed: &[Player],
+    updated: &[Player],
+    charity_pool: i64,
+    leftover: i64,
+    day: i64,
     database: &Pool<Sqlite>,
-) -> Vec<(serenity::UserId, i64, usize, usize, i64)> {
-    let grouped_by_balance: BTreeMap<i64, Vec<(serenity::UserId, i64)>> = sqlx::query_as!(
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    for player in removed {
+        let deleted = sqlx::query!(
+            "DELETE FROM players WHERE discord_user_id = ? AND version = ?",
+            player.discord_user_id,
+            player.version
+        )
+        .execute(&mut *tx)
+        .await
+        .ok()
+        .map_or_else(|| false, |result| result.rows_affected() > 0);
+
+        if !deleted {
+            return false;
+        }
+    }
+
+    for player in updated {
+        if !save_player(player, &mut tx).await {
+            return false;
+        }
+    }
+
+    if charity_pool > 0 && !take_from_charity_pool(charity_pool, &mut tx).await {
+        return false;
+    }
+
+    if leftover > 0 && !change_house_balance(leftover, "charity", day, &mut tx).await {
+        return false;
+    }
+
+    tx.commit().await.is_ok()
+}
+
+/// Saves an imported player within the transaction of the import: a new
+/// player without `version`, otherwise the stored player if it is still at
+/// `version`.
+pub async fn import_player(
+    player: &Player,
+    version: Option<i64>,
+    connection: &mut SqliteConnection,
+) -> bool {
+    let saved = match version {
+        None => sqlx::query!(
+            "INSERT INTO players (discord_user_id, balance, last_feed_ts, idle_since_ts, version) VALUES (?, ?, ?, ?, 1)",
+            player.discord_user_id,
+            player.balance,
+            player.last_feed_ts,
+            player.idle_since_ts
+        )
+        .execute(&mut *connection)
+        .await,
+        Some(version) => sqlx::query!(
+            "UPDATE players SET balance = ?, last_feed_ts = ?, idle_since_ts = ?, version = version + 1 WHERE discord_user_id = ? AND version = ?",
+            player.balance,
+            player.last_feed_ts,
+            player.idle_since_ts,
+            player.discord_user_id,
+            version
+        )
+        .execute(&mut *connection)
+        .await,
+    };
+
+    saved.map_or_else(|_| false, |result| result.rows_affected() > 0)
+}
+
+/// Adds `amount` to the balance of the player without a version check within
+/// the transaction of the caller, use only for payouts and refunds where the
+/// current balance does not matter.
+pub async fn credit_player(user_id: &str, amount: i64, connection: &mut SqliteConnection) -> bool {
+    sqlx::query!(
+        "UPDATE players SET balance = balance + ?, version = version + 1 WHERE discord_user_id = ?",
+        amount,
+        user_id
+    )
+    .execute(&mut *connection)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+#[instrument]
+pub async fn load_players_by_balance(database: &Pool<Sqlite>) -> Vec<Player> {
+    sqlx::query_as!(
         Player,
         "SELECT discord_user_id, balance, last_feed_ts, idle_since_ts, version FROM players ORDER BY balance DESC"
     )
     .fetch_all(database)
     .await
     .unwrap_or_else(|_| Vec::new())
-    .iter()
-    .map(|p| {
-        (
-            (serenity::UserId::new(p.discord_user_id.parse::<u64>().unwrap()), p.idle_since_ts),
-            p.balance,
-        )
-    })
-    .fold(BTreeMap::new(), |mut acc, (row, balance)| {
-        acc.entry(balance).or_default().push(row);
-        acc
-    });
+}
+
+/// Potatoes held by all the players.
+#[instrument]
+pub async fn find_circulation(database: &Pool<Sqlite>) -> i64 {
+    sqlx::query_scalar!("SELECT COALESCE(SUM(balance), 0) as \"sum!: i64\" FROM players")
+        .fetch_one(database)
+        .await
+        .unwrap_or_default()
+}
+
+/// Leaderboard positions of the players, players with the same balance share
+/// the range of positions from `min_pos` to `max_pos`.
+pub fn rank_players(players: &[Player]) -> Vec<(serenity::UserId, i64, usize, usize, i64)> {
+    let grouped_by_balance: BTreeMap<i64, Vec<(serenity::UserId, i64)>> = players
+        .iter()
+        .map(|p| {
+            (
+                (
+                    serenity::UserId::new(p.discord_user_id.parse::<u64>().unwrap()),
+                    p.idle_since_ts,
+                ),
+                p.balance,
+            )
+        })
+        .fold(BTreeMap::new(), |mut acc, (row, balance)| {
+            acc.entry(balance).or_default().push(row);
+            acc
+        });
     grouped_by_balance
         .iter()
         .rev()
@@ -0,0 +1,441 @@
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tracing::instrument;
+
+use crate::database::house::change_house_balance;
+use crate::database::limits::record_player_loss;
+use crate::database::players::{credit_player, save_player, Player};
+
+pub const STATUS_WAITING: &str = "waiting";
+pub const STATUS_PLAYING: &str = "playing";
+pub const STATUS_CLOSED: &str = "closed";
+
+#[derive(Clone, Debug)]
+pub struct PokerTable {
+    pub id: i64,
+    pub channel_id: String,
+    pub message_id: Option<String>,
+    pub owner_id: String,
+    pub buy_in: i64,
+    pub small_blind: i64,
+    pub big_blind: i64,
+    pub status: String,
+    pub hand_no: i64,
+    pub street: String,
+    pub dealer_seat: i64,
+    pub to_act: Option<i64>,
+    pub current_bet: i64,
+    pub min_raise: i64,
+    pub deck: String,
+    pub board: String,
+    pub last_result: Option<String>,
+    pub version: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct PokerSeat {
+    pub seat: i64,
+    pub discord_user_id: String,
+    pub stack: i64,
+    pub hole_cards: String,
+    /// Chips put in during the current street.
+    pub bet: i64,
+    /// Chips put in during the whole hand, used for side pots.
+    pub committed: i64,
+    pub in_hand: bool,
+    pub folded: bool,
+    pub all_in: bool,
+    pub acted: bool,
+    pub leaving: bool,
+}
+
+/// Opens the table with the owner seated in one transaction, the buy-in is
+/// taken from the saved player. Fails when the channel has an open table
+/// already.
+#[instrument(skip(seats))]
+pub async fn open_poker_table(
+    table: &mut PokerTable,
+    seats: &[PokerSeat],
+    player: &mut Player,
+    day: i64,
+    database: &Pool<Sqlite>,
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    let Ok(result) = sqlx::query!(
+        "INSERT INTO poker_tables (channel_id, message_id, owner_id, buy_in, small_blind, big_blind, status, version)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        table.channel_id,
+        table.message_id,
+        table.owner_id,
+        table.buy_in,
+        table.small_blind,
+        table.big_blind,
+        table.status,
+        table.version
+    )
+    .execute(&mut *tx)
+    .await
+    else {
+        return false;
+    };
+
+    let table_id = result.last_insert_rowid();
+
+    if !insert_poker_seats(table_id, seats, &mut tx).await
+        || !save_player(player, &mut tx).await
+        || !record_player_loss(&player.discord_user_id, table.buy_in, day, &mut tx).await
+        || tx.commit().await.is_err()
+    {
+        return false;
+    }
+
+    table.id = table_id;
+    player.version += 1;
+    true
+}
+
+#[instrument]
+pub async fn find_poker_table(id: i64, database: &Pool<Sqlite>) -> Option<PokerTable> {
+    sqlx::query_as!(
+        PokerTable,
+        "SELECT id as \"id!\", channel_id, message_id, owner_id, buy_in, small_blind, big_blind, status, hand_no, street,
+                dealer_seat, to_act, current_bet, min_raise, deck, board, last_result, version
+         FROM poker_tables WHERE id = ?",
+        id
+    )
+    .fetch_optional(database)
+    .await
+    .unwrap_or(None)
+}
+
+#[instrument]
+pub async fn find_open_poker_table(
+    channel_id: &String,
+    database: &Pool<Sqlite>,
+) -> Option<PokerTable> {
+    sqlx::query_as!(
+        PokerTable,
+        "SELECT id as \"id!\", channel_id, message_id, owner_id, buy_in, small_blind, big_blind, status, hand_no, street,
+                dealer_seat, to_act, current_bet, min_raise, deck, board, last_result, version
+         FROM poker_tables WHERE channel_id = ? AND status IN (?, ?)",
+        channel_id,
+        STATUS_WAITING,
+        STATUS_PLAYING
+    )
+    .fetch_optional(database)
+    .await
+    .unwrap_or(None)
+}
+
+#[instrument]
+pub async fn load_poker_seats(table_id: i64, database: &Pool<Sqlite>) -> Vec<PokerSeat> {
+    sqlx::query_as!(
+        PokerSeat,
+        "SELECT seat, discord_user_id, stack, hole_cards, bet, committed, in_hand, folded, all_in, acted, leaving
+         FROM poker_seats WHERE table_id = ? ORDER BY seat",
+        table_id
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+/// Saves the table together with all of its seats, fails when somebody else
+/// has changed the table in the meantime.
+#[instrument]
+pub async fn save_poker_table(
+    table: &mut PokerTable,
+    seats: &[PokerSeat],
+    database: &Pool<Sqlite>,
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    if !update_poker_table(table, seats, &mut tx).await || tx.commit().await.is_err() {
+        return false;
+    }
+
+    table.version += 1;
+    true
+}
+
+/// Saves the table like `save_poker_table` and moves the potatoes in the same
+/// transaction: the buy-in of `buyer` saved with the balance already taken,
+/// the stacks of the seats cashed out and the rake of the house.
+#[instrument(skip(seats, cash_outs))]
+pub async fn settle_poker_table(
+    table: &mut PokerTable,
+    seats: &[PokerSeat],
+    buyer: Option<&Player>,
+    cash_outs: &[PokerSeat],
+    rake: i64,
+    day: i64,
+    database: &Pool<Sqlite>,
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    if !update_poker_table(table, seats, &mut tx).await {
+        return false;
+    }
+
+    if let Some(player) = buyer {
+        if !save_player(player, &mut tx).await
+            || !record_player_loss(&player.discord_user_id, table.buy_in, day, &mut tx).await
+        {
+            return false;
+        }
+    }
+
+    for seat in cash_outs.iter().filter(|seat| seat.stack > 0) {
+        if !credit_player(&seat.discord_user_id, seat.stack, &mut tx).await
+            || !record_player_loss(&seat.discord_user_id, -seat.stack, day, &mut tx).await
+        {
+            return false;
+        }
+    }
+
+    if rake > 0 && !change_house_balance(rake, "poker", day, &mut tx).await {
+        return false;
+    }
+
+    if tx.commit().await.is_err() {
+        return false;
+    }
+
+    table.version += 1;
+    true
+}
+
+/// Saves the table and its seats within the transaction of the caller, who
+/// bumps `table.version` once the transaction is committed.
+async fn update_poker_table(
+    table: &PokerTable,
+    seats: &[PokerSeat],
+    connection: &mut SqliteConnection,
+) -> bool {
+    let current_version = table.version;
+    let next_version = current_version + 1;
+
+    let updated = sqlx::query!(
+        "UPDATE poker_tables SET message_id = ?, status = ?, hand_no = ?, street = ?, dealer_seat = ?, to_act = ?,
+                current_bet = ?, min_raise = ?, deck = ?, board = ?, last_result = ?, version = ?
+         WHERE id = ? AND version = ?",
+        table.message_id,
+        table.status,
+        table.hand_no,
+        table.street,
+        table.dealer_seat,
+        table.to_act,
+        table.current_bet,
+        table.min_raise,
+        table.deck,
+        table.board,
+        table.last_result,
+        next_version,
+        table.id,
+        current_version
+    )
+    .execute(&mut *connection)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0);
+
+    if !updated {
+        return false;
+    }
+
+    if sqlx::query!("DELETE FROM poker_seats WHERE table_id = ?", table.id)
+        .execute(&mut *connection)
+        .await
+        .is_err()
+    {
+        return false;
+    }
+
+    insert_poker_seats(table.id, seats, connection).await
+}
+
+async fn insert_poker_seats(
+    table_id: i64,
+    seats: &[PokerSeat],
+    connection: &mut SqliteConnection,
+) -> bool {
+    for seat in seats {
+        let inserted = sqlx::query!(
+            "INSERT INTO poker_seats (table_id, seat, discord_user_id, stack, hole_cards, bet, committed, in_hand, folded, all_in, acted, leaving)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            table_id,
+            seat.seat,
+            seat.discord_user_id,
+            seat.stack,
+            seat.hole_cards,
+            seat.bet,
+            seat.committed,
+            seat.in_hand,
+            seat.folded,
+            seat.all_in,
+            seat.acted,
+            seat.leaving
+        )
+        .execute(&mut *connection)
+        .await
+        .is_ok();
+
+        if !inserted {
+            return false;
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::house::find_house_balance;
+    use crate::database::players::{create_player, find_player};
+
+    const TS: i64 = 1_760_000_000;
+    const DAY: i64 = 20_370;
+
+    fn new_table() -> PokerTable {
+        PokerTable {
+            id: 0,
+            channel_id: "1".into(),
+            message_id: None,
+            owner_id: "1".into(),
+            buy_in: 1000,
+            small_blind: 5,
+            big_blind: 10,
+            status: STATUS_WAITING.into(),
+            hand_no: 0,
+            street: String::new(),
+            dealer_seat: 0,
+            to_act: None,
+            current_bet: 0,
+            min_raise: 0,
+            deck: String::new(),
+            board: String::new(),
+            last_result: None,
+            version: 1,
+        }
+    }
+
+    fn seat(seat: i64, user_id: &str, stack: i64) -> PokerSeat {
+        PokerSeat {
+            seat,
+            discord_user_id: user_id.into(),
+            stack,
+            hole_cards: String::new(),
+            bet: 0,
+            committed: 0,
+            in_hand: false,
+            folded: false,
+            all_in: false,
+            acted: false,
+            leaving: false,
+        }
+    }
+
+    async fn bought_in(user_id: &str, database: &Pool<Sqlite>) -> Player {
+        let mut player = create_player(&user_id.to_string(), TS, database)
+            .await
+            .unwrap();
+        player.balance -= 1000;
+        player
+    }
+
+    async fn balance(user_id: &str, database: &Pool<Sqlite>) -> i64 {
+        find_player(&user_id.to_string(), database)
+            .await
+            .unwrap()
+            .balance
+    }
+
+    async fn opened(database: &Pool<Sqlite>) -> PokerTable {
+        let mut table = new_table();
+        let mut owner = bought_in("1", database).await;
+        assert!(
+            open_poker_table(&mut table, &[seat(0, "1", 1000)], &mut owner, DAY, database).await
+        );
+        table
+    }
+
+    #[tokio::test]
+    async fn second_table_in_the_channel_takes_no_buy_in() {
+        let database = crate::database::in_memory().await;
+        opened(&database).await;
+
+        let mut player = bought_in("2", &database).await;
+        assert!(
+            !open_poker_table(
+                &mut new_table(),
+                &[seat(0, "2", 1000)],
+                &mut player,
+                DAY,
+                &database
+            )
+            .await
+        );
+
+        assert_eq!(balance("1", &database).await, 4000);
+        assert_eq!(balance("2", &database).await, 5000);
+    }
+
+    #[tokio::test]
+    async fn buy_in_cash_out_and_rake_are_saved_with_the_table() {
+        let database = crate::database::in_memory().await;
+        let house = find_house_balance(&database).await;
+        let mut table = opened(&database).await;
+
+        let buyer = bought_in("2", &database).await;
+        let seats = [seat(0, "1", 1000), seat(1, "2", 1000)];
+        assert!(settle_poker_table(&mut table, &seats, Some(&buyer), &[], 0, DAY, &database).await);
+
+        // The owner leaves with the pot of the hand, minus the rake.
+        let seats = [seat(1, "2", 0)];
+        let cash_outs = [seat(0, "1", 1980)];
+        assert!(settle_poker_table(&mut table, &seats, None, &cash_outs, 20, DAY, &database).await);
+
+        assert_eq!(balance("1", &database).await, 5980);
+        assert_eq!(balance("2", &database).await, 4000);
+        assert_eq!(find_house_balance(&database).await, house + 20);
+        assert_eq!(load_poker_seats(table.id, &database).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn stale_table_moves_no_potatoes() {
+        let database = crate::database::in_memory().await;
+        let house = find_house_balance(&database).await;
+        let table = opened(&database).await;
+
+        let mut first = table.clone();
+        assert!(save_poker_table(&mut first, &[seat(0, "1", 1000)], &database).await);
+
+        let mut stale = table;
+        let buyer = bought_in("2", &database).await;
+        let seats = [seat(1, "2", 1000)];
+        let cash_outs = [seat(0, "1", 1000)];
+        assert!(
+            !settle_poker_table(
+                &mut stale,
+                &seats,
+                Some(&buyer),
+                &cash_outs,
+                10,
+                DAY,
+                &database
+            )
+            .await
+        );
+
+        assert_eq!(balance("1", &database).await, 4000);
+        assert_eq!(balance("2", &database).await, 5000);
+        assert_eq!(find_house_balance(&database).await, house);
+    }
+}
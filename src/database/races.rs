@@ -0,0 +1,427 @@
+use sqlx::{Pool, Sqlite, SqliteConnection};
+use tracing::instrument;
+
+use crate::database::house::change_house_balance;
+use crate::database::limits::record_player_loss;
+use crate::database::players::{credit_player, save_player, Player};
+
+pub const STATUS_BETTING: &str = "betting";
+pub const STATUS_RUNNING: &str = "running";
+pub const STATUS_FINISHED: &str = "finished";
+pub const STATUS_CANCELLED: &str = "cancelled";
+
+#[derive(Clone, Debug)]
+pub struct Race {
+    pub id: i64,
+    pub channel_id: String,
+    pub message_id: Option<String>,
+    pub status: String,
+    pub created_ts: i64,
+    pub finished_ts: Option<i64>,
+    pub winner_lane: Option<i64>,
+    pub pool: i64,
+    pub house_take: i64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Racer {
+    pub lane: i64,
+    pub name: String,
+    pub strength: i64,
+    pub distance: i64,
+    pub finish_position: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RaceBet {
+    pub discord_user_id: String,
+    pub lane: i64,
+    pub amount: i64,
+    pub payout: Option<i64>,
+}
+
+/// Creates the race together with its racers.
+#[instrument]
+pub async fn create_race(race: &mut Race, racers: &[Racer], database: &Pool<Sqlite>) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    let Ok(result) = sqlx::query!(
+        "INSERT INTO races (channel_id, message_id, status, created_ts) VALUES (?, ?, ?, ?)",
+        race.channel_id,
+        race.message_id,
+        race.status,
+        race.created_ts
+    )
+    .execute(&mut *tx)
+    .await
+    else {
+        return false;
+    };
+
+    race.id = result.last_insert_rowid();
+
+    for racer in racers {
+        let inserted = sqlx::query!(
+            "INSERT INTO race_racers (race_id, lane, name, strength) VALUES (?, ?, ?, ?)",
+            race.id,
+            racer.lane,
+            racer.name,
+            racer.strength
+        )
+        .execute(&mut *tx)
+        .await
+        .is_ok();
+
+        if !inserted {
+            return false;
+        }
+    }
+
+    tx.commit().await.is_ok()
+}
+
+#[instrument]
+pub async fn update_race(race: &Race, database: &Pool<Sqlite>) -> bool {
+    let Ok(mut connection) = database.acquire().await else {
+        return false;
+    };
+
+    save_race(race, &mut connection).await
+}
+
+async fn save_race(race: &Race, connection: &mut SqliteConnection) -> bool {
+    sqlx::query!(
+        "UPDATE races SET message_id = ?, status = ?, finished_ts = ?, winner_lane = ?, pool = ?, house_take = ? WHERE id = ?",
+        race.message_id,
+        race.status,
+        race.finished_ts,
+        race.winner_lane,
+        race.pool,
+        race.house_take,
+        race.id
+    )
+    .execute(&mut *connection)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+#[instrument]
+pub async fn find_open_race(channel_id: &String, database: &Pool<Sqlite>) -> Option<Race> {
+    sqlx::query_as!(
+        Race,
+        "SELECT id as \"id!\", channel_id, message_id, status, created_ts, finished_ts, winner_lane, pool, house_take
+         FROM races WHERE channel_id = ? AND status IN (?, ?)",
+        channel_id,
+        STATUS_BETTING,
+        STATUS_RUNNING
+    )
+    .fetch_optional(database)
+    .await
+    .unwrap_or(None)
+}
+
+#[instrument]
+pub async fn find_unfinished_races(database: &Pool<Sqlite>) -> Vec<Race> {
+    sqlx::query_as!(
+        Race,
+        "SELECT id as \"id!\", channel_id, message_id, status, created_ts, finished_ts, winner_lane, pool, house_take
+         FROM races WHERE status IN (?, ?)",
+        STATUS_BETTING,
+        STATUS_RUNNING
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+#[instrument]
+pub async fn load_racers(race_id: i64, database: &Pool<Sqlite>) -> Vec<Racer> {
+    sqlx::query_as!(
+        Racer,
+        "SELECT lane, name, strength, distance, finish_position FROM race_racers WHERE race_id = ? ORDER BY lane",
+        race_id
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+#[instrument]
+pub async fn update_racer(race_id: i64, racer: &Racer, database: &Pool<Sqlite>) -> bool {
+    sqlx::query!(
+        "UPDATE race_racers SET distance = ?, finish_position = ? WHERE race_id = ? AND lane = ?",
+        racer.distance,
+        racer.finish_position,
+        race_id,
+        racer.lane
+    )
+    .execute(database)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+/// Saves the player with the bet taken from the balance and adds to the bet
+/// of the user on the racer in one transaction, a new bet is created when
+/// needed. Nothing is saved once the race has left the betting phase.
+#[instrument]
+pub async fn place_race_bet(
+    player: &mut Player,
+    race_id: i64,
+    bet: &RaceBet,
+    day: i64,
+    database: &Pool<Sqlite>,
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    if !save_player(player, &mut tx).await
+        || !record_player_loss(&bet.discord_user_id, bet.amount, day, &mut tx).await
+    {
+        return false;
+    }
+
+    let added = sqlx::query!(
+        "INSERT INTO race_bets (race_id, discord_user_id, lane, amount)
+         SELECT ?, ?, ?, ? WHERE EXISTS (SELECT 1 FROM races WHERE id = ? AND status = ?)
+         ON CONFLICT (race_id, discord_user_id, lane) DO UPDATE SET amount = amount + excluded.amount",
+        race_id,
+        bet.discord_user_id,
+        bet.lane,
+        bet.amount,
+        race_id,
+        STATUS_BETTING
+    )
+    .execute(&mut *tx)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0);
+
+    if !added || tx.commit().await.is_err() {
+        return false;
+    }
+
+    player.version += 1;
+    true
+}
+
+#[instrument]
+pub async fn load_race_bets(race_id: i64, database: &Pool<Sqlite>) -> Vec<RaceBet> {
+    sqlx::query_as!(
+        RaceBet,
+        "SELECT discord_user_id, lane, amount, payout FROM race_bets WHERE race_id = ? ORDER BY amount DESC",
+        race_id
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+/// Pays `amount` out of the race to the user within the transaction of the
+/// caller, the payout is saved on the bet so it is paid only once.
+async fn pay_race_bet(
+    race_id: i64,
+    bet: &RaceBet,
+    amount: i64,
+    day: i64,
+    connection: &mut SqliteConnection,
+) -> bool {
+    let saved = sqlx::query!(
+        "UPDATE race_bets SET payout = ? WHERE race_id = ? AND discord_user_id = ? AND lane = ? AND payout IS NULL",
+        amount,
+        race_id,
+        bet.discord_user_id,
+        bet.lane
+    )
+    .execute(&mut *connection)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0);
+
+    if !saved {
+        return false;
+    }
+
+    if amount == 0 {
+        return true;
+    }
+
+    credit_player(&bet.discord_user_id, amount, &mut *connection).await
+        && record_player_loss(&bet.discord_user_id, -amount, day, &mut *connection).await
+}
+
+/// Pays out the settled bets, gives the house its take and saves the race in
+/// one transaction.
+#[instrument(skip(bets))]
+pub async fn finish_race(race: &Race, bets: &[RaceBet], day: i64, database: &Pool<Sqlite>) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    for bet in bets {
+        let payout = bet.payout.unwrap_or_default();
+        if !pay_race_bet(race.id, bet, payout, day, &mut tx).await {
+            return false;
+        }
+    }
+
+    if race.house_take != 0 && !change_house_balance(race.house_take, "race", day, &mut tx).await {
+        return false;
+    }
+
+    save_race(race, &mut tx).await && tx.commit().await.is_ok()
+}
+
+/// Refunds the bets not paid out yet and saves the cancelled race in one
+/// transaction.
+#[instrument]
+pub async fn cancel_race(race: &Race, day: i64, database: &Pool<Sqlite>) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    let bets = sqlx::query_as!(
+        RaceBet,
+        "SELECT discord_user_id, lane, amount, payout FROM race_bets WHERE race_id = ? AND payout IS NULL",
+        race.id
+    )
+    .fetch_all(&mut *tx)
+    .await;
+
+    let Ok(bets) = bets else {
+        return false;
+    };
+
+    for bet in bets.iter() {
+        if !pay_race_bet(race.id, bet, bet.amount, day, &mut tx).await {
+            return false;
+        }
+    }
+
+    save_race(race, &mut tx).await && tx.commit().await.is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::house::find_house_balance;
+    use crate::database::limits::find_player_loss;
+    use crate::database::players::{create_player, find_player};
+
+    const TS: i64 = 1_760_000_000;
+    const DAY: i64 = 20_370;
+
+    async fn open_race(database: &Pool<Sqlite>) -> Race {
+        let mut race = Race {
+            id: 0,
+            channel_id: "1".to_string(),
+            message_id: None,
+            status: STATUS_BETTING.into(),
+            created_ts: TS,
+            finished_ts: None,
+            winner_lane: None,
+            pool: 0,
+            house_take: 0,
+        };
+        assert!(create_race(&mut race, &[], database).await);
+        race
+    }
+
+    async fn bet(
+        race: &Race,
+        user_id: &str,
+        lane: i64,
+        amount: i64,
+        database: &Pool<Sqlite>,
+    ) -> bool {
+        let mut player = match find_player(&user_id.to_string(), database).await {
+            Some(player) => player,
+            None => create_player(&user_id.to_string(), TS, database)
+                .await
+                .unwrap(),
+        };
+        player.balance -= amount;
+        let bet = RaceBet {
+            discord_user_id: user_id.to_string(),
+            lane,
+            amount,
+            payout: None,
+        };
+
+        place_race_bet(&mut player, race.id, &bet, DAY, database).await
+    }
+
+    async fn balance(user_id: &str, database: &Pool<Sqlite>) -> i64 {
+        find_player(&user_id.to_string(), database)
+            .await
+            .unwrap()
+            .balance
+    }
+
+    #[tokio::test]
+    async fn bet_is_not_taken_once_betting_is_over() {
+        let database = crate::database::in_memory().await;
+        let mut race = open_race(&database).await;
+
+        assert!(bet(&race, "1", 1, 500, &database).await);
+        race.status = STATUS_RUNNING.into();
+        assert!(update_race(&race, &database).await);
+        assert!(!bet(&race, "1", 1, 700, &database).await);
+
+        assert_eq!(balance("1", &database).await, 4500);
+        assert_eq!(
+            find_player_loss(&"1".to_string(), DAY, &database).await,
+            500
+        );
+        assert_eq!(load_race_bets(race.id, &database).await[0].amount, 500);
+    }
+
+    #[tokio::test]
+    async fn finished_race_pays_the_winners_once() {
+        let database = crate::database::in_memory().await;
+        let mut race = open_race(&database).await;
+        let house = find_house_balance(&database).await;
+
+        assert!(bet(&race, "1", 1, 600, &database).await);
+        assert!(bet(&race, "2", 2, 400, &database).await);
+
+        let mut bets = load_race_bets(race.id, &database).await;
+        bets[0].payout = Some(950);
+        bets[1].payout = Some(0);
+        race.status = STATUS_FINISHED.into();
+        race.pool = 1000;
+        race.house_take = 50;
+        assert!(finish_race(&race, &bets, DAY, &database).await);
+        assert!(!finish_race(&race, &bets, DAY, &database).await);
+
+        assert_eq!(balance("1", &database).await, 5350);
+        assert_eq!(balance("2", &database).await, 4600);
+        assert_eq!(find_house_balance(&database).await, house + 50);
+        assert_eq!(
+            find_player_loss(&"1".to_string(), DAY, &database).await,
+            -350
+        );
+    }
+
+    #[tokio::test]
+    async fn cancelled_race_refunds_the_bets_once() {
+        let database = crate::database::in_memory().await;
+        let mut race = open_race(&database).await;
+        let house = find_house_balance(&database).await;
+
+        assert!(bet(&race, "1", 1, 600, &database).await);
+        assert!(bet(&race, "1", 1, 100, &database).await);
+
+        race.status = STATUS_CANCELLED.into();
+        assert!(cancel_race(&race, DAY, &database).await);
+        assert!(cancel_race(&race, DAY, &database).await);
+
+        assert_eq!(balance("1", &database).await, 5000);
+        assert_eq!(find_house_balance(&database).await, house);
+        assert_eq!(find_player_loss(&"1".to_string(), DAY, &database).await, 0);
+    }
+}
@@ -0,0 +1,202 @@
+use async_trait::async_trait;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::database::players::Player;
+
+use crate::internal::settings::{Backend, Settings};
+
+pub mod memory;
+pub mod postgres;
+pub mod sqlite;
+
+pub use memory::InMemoryPlayerRepository;
+pub use postgres::PostgresPlayerRepository;
+pub use sqlite::SqlitePlayerRepository;
+
+/// Storage of the players and the pools their potatoes move to, kept in
+/// SQLite or PostgreSQL and in memory by the simulation. The methods behave like
+/// the free functions in `database::players`, `database::house` and
+/// `database::charity`.
+#[async_trait]
+pub trait PlayerRepository: Debug + Send + Sync {
+    async fn find_player(&self, user_id: &str) -> Option<Player>;
+
+    /// Players not fed since `ts`.
+    async fn find_unfeeded_players(&self, ts: i64) -> Vec<Player>;
+
+    /// Creates a player with the starting balance, first seen at `ts`. Fails
+    /// when the player already exists.
+    async fn create_player(&self, user_id: &str, ts: i64) -> Option<Player>;
+
+    /// Fails when the player was changed in the meantime.
+    async fn update_player(&self, player: &mut Player) -> bool;
+
+    /// Every player, the richest first.
+    async fn load_players_by_balance(&self) -> Vec<Player>;
+
+    /// Saves the player like `update_player` and settles the bet with the
+    /// house and the jackpot at the same time. `house_amount` is what the
+    /// house wins, negative when it pays out. `day` is the local day of the bet.
+    async fn settle_bet(
+        &self,
+        player: &mut Player,
+        house_amount: i64,
+        jackpot_amount: i64,
+        source: &str,
+        day: i64,
+    ) -> bool;
+
+    async fn find_house_balance(&self) -> i64;
+
+    async fn find_jackpot_pool(&self) -> i64;
+
+    async fn find_charity_pool(&self) -> i64;
+
+    /// Saves a feeding at once: removes and updates the players, takes
+    /// `charity_pool` from the charity pool and gives `leftover` to the house
+    /// as charity. Saves nothing when any of the players was changed in the
+    /// meantime.
+    async fn save_feeding(
+        &self,
+        removed: &[Player],
+        updated: &[Player],
+        charity_pool: i64,
+        leftover: i64,
+        day: i64,
+    ) -> bool;
+}
+
+/// Player repository of the backend picked in the settings, SQLite shares
+/// the pool of the game tables.
+pub async fn player_repository(
+    settings: &Settings,
+    database: &sqlx::SqlitePool,
+) -> Arc<dyn PlayerRepository> {
+    match settings.database.backend {
+        Backend::Sqlite => Arc::new(SqlitePlayerRepository::new(database.clone())),
+        Backend::Postgres => {
+            let url = settings
+                .database
+                .url
+                .as_deref()
+                .expect("PostgreSQL backend needs the database url");
+            Arc::new(PostgresPlayerRepository::connect(url).await)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every backend has to pass these.
+    async fn conformance(repository: &dyn PlayerRepository) {
+        let user_id = "1001";
+        let ts = 1_760_000_000;
+
+        assert!(repository.find_player(user_id).await.is_none());
+
+        let mut player = repository.create_player(user_id, ts).await.unwrap();
+        assert_eq!(player.balance, 5000);
+        assert_eq!(player.last_feed_ts, ts);
+        assert_eq!(player.idle_since_ts, ts);
+        assert_eq!(player.version, 1);
+        assert!(repository.create_player(user_id, ts).await.is_none());
+
+        let found = repository.find_player(user_id).await.unwrap();
+        assert_eq!(found.discord_user_id, user_id);
+        assert_eq!(found.balance, player.balance);
+        assert_eq!(found.last_feed_ts, player.last_feed_ts);
+        assert_eq!(found.idle_since_ts, player.idle_since_ts);
+
+        player.balance = 1234;
+        assert!(repository.update_player(&mut player).await);
+        assert_eq!(player.version, 2);
+        assert_eq!(repository.find_player(user_id).await.unwrap().balance, 1234);
+
+        // A stale copy must not overwrite the newer one.
+        let mut stale = found.clone();
+        stale.balance = 1;
+        assert!(!repository.update_player(&mut stale).await);
+        assert_eq!(repository.find_player(user_id).await.unwrap().balance, 1234);
+
+        let mut other = repository.create_player("1002", ts).await.unwrap();
+        other.last_feed_ts = player.last_feed_ts - 100;
+        assert!(repository.update_player(&mut other).await);
+
+        let unfeeded = repository.find_unfeeded_players(player.last_feed_ts).await;
+        assert_eq!(unfeeded.len(), 1);
+        assert_eq!(unfeeded[0].discord_user_id, "1002");
+
+        let by_balance = repository.load_players_by_balance().await;
+        let order = by_balance
+            .iter()
+            .map(|player| player.discord_user_id.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(order, vec!["1002", "1001"]);
+
+        let house = repository.find_house_balance().await;
+        let jackpot = repository.find_jackpot_pool().await;
+        player.balance -= 100;
+        assert!(repository.settle_bet(&mut player, 95, 5, "flip", ts).await);
+        assert_eq!(player.version, 3);
+        assert_eq!(repository.find_player(user_id).await.unwrap().balance, 1134);
+        assert_eq!(repository.find_house_balance().await, house + 95);
+        assert_eq!(repository.find_jackpot_pool().await, jackpot + 5);
+
+        // A stale bet must not move the pools either.
+        let mut stale = player.clone();
+        stale.version -= 1;
+        assert!(!repository.settle_bet(&mut stale, 95, 5, "flip", ts).await);
+        assert_eq!(repository.find_house_balance().await, house + 95);
+        assert_eq!(repository.find_jackpot_pool().await, jackpot + 5);
+
+        let charity = repository.find_charity_pool().await;
+        let mut fed = repository.find_player("1002").await.unwrap();
+        fed.balance += 10;
+        assert!(repository.save_feeding(&[], &[fed.clone()], 0, 3, ts).await);
+        assert_eq!(
+            repository.find_player("1002").await.unwrap().balance,
+            fed.balance
+        );
+        assert_eq!(repository.find_house_balance().await, house + 98);
+
+        // One stale player keeps the whole feeding from being saved.
+        fed.balance += 10;
+        assert!(
+            !repository
+                .save_feeding(&[player.clone()], &[fed], 0, 3, ts)
+                .await
+        );
+        assert!(repository.find_player(user_id).await.is_some());
+        assert_eq!(repository.find_house_balance().await, house + 98);
+        assert_eq!(repository.find_charity_pool().await, charity);
+
+        assert!(repository.save_feeding(&[player], &[], 0, 0, ts).await);
+        assert!(repository.find_player(user_id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn memory_conformance() {
+        conformance(&InMemoryPlayerRepository::new()).await;
+    }
+
+    #[tokio::test]
+    async fn sqlite_conformance() {
+        let repository = SqlitePlayerRepository::in_memory().await;
+        conformance(&repository).await;
+    }
+
+    #[tokio::test]
+    async fn postgres_conformance() {
+        let Ok(url) = std::env::var("TEST_POSTGRES_URL") else {
+            eprintln!("TEST_POSTGRES_URL not set, skipping the PostgreSQL conformance tests");
+            return;
+        };
+
+        let repository = PostgresPlayerRepository::connect(&url).await;
+        repository.clear().await;
+        conformance(&repository).await;
+    }
+}
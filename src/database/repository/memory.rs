@@ -0,0 +1,161 @@
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::database::players::Player;
+use crate::database::repository::PlayerRepository;
+
+#[derive(Debug, Default)]
+struct Pools {
+    house: i64,
+    jackpot: i64,
+    charity: i64,
+}
+
+/// Players and pools kept in memory, for tests and simulations.
+#[derive(Debug, Default)]
+pub struct InMemoryPlayerRepository {
+    players: Mutex<BTreeMap<String, Player>>,
+    pools: Mutex<Pools>,
+}
+
+impl InMemoryPlayerRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores the players as they are, replacing existing ones.
+    pub fn insert(&self, players: &[Player]) {
+        let mut stored = self.players.lock().unwrap();
+        for player in players {
+            stored.insert(player.discord_user_id.clone(), player.clone());
+        }
+    }
+
+    /// Collects a transfer fee like the charity pool of the database.
+    pub fn add_to_charity_pool(&self, amount: i64) {
+        self.pools.lock().unwrap().charity += amount;
+    }
+}
+
+#[async_trait]
+impl PlayerRepository for InMemoryPlayerRepository {
+    async fn find_player(&self, user_id: &str) -> Option<Player> {
+        self.players.lock().unwrap().get(user_id).cloned()
+    }
+
+    async fn find_unfeeded_players(&self, ts: i64) -> Vec<Player> {
+        self.players
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|player| player.last_feed_ts < ts)
+            .cloned()
+            .collect()
+    }
+
+    async fn create_player(&self, user_id: &str, ts: i64) -> Option<Player> {
+        let mut players = self.players.lock().unwrap();
+        if players.contains_key(user_id) {
+            return None;
+        }
+
+        let player = Player::new(user_id, ts);
+        players.insert(user_id.to_string(), player.clone());
+        Some(player)
+    }
+
+    async fn update_player(&self, player: &mut Player) -> bool {
+        let current_version = player.version;
+        player.version += 1;
+
+        let mut players = self.players.lock().unwrap();
+        match players.get_mut(&player.discord_user_id) {
+            Some(stored) if stored.version == current_version => {
+                *stored = player.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    async fn load_players_by_balance(&self) -> Vec<Player> {
+        let mut players = self
+            .players
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        players.sort_by_key(|player| std::cmp::Reverse(player.balance));
+        players
+    }
+
+    async fn settle_bet(
+        &self,
+        player: &mut Player,
+        house_amount: i64,
+        jackpot_amount: i64,
+        _source: &str,
+        _day: i64,
+    ) -> bool {
+        if !self.update_player(player).await {
+            return false;
+        }
+
+        let mut pools = self.pools.lock().unwrap();
+        pools.house += house_amount;
+        pools.jackpot += jackpot_amount;
+        true
+    }
+
+    async fn find_house_balance(&self) -> i64 {
+        self.pools.lock().unwrap().house
+    }
+
+    async fn find_jackpot_pool(&self) -> i64 {
+        self.pools.lock().unwrap().jackpot
+    }
+
+    async fn find_charity_pool(&self) -> i64 {
+        self.pools.lock().unwrap().charity
+    }
+
+    async fn save_feeding(
+        &self,
+        removed: &[Player],
+        updated: &[Player],
+        charity_pool: i64,
+        leftover: i64,
+        _day: i64,
+    ) -> bool {
+        let mut players = self.players.lock().unwrap();
+        let mut pools = self.pools.lock().unwrap();
+
+        let unchanged = removed.iter().chain(updated).all(|player| {
+            players
+                .get(&player.discord_user_id)
+                .is_some_and(|stored| stored.version == player.version)
+        });
+        if !unchanged || pools.charity < charity_pool {
+            return false;
+        }
+
+        for player in removed {
+            players.remove(&player.discord_user_id);
+        }
+        for player in updated {
+            players.insert(
+                player.discord_user_id.clone(),
+                Player {
+                    version: player.version + 1,
+                    ..player.clone()
+                },
+            );
+        }
+
+        pools.charity -= charity_pool;
+        pools.house += leftover;
+        true
+    }
+}
@@ -0,0 +1,297 @@
+use async_trait::async_trait;
+use sqlx::{PgConnection, Pool, Postgres};
+use tracing::instrument;
+
+use crate::database::players::Player;
+use crate::database::repository::PlayerRepository;
+
+/// Queries are checked at runtime, the compile time checked macros only know
+/// the SQLite database.
+#[derive(Debug)]
+pub struct PostgresPlayerRepository {
+    database: Pool<Postgres>,
+}
+
+impl PostgresPlayerRepository {
+    /// Connects to the database and brings it up to date.
+    #[instrument]
+    pub async fn connect(url: &str) -> Self {
+        let database = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(5)
+            .connect(url)
+            .await
+            .expect("Could not connect to database");
+
+        sqlx::migrate!("./migrations/postgres")
+            .run(&database)
+            .await
+            .expect("Could not run database migrations");
+
+        PostgresPlayerRepository { database }
+    }
+
+    /// Saves the player like `update_player` within the transaction of the
+    /// caller, who bumps `player.version` once the transaction is committed.
+    async fn save_player(player: &Player, connection: &mut PgConnection) -> bool {
+        sqlx::query(
+            "UPDATE players SET balance = $1, last_feed_ts = $2, idle_since_ts = $3, version = $4 WHERE discord_user_id = $5 AND version = $6",
+        )
+        .bind(player.balance)
+        .bind(player.last_feed_ts)
+        .bind(player.idle_since_ts)
+        .bind(player.version + 1)
+        .bind(&player.discord_user_id)
+        .bind(player.version)
+        .execute(&mut *connection)
+        .await
+        .ok()
+        .map_or_else(|| false, |result| result.rows_affected() > 0)
+    }
+
+    /// Changes the balance of the house and books it in the ledger on `day`,
+    /// like `house::change_house_balance`.
+    async fn change_house_balance(
+        amount: i64,
+        source: &str,
+        day: i64,
+        connection: &mut PgConnection,
+    ) -> bool {
+        let updated = sqlx::query("UPDATE house SET balance = balance + $1 WHERE id = 1")
+            .bind(amount)
+            .execute(&mut *connection)
+            .await
+            .ok()
+            .map_or_else(|| false, |result| result.rows_affected() > 0);
+
+        updated
+            && sqlx::query(
+                "INSERT INTO house_ledger (day, source, amount) VALUES ($1, $2, $3)
+                 ON CONFLICT (day, source) DO UPDATE SET amount = house_ledger.amount + excluded.amount",
+            )
+            .bind(day)
+            .bind(source)
+            .bind(amount)
+            .execute(&mut *connection)
+            .await
+            .is_ok()
+    }
+
+    async fn find_pool(&self, query: &str) -> i64 {
+        sqlx::query_scalar::<_, i64>(query)
+            .fetch_optional(&self.database)
+            .await
+            .unwrap_or(None)
+            .unwrap_or_default()
+    }
+
+    #[cfg(test)]
+    pub async fn clear(&self) {
+        sqlx::query("DELETE FROM players")
+            .execute(&self.database)
+            .await
+            .expect("Could not clear players");
+    }
+}
+
+#[async_trait]
+impl PlayerRepository for PostgresPlayerRepository {
+    #[instrument]
+    async fn find_player(&self, user_id: &str) -> Option<Player> {
+        sqlx::query_as::<_, Player>(
+            "SELECT discord_user_id, balance, last_feed_ts, idle_since_ts, version FROM players WHERE discord_user_id = $1",
+        )
+        .bind(user_id)
+        .fetch_optional(&self.database)
+        .await
+        .unwrap_or(None)
+    }
+
+    #[instrument]
+    async fn find_unfeeded_players(&self, ts: i64) -> Vec<Player> {
+        sqlx::query_as::<_, Player>(
+            "SELECT discord_user_id, balance, last_feed_ts, idle_since_ts, version FROM players WHERE last_feed_ts < $1",
+        )
+        .bind(ts)
+        .fetch_all(&self.database)
+        .await
+        .unwrap_or(vec![])
+    }
+
+    #[instrument]
+    async fn create_player(&self, user_id: &str, ts: i64) -> Option<Player> {
+        let player = Player::new(user_id, ts);
+
+        sqlx::query(
+            "INSERT INTO players (discord_user_id, balance, last_feed_ts, idle_since_ts, version) VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(&player.discord_user_id)
+        .bind(player.balance)
+        .bind(player.last_feed_ts)
+        .bind(player.idle_since_ts)
+        .bind(player.version)
+        .execute(&self.database)
+        .await
+        .map_or(None, |_| Some(player))
+    }
+
+    #[instrument]
+    async fn update_player(&self, player: &mut Player) -> bool {
+        let current_version = player.version;
+        player.version += 1;
+
+        sqlx::query(
+            "UPDATE players SET balance = $1, last_feed_ts = $2, idle_since_ts = $3, version = $4 WHERE discord_user_id = $5 AND version = $6",
+        )
+        .bind(player.balance)
+        .bind(player.last_feed_ts)
+        .bind(player.idle_since_ts)
+        .bind(player.version)
+        .bind(&player.discord_user_id)
+        .bind(current_version)
+        .execute(&self.database)
+        .await
+        .ok()
+        .map_or_else(|| false, |result| result.rows_affected() > 0)
+    }
+
+    #[instrument]
+    async fn load_players_by_balance(&self) -> Vec<Player> {
+        sqlx::query_as::<_, Player>(
+            "SELECT discord_user_id, balance, last_feed_ts, idle_since_ts, version FROM players ORDER BY balance DESC",
+        )
+        .fetch_all(&self.database)
+        .await
+        .unwrap_or_else(|_| Vec::new())
+    }
+
+    #[instrument]
+    async fn settle_bet(
+        &self,
+        player: &mut Player,
+        house_amount: i64,
+        jackpot_amount: i64,
+        source: &str,
+        day: i64,
+    ) -> bool {
+        let Ok(mut tx) = self.database.begin().await else {
+            return false;
+        };
+
+        if !Self::save_player(player, &mut tx).await {
+            return false;
+        }
+
+        if house_amount != 0
+            && !Self::change_house_balance(house_amount, source, day, &mut tx).await
+        {
+            return false;
+        }
+
+        let loss = house_amount + jackpot_amount;
+        if loss != 0
+            && sqlx::query(
+                "INSERT INTO player_losses (discord_user_id, day, amount) VALUES ($1, $2, $3)
+                 ON CONFLICT (discord_user_id, day) DO UPDATE SET amount = player_losses.amount + excluded.amount",
+            )
+            .bind(&player.discord_user_id)
+            .bind(day)
+            .bind(loss)
+            .execute(&mut *tx)
+            .await
+            .is_err()
+        {
+            return false;
+        }
+
+        if jackpot_amount > 0
+            && sqlx::query("UPDATE jackpot SET pool = pool + $1 WHERE id = 1")
+                .bind(jackpot_amount)
+                .execute(&mut *tx)
+                .await
+                .is_err()
+        {
+            return false;
+        }
+
+        if tx.commit().await.is_err() {
+            return false;
+        }
+
+        player.version += 1;
+        true
+    }
+
+    #[instrument]
+    async fn find_house_balance(&self) -> i64 {
+        self.find_pool("SELECT balance FROM house WHERE id = 1")
+            .await
+    }
+
+    #[instrument]
+    async fn find_jackpot_pool(&self) -> i64 {
+        self.find_pool("SELECT pool FROM jackpot WHERE id = 1")
+            .await
+    }
+
+    #[instrument]
+    async fn find_charity_pool(&self) -> i64 {
+        self.find_pool("SELECT balance FROM charity_pool WHERE id = 1")
+            .await
+    }
+
+    #[instrument(skip(removed, updated))]
+    async fn save_feeding(
+        &self,
+        removed: &[Player],
+        updated: &[Player],
+        charity_pool: i64,
+        leftover: i64,
+        day: i64,
+    ) -> bool {
+        let Ok(mut tx) = self.database.begin().await else {
+            return false;
+        };
+
+        for player in removed {
+            let deleted =
+                sqlx::query("DELETE FROM players WHERE discord_user_id = $1 AND version = $2")
+                    .bind(&player.discord_user_id)
+                    .bind(player.version)
+                    .execute(&mut *tx)
+                    .await
+                    .ok()
+                    .map_or_else(|| false, |result| result.rows_affected() > 0);
+
+            if !deleted {
+                return false;
+            }
+        }
+
+        for player in updated {
+            if !Self::save_player(player, &mut tx).await {
+                return false;
+            }
+        }
+
+        if charity_pool > 0 {
+            let taken = sqlx::query(
+                "UPDATE charity_pool SET balance = balance - $1 WHERE id = 1 AND balance >= $1",
+            )
+            .bind(charity_pool)
+            .execute(&mut *tx)
+            .await
+            .ok()
+            .map_or_else(|| false, |result| result.rows_affected() > 0);
+
+            if !taken {
+                return false;
+            }
+        }
+
+        if leftover > 0 && !Self::change_house_balance(leftover, "charity", day, &mut tx).await {
+            return false;
+        }
+
+        tx.commit().await.is_ok()
+    }
+}
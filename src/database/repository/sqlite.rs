@@ -0,0 +1,98 @@
+use async_trait::async_trait;
+use sqlx::{Pool, Sqlite};
+
+use crate::database::players::{self, Player};
+use crate::database::repository::PlayerRepository;
+use crate::database::{charity, house, jackpot};
+
+#[derive(Debug)]
+pub struct SqlitePlayerRepository {
+    database: Pool<Sqlite>,
+}
+
+impl SqlitePlayerRepository {
+    pub fn new(database: Pool<Sqlite>) -> Self {
+        SqlitePlayerRepository { database }
+    }
+
+    /// Fresh database living only as long as the repository, for tests.
+    #[cfg(test)]
+    pub async fn in_memory() -> Self {
+        SqlitePlayerRepository {
+            database: crate::database::in_memory().await,
+        }
+    }
+}
+
+#[async_trait]
+impl PlayerRepository for SqlitePlayerRepository {
+    async fn find_player(&self, user_id: &str) -> Option<Player> {
+        players::find_player(&user_id.to_string(), &self.database).await
+    }
+
+    async fn find_unfeeded_players(&self, ts: i64) -> Vec<Player> {
+        players::find_unfeeded_players(ts, &self.database).await
+    }
+
+    async fn create_player(&self, user_id: &str, ts: i64) -> Option<Player> {
+        players::create_player(&user_id.to_string(), ts, &self.database).await
+    }
+
+    async fn update_player(&self, player: &mut Player) -> bool {
+        players::update_player(player, &self.database).await
+    }
+
+    async fn load_players_by_balance(&self) -> Vec<Player> {
+        players::load_players_by_balance(&self.database).await
+    }
+
+    async fn settle_bet(
+        &self,
+        player: &mut Player,
+        house_amount: i64,
+        jackpot_amount: i64,
+        source: &str,
+        day: i64,
+    ) -> bool {
+        house::settle_player_bet(
+            player,
+            house_amount,
+            jackpot_amount,
+            source,
+            day,
+            &self.database,
+        )
+        .await
+    }
+
+    async fn find_house_balance(&self) -> i64 {
+        house::find_house_balance(&self.database).await
+    }
+
+    async fn find_jackpot_pool(&self) -> i64 {
+        jackpot::find_jackpot_pool(&self.database).await
+    }
+
+    async fn find_charity_pool(&self) -> i64 {
+        charity::find_charity_pool(&self.database).await
+    }
+
+    async fn save_feeding(
+        &self,
+        removed: &[Player],
+        updated: &[Player],
+        charity_pool: i64,
+        leftover: i64,
+        day: i64,
+    ) -> bool {
+        players::save_feeding(
+            removed,
+            updated,
+            charity_pool,
+            leftover,
+            day,
+            &self.database,
+        )
+        .await
+    }
+}
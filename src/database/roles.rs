@@ -0,0 +1,300 @@
+use sqlx::{Pool, Sqlite};
+use tracing::instrument;
+
+use crate::database::house::change_house_balance;
+use crate::database::players::{credit_player, save_player, Player};
+
+#[derive(Clone, Debug)]
+pub struct PurchasableRole {
+    pub guild_id: String,
+    pub role_id: String,
+    pub price: i64,
+    pub duration_secs: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RolePurchase {
+    pub id: i64,
+    pub guild_id: String,
+    pub role_id: String,
+    pub discord_user_id: String,
+    pub price: i64,
+    pub purchased_ts: i64,
+    pub expires_ts: Option<i64>,
+}
+
+#[instrument]
+pub async fn find_purchasable_role(
+    guild_id: &String,
+    role_id: &String,
+    database: &Pool<Sqlite>,
+) -> Option<PurchasableRole> {
+    sqlx::query_as!(
+        PurchasableRole,
+        "SELECT guild_id, role_id, price, duration_secs FROM purchasable_roles WHERE guild_id = ? AND role_id = ?",
+        guild_id,
+        role_id
+    )
+    .fetch_optional(database)
+    .await
+    .unwrap_or(None)
+}
+
+#[instrument]
+pub async fn load_purchasable_roles(
+    guild_id: &String,
+    database: &Pool<Sqlite>,
+) -> Vec<PurchasableRole> {
+    sqlx::query_as!(
+        PurchasableRole,
+        "SELECT guild_id, role_id, price, duration_secs FROM purchasable_roles WHERE guild_id = ? ORDER BY price",
+        guild_id
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+#[instrument]
+pub async fn save_purchasable_role(role: &PurchasableRole, database: &Pool<Sqlite>) -> bool {
+    sqlx::query!(
+        "INSERT INTO purchasable_roles (guild_id, role_id, price, duration_secs) VALUES (?, ?, ?, ?)
+         ON CONFLICT (guild_id, role_id) DO UPDATE SET price = excluded.price, duration_secs = excluded.duration_secs",
+        role.guild_id,
+        role.role_id,
+        role.price,
+        role.duration_secs
+    )
+    .execute(database)
+    .await
+    .is_ok()
+}
+
+#[instrument]
+pub async fn remove_purchasable_role(
+    guild_id: &String,
+    role_id: &String,
+    database: &Pool<Sqlite>,
+) -> bool {
+    sqlx::query!(
+        "DELETE FROM purchasable_roles WHERE guild_id = ? AND role_id = ?",
+        guild_id,
+        role_id
+    )
+    .execute(database)
+    .await
+    .ok()
+    .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+#[instrument]
+pub async fn find_active_role_purchase(
+    guild_id: &String,
+    role_id: &String,
+    user_id: &String,
+    ts: i64,
+    database: &Pool<Sqlite>,
+) -> Option<RolePurchase> {
+    sqlx::query_as!(
+        RolePurchase,
+        "SELECT id as \"id!\", guild_id, role_id, discord_user_id, price, purchased_ts, expires_ts FROM role_purchases
+         WHERE guild_id = ? AND role_id = ? AND discord_user_id = ? AND (expires_ts IS NULL OR expires_ts > ?)",
+        guild_id,
+        role_id,
+        user_id,
+        ts
+    )
+    .fetch_optional(database)
+    .await
+    .unwrap_or(None)
+}
+
+#[instrument]
+pub async fn find_expired_role_purchases(ts: i64, database: &Pool<Sqlite>) -> Vec<RolePurchase> {
+    sqlx::query_as!(
+        RolePurchase,
+        "SELECT id as \"id!\", guild_id, role_id, discord_user_id, price, purchased_ts, expires_ts FROM role_purchases
+         WHERE expires_ts IS NOT NULL AND expires_ts <= ?",
+        ts
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+/// Saves the player with `price` taken from the balance, pays it to the
+/// house and saves the purchase in one transaction. An existing purchase gets
+/// its expiry extended, a new one is created otherwise.
+#[instrument]
+pub async fn buy_role(
+    player: &mut Player,
+    purchase: &mut RolePurchase,
+    price: i64,
+    day: i64,
+    database: &Pool<Sqlite>,
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    if !save_player(player, &mut tx).await
+        || !change_house_balance(price, "roles", day, &mut tx).await
+    {
+        return false;
+    }
+
+    let saved = if purchase.id != 0 {
+        sqlx::query!(
+            "UPDATE role_purchases SET expires_ts = ? WHERE id = ?",
+            purchase.expires_ts,
+            purchase.id
+        )
+        .execute(&mut *tx)
+        .await
+        .ok()
+        .filter(|result| result.rows_affected() > 0)
+    } else {
+        sqlx::query!(
+            "INSERT INTO role_purchases (guild_id, role_id, discord_user_id, price, purchased_ts, expires_ts) VALUES (?, ?, ?, ?, ?, ?)",
+            purchase.guild_id,
+            purchase.role_id,
+            purchase.discord_user_id,
+            purchase.price,
+            purchase.purchased_ts,
+            purchase.expires_ts
+        )
+        .execute(&mut *tx)
+        .await
+        .ok()
+    };
+
+    let Some(result) = saved else {
+        return false;
+    };
+
+    if tx.commit().await.is_err() {
+        return false;
+    }
+
+    if purchase.id == 0 {
+        purchase.id = result.last_insert_rowid();
+    }
+    player.version += 1;
+    true
+}
+
+/// Removes the purchase and pays its price back from the house in one
+/// transaction, for roles that could not be given.
+#[instrument]
+pub async fn refund_role_purchase(
+    purchase: &RolePurchase,
+    day: i64,
+    database: &Pool<Sqlite>,
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    let removed = sqlx::query!("DELETE FROM role_purchases WHERE id = ?", purchase.id)
+        .execute(&mut *tx)
+        .await
+        .ok()
+        .map_or_else(|| false, |result| result.rows_affected() > 0);
+
+    removed
+        && credit_player(&purchase.discord_user_id, purchase.price, &mut tx).await
+        && change_house_balance(-purchase.price, "roles", day, &mut tx).await
+        && tx.commit().await.is_ok()
+}
+
+#[instrument]
+pub async fn remove_role_purchase(purchase: &RolePurchase, database: &Pool<Sqlite>) -> bool {
+    sqlx::query!("DELETE FROM role_purchases WHERE id = ?", purchase.id)
+        .execute(database)
+        .await
+        .ok()
+        .map_or_else(|| false, |result| result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::house::find_house_balance;
+    use crate::database::players::{create_player, find_player};
+
+    const TS: i64 = 1_760_000_000;
+    const DAY: i64 = 20_370;
+
+    fn purchase(price: i64) -> RolePurchase {
+        RolePurchase {
+            id: 0,
+            guild_id: "1".into(),
+            role_id: "2".into(),
+            discord_user_id: "3".into(),
+            price,
+            purchased_ts: TS,
+            expires_ts: None,
+        }
+    }
+
+    async fn balance(database: &Pool<Sqlite>) -> i64 {
+        find_player(&"3".to_string(), database)
+            .await
+            .unwrap()
+            .balance
+    }
+
+    #[tokio::test]
+    async fn price_goes_to_the_house_and_back_on_refund() {
+        let database = crate::database::in_memory().await;
+        let mut player = create_player(&"3".to_string(), TS, &database)
+            .await
+            .unwrap();
+        let house = find_house_balance(&database).await;
+
+        let mut purchase = purchase(1000);
+        player.balance -= 1000;
+        assert!(buy_role(&mut player, &mut purchase, 1000, DAY, &database).await);
+        assert_eq!(balance(&database).await, 4000);
+        assert_eq!(find_house_balance(&database).await, house + 1000);
+
+        assert!(refund_role_purchase(&purchase, DAY, &database).await);
+        assert!(!refund_role_purchase(&purchase, DAY, &database).await);
+        assert_eq!(balance(&database).await, 5000);
+        assert_eq!(find_house_balance(&database).await, house);
+    }
+
+    #[tokio::test]
+    async fn stale_player_buys_nothing() {
+        let database = crate::database::in_memory().await;
+        let player = create_player(&"3".to_string(), TS, &database)
+            .await
+            .unwrap();
+        let house = find_house_balance(&database).await;
+
+        let mut first = player.clone();
+        first.balance -= 1000;
+        assert!(buy_role(&mut first, &mut purchase(1000), 1000, DAY, &database).await);
+
+        // Extending the purchase with an outdated balance is rolled back.
+        let mut stale = player;
+        stale.balance -= 500;
+        let mut extended = find_active_role_purchase(
+            &"1".to_string(),
+            &"2".to_string(),
+            &"3".to_string(),
+            TS,
+            &database,
+        )
+        .await
+        .unwrap();
+        extended.expires_ts = Some(TS + 60);
+        assert!(!buy_role(&mut stale, &mut extended, 500, DAY, &database).await);
+
+        assert_eq!(balance(&database).await, 4000);
+        assert_eq!(find_house_balance(&database).await, house + 1000);
+        assert!(find_expired_role_purchases(TS + 120, &database)
+            .await
+            .is_empty());
+    }
+}
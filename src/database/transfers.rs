@@ -0,0 +1,124 @@
+use sqlx::{Pool, Sqlite};
+use tracing::instrument;
+
+use crate::database::charity::change_charity_pool;
+use crate::database::house::change_house_balance;
+use crate::database::players::Player;
+use crate::internal::settings::FeeDestination;
+
+#[derive(Clone, Debug)]
+pub struct Transfer {
+    pub sender_id: String,
+    pub receiver_id: String,
+    /// Amount taken from the sender, the receiver gets it without the fee.
+    pub amount: i64,
+    pub fee: i64,
+    pub created_ts: i64,
+}
+
+#[instrument]
+pub async fn sum_sent_since(sender_id: &String, since_ts: i64, database: &Pool<Sqlite>) -> i64 {
+    sqlx::query_scalar!(
+        "SELECT COALESCE(SUM(amount), 0) as \"amount!: i64\" FROM transfers WHERE sender_id = ? AND created_ts >= ?",
+        sender_id,
+        since_ts
+    )
+    .fetch_one(database)
+    .await
+    .unwrap_or_default()
+}
+
+#[instrument]
+pub async fn sum_received_since(
+    receiver_id: &String,
+    since_ts: i64,
+    database: &Pool<Sqlite>,
+) -> i64 {
+    sqlx::query_scalar!(
+        "SELECT COALESCE(SUM(amount), 0) as \"amount!: i64\" FROM transfers WHERE receiver_id = ? AND created_ts >= ?",
+        receiver_id,
+        since_ts
+    )
+    .fetch_one(database)
+    .await
+    .unwrap_or_default()
+}
+
+#[instrument]
+pub async fn load_transfers_since(since_ts: i64, database: &Pool<Sqlite>) -> Vec<Transfer> {
+    sqlx::query_as!(
+        Transfer,
+        "SELECT sender_id, receiver_id, amount, fee, created_ts FROM transfers WHERE created_ts >= ? ORDER BY created_ts",
+        since_ts
+    )
+    .fetch_all(database)
+    .await
+    .unwrap_or(vec![])
+}
+
+/// Saves both players, records the transfer and collects the fee in one
+/// transaction, fails when either of the players was changed in the meantime.
+#[instrument]
+pub async fn transfer_potatoes(
+    sender: &mut Player,
+    receiver: &mut Player,
+    transfer: &Transfer,
+    fee_to: &FeeDestination,
+    day: i64,
+    database: &Pool<Sqlite>,
+) -> bool {
+    let Ok(mut tx) = database.begin().await else {
+        return false;
+    };
+
+    for player in [&*sender, &*receiver] {
+        let next_version = player.version + 1;
+        let updated = sqlx::query!(
+            "UPDATE players SET balance = ?, last_feed_ts = ?, idle_since_ts = ?, version = ? WHERE discord_user_id = ? AND version = ?",
+            player.balance,
+            player.last_feed_ts,
+            player.idle_since_ts,
+            next_version,
+            player.discord_user_id,
+            player.version
+        )
+        .execute(&mut *tx)
+        .await
+        .ok()
+        .map_or_else(|| false, |result| result.rows_affected() > 0);
+
+        if !updated {
+            return false;
+        }
+    }
+
+    let inserted = sqlx::query!(
+        "INSERT INTO transfers (sender_id, receiver_id, amount, fee, created_ts) VALUES (?, ?, ?, ?, ?)",
+        transfer.sender_id,
+        transfer.receiver_id,
+        transfer.amount,
+        transfer.fee,
+        transfer.created_ts
+    )
+    .execute(&mut *tx)
+    .await
+    .is_ok();
+
+    if !inserted {
+        return false;
+    }
+
+    let collected = match (transfer.fee, fee_to) {
+        (0, _) => true,
+        (fee, FeeDestination::House) => change_house_balance(fee, "give", day, &mut tx).await,
+        (fee, FeeDestination::Charity) => change_charity_pool(fee, &mut tx).await,
+    };
+
+    if !collected || tx.commit().await.is_err() {
+        return false;
+    }
+
+    sender.version += 1;
+    receiver.version += 1;
+    true
+}
@@ -0,0 +1,531 @@
+use chrono::{DateTime, Datelike, Days, Duration, NaiveDate, NaiveTime, Offset, TimeZone, Weekday};
+use tracing::{error, info, warn};
+
+use crate::database::players::{CharityRates, IdleStatus, Player};
+use crate::database::repository::PlayerRepository;
+use crate::internal::clock::Clock;
+use crate::internal::events;
+
+/// Outcome of a feeding, the players are saved as listed here.
+#[derive(Debug, Default)]
+pub struct Feeding {
+    /// Idle players without potatoes, kicked out of the game.
+    pub removed: Vec<Player>,
+    /// Idle players and the potatoes taken from them for charity.
+    pub taxed: Vec<(Player, i64)>,
+    /// Active players, each got `share` potatoes.
+    pub fed: Vec<Player>,
+    pub share: i64,
+    /// Potatoes taken for charity and the charity pool, part of the share.
+    pub charity: i64,
+    /// Potatoes taken from the charity pool, part of `charity`.
+    pub charity_pool: i64,
+    /// Charity that can't be shared evenly between the active players, goes
+    /// to the house.
+    pub leftover: i64,
+}
+
+/// Start of `date`, the moment the clocks jump when they skip midnight.
+fn midnight<Tz: TimeZone>(timezone: &Tz, date: NaiveDate) -> DateTime<Tz> {
+    let midnight = date.and_time(NaiveTime::MIN);
+
+    timezone
+        .from_local_datetime(&midnight)
+        .earliest()
+        .unwrap_or_else(|| {
+            let before = timezone.offset_from_utc_datetime(&(midnight - Duration::days(1)));
+            let offset = Duration::seconds(before.fix().local_minus_utc().into());
+            timezone.from_utc_datetime(&(midnight - offset))
+        })
+}
+
+/// Midnight of the last Friday, the players not fed since then are fed next.
+pub fn last_friday<Tz: TimeZone>(now: DateTime<Tz>) -> DateTime<Tz> {
+    let mut last_friday = now.date_naive();
+    while last_friday.weekday() != Weekday::Fri {
+        last_friday = last_friday.checked_sub_days(Days::new(1)).unwrap();
+    }
+
+    midnight(&now.timezone(), last_friday)
+}
+
+/// Midnight of the next Friday, when the players fed at `now` are due again.
+pub fn next_friday<Tz: TimeZone>(now: DateTime<Tz>) -> DateTime<Tz> {
+    let last_friday = last_friday(now.clone()).date_naive();
+
+    midnight(&now.timezone(), last_friday + Days::new(7))
+}
+
+/// Splits the players into the removed, taxed and fed ones. Every active
+/// player gets `amount` plus an even share of the charity taken from the idle
+/// players at the `rates` and of `charity_pool`.
+pub fn plan(
+    players: &[Player],
+    ts: i64,
+    amount: i64,
+    charity_pool: i64,
+    rates: &CharityRates,
+) -> Feeding {
+    let mut feeding = Feeding {
+        charity_pool,
+        ..Default::default()
+    };
+    let mut charity_sum = charity_pool;
+    let mut active_players = vec![];
+
+    for player in players {
+        let mut player = player.clone();
+
+        if let IdleStatus::Active = player.idle_status(ts) {
+            active_players.push(player);
+            continue;
+        }
+
+        if player.balance < 1 {
+            feeding.removed.push(player);
+            continue;
+        }
+
+        let charity = player.charity_amount(ts, rates);
+        charity_sum += charity;
+        player.balance -= charity;
+        player.last_feed_ts = ts;
+        feeding.taxed.push((player, charity));
+    }
+
+    feeding.charity = charity_sum;
+
+    let num_active_players = active_players.len() as i64;
+    feeding.share = match num_active_players {
+        0 => charity_sum,
+        _ => amount + charity_sum / num_active_players,
+    };
+    feeding.leftover = match num_active_players {
+        0 => charity_sum,
+        _ => charity_sum % num_active_players,
+    };
+
+    for mut player in active_players {
+        player.balance += feeding.share;
+        player.last_feed_ts = ts;
+        feeding.fed.push(player);
+    }
+
+    feeding
+}
+
+impl Feeding {
+    /// Part of the share coming from charity.
+    pub fn charity_share(&self) -> i64 {
+        match self.fed.len() as i64 {
+            0 => 0,
+            num_fed => self.charity / num_fed,
+        }
+    }
+}
+
+/// Feedings given up on after conflicting this many times with other changes
+/// to the players.
+const ATTEMPTS: usize = 5;
+
+/// Saves the outcome of the feeding in one go with the leftover booked on the
+/// local `day`, fails when any of the players was changed in the meantime.
+pub async fn apply(feeding: &mut Feeding, players: &dyn PlayerRepository, day: i64) -> bool {
+    let updated = feeding
+        .taxed
+        .iter()
+        .map(|(player, _)| player.clone())
+        .chain(feeding.fed.iter().cloned())
+        .collect::<Vec<_>>();
+
+    if !players
+        .save_feeding(
+            &feeding.removed,
+            &updated,
+            feeding.charity_pool,
+            feeding.leftover,
+            day,
+        )
+        .await
+    {
+        return false;
+    }
+
+    for player in feeding
+        .taxed
+        .iter_mut()
+        .map(|(player, _)| player)
+        .chain(feeding.fed.iter_mut())
+    {
+        player.version += 1;
+    }
+
+    true
+}
+
+/// Players not fed since the last Friday.
+pub async fn find_unfeeded(players: &dyn PlayerRepository, clock: &dyn Clock) -> Vec<Player> {
+    players
+        .find_unfeeded_players(last_friday(clock.now()).timestamp())
+        .await
+}
+
+/// Feeds the players not fed since the last Friday, `amount` is multiplied
+/// by the `multiplier` of the running events. The charity pool is shared like
+/// charity, whatever can't be shared evenly goes to the house. When players
+/// change while they are fed, the feeding is planned again from their new
+/// state. `None` when that kept happening.
+pub async fn feed(
+    players: &dyn PlayerRepository,
+    clock: &dyn Clock,
+    amount: i64,
+    multiplier: f64,
+    rates: &CharityRates,
+) -> Option<Feeding> {
+    let amount = events::multiply(amount, multiplier);
+
+    for attempt in 1..=ATTEMPTS {
+        let unfeeded = find_unfeeded(players, clock).await;
+
+        // The pool is left alone until there is somebody to share it with.
+        if unfeeded.is_empty() {
+            return Some(Feeding::default());
+        }
+
+        let charity_pool = players.find_charity_pool().await;
+        let now = clock.timestamp();
+        let mut feeding = plan(&unfeeded, now, amount, charity_pool, rates);
+
+        if apply(&mut feeding, players, clock.day()).await {
+            info!(
+                "Fed {} players, taxed {} and removed {}",
+                feeding.fed.len(),
+                feeding.taxed.len(),
+                feeding.removed.len()
+            );
+            return Some(feeding);
+        }
+
+        warn!(
+            "Players changed during feeding attempt {} of {}",
+            attempt, ATTEMPTS
+        );
+    }
+
+    error!("Gave up feeding after {} attempts", ATTEMPTS);
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::repository::InMemoryPlayerRepository;
+    use crate::internal::clock::FakeClock;
+    use chrono_tz::Asia::Amman;
+    use chrono_tz::Europe::Tallinn;
+
+    const WEEK: i64 = 604_800;
+
+    fn player(user_id: &str, balance: i64, idle_since_ts: i64) -> Player {
+        Player {
+            discord_user_id: user_id.to_string(),
+            balance,
+            last_feed_ts: 0,
+            idle_since_ts,
+            version: 1,
+        }
+    }
+
+    /// Friday noon.
+    fn clock() -> FakeClock {
+        FakeClock::new(Tallinn.with_ymd_and_hms(2026, 10, 23, 12, 0, 0).unwrap())
+    }
+
+    #[test]
+    fn last_friday_is_at_midnight() {
+        let friday = Tallinn.with_ymd_and_hms(2026, 10, 23, 0, 0, 0).unwrap();
+
+        assert_eq!(last_friday(friday), friday);
+        assert_eq!(last_friday(friday + Duration::hours(12)), friday);
+        assert_eq!(last_friday(friday + Duration::days(6)), friday);
+        assert_eq!(
+            last_friday(friday - Duration::hours(1)),
+            friday - Duration::weeks(1)
+        );
+    }
+
+    #[test]
+    fn next_friday_is_after_now() {
+        let friday = Tallinn.with_ymd_and_hms(2026, 10, 16, 0, 0, 0).unwrap();
+
+        assert_eq!(next_friday(friday - Duration::hours(1)), friday);
+        assert_eq!(next_friday(friday), friday + Duration::weeks(1));
+        assert_eq!(
+            next_friday(friday + Duration::days(3)),
+            friday + Duration::weeks(1)
+        );
+    }
+
+    #[test]
+    fn fridays_follow_the_clocks_across_a_week_of_daylight_saving() {
+        // The clocks go back on the Sunday in between.
+        let friday = Tallinn.with_ymd_and_hms(2026, 10, 23, 0, 0, 0).unwrap();
+        let next = Tallinn.with_ymd_and_hms(2026, 10, 30, 0, 0, 0).unwrap();
+
+        assert_eq!(next_friday(friday), next);
+        assert_eq!(next - friday, Duration::weeks(1) + Duration::hours(1));
+        assert_eq!(last_friday(next - Duration::minutes(30)), friday);
+    }
+
+    #[test]
+    fn friday_starts_after_the_clocks_spring_over_midnight() {
+        // Jordan skipped from 00:00 to 01:00 on Friday 26 March 2021.
+        let friday = Amman.with_ymd_and_hms(2021, 3, 26, 1, 0, 0).unwrap();
+
+        assert_eq!(last_friday(friday + Duration::hours(12)), friday);
+        assert_eq!(
+            next_friday(friday - Duration::weeks(1) + Duration::hours(12)),
+            friday
+        );
+    }
+
+    #[test]
+    fn friday_starts_at_the_first_midnight_when_the_clocks_fall_back() {
+        // Jordan went back from 01:00 to 00:00 on Friday 29 October 2021.
+        let friday = Amman
+            .with_ymd_and_hms(2021, 10, 29, 0, 0, 0)
+            .earliest()
+            .unwrap();
+
+        assert_eq!(last_friday(friday + Duration::hours(12)), friday);
+        assert_eq!(last_friday(friday + Duration::minutes(90)), friday);
+        assert_eq!(
+            next_friday(friday - Duration::weeks(1) + Duration::hours(12)),
+            friday
+        );
+    }
+
+    #[test]
+    fn idle_players_pay_at_the_given_rates() {
+        let now = clock().timestamp();
+        let players = [
+            player("1", 1000, now),
+            player("2", 2000, now - WEEK),
+            player("3", 600, now - 2 * WEEK),
+            player("4", 900, now - 3 * WEEK),
+        ];
+        let rates = CharityRates {
+            away: 20,
+            sleeping: 10,
+            missing: 5,
+        };
+
+        let feeding = plan(&players, now, 100, 0, &rates);
+
+        assert_eq!(
+            feeding
+                .taxed
+                .iter()
+                .map(|(player, charity)| (player.discord_user_id.as_str(), *charity))
+                .collect::<Vec<_>>(),
+            vec![("2", 100), ("3", 60), ("4", 180)]
+        );
+        assert_eq!(feeding.share, 100 + 340);
+    }
+
+    #[tokio::test]
+    async fn idle_players_pay_for_the_active_ones() {
+        let clock = clock();
+        let now = clock.timestamp();
+        let repository = InMemoryPlayerRepository::new();
+        repository.insert(&[
+            player("1", 1000, now),
+            player("2", 2000, now - WEEK),
+            player("3", 600, now - 2 * WEEK),
+            player("4", 0, now - 5 * WEEK),
+            player("5", 3000, now),
+        ]);
+
+        repository.add_to_charity_pool(5);
+
+        let feeding = feed(&repository, &clock, 100, 1.0, &CharityRates::default())
+            .await
+            .unwrap();
+
+        assert_eq!(feeding.removed.len(), 1);
+        assert_eq!(feeding.removed[0].discord_user_id, "4");
+        assert_eq!(
+            feeding
+                .taxed
+                .iter()
+                .map(|(player, charity)| (player.discord_user_id.as_str(), *charity))
+                .collect::<Vec<_>>(),
+            vec![("2", 200), ("3", 100)]
+        );
+        // 200 + 100 + 5 shared between two players.
+        assert_eq!(feeding.share, 252);
+        assert_eq!(feeding.charity, 305);
+        assert_eq!(feeding.charity_share(), 152);
+        assert_eq!(feeding.leftover, 1);
+        assert_eq!(repository.find_charity_pool().await, 0);
+        assert_eq!(repository.find_house_balance().await, 1);
+
+        let balance = |user_id: &'static str| {
+            let repository = &repository;
+            async move { repository.find_player(user_id).await.map(|p| p.balance) }
+        };
+        assert_eq!(balance("1").await, Some(1252));
+        assert_eq!(balance("2").await, Some(1800));
+        assert_eq!(balance("3").await, Some(500));
+        assert_eq!(balance("4").await, None);
+        assert_eq!(balance("5").await, Some(3252));
+    }
+
+    #[tokio::test]
+    async fn players_are_fed_once_a_week() {
+        let clock = clock();
+        let repository = InMemoryPlayerRepository::new();
+        repository.insert(&[player("1", 1000, clock.timestamp())]);
+
+        assert_eq!(
+            feed(&repository, &clock, 100, 1.0, &CharityRates::default())
+                .await
+                .unwrap()
+                .fed
+                .len(),
+            1
+        );
+
+        clock.advance(Duration::days(6));
+        assert_eq!(
+            feed(&repository, &clock, 100, 1.0, &CharityRates::default())
+                .await
+                .unwrap()
+                .fed
+                .len(),
+            0
+        );
+
+        clock.advance(Duration::hours(13));
+        let feeding = feed(&repository, &clock, 100, 1.0, &CharityRates::default())
+            .await
+            .unwrap();
+        assert_eq!(feeding.fed.len(), 1);
+        assert_eq!(feeding.fed[0].balance, 1200);
+    }
+
+    #[tokio::test]
+    async fn idle_players_lose_more_every_week() {
+        let clock = clock();
+        let repository = InMemoryPlayerRepository::new();
+        repository.insert(&[player("1", 1000, clock.timestamp())]);
+
+        assert_eq!(
+            feed(&repository, &clock, 100, 1.0, &CharityRates::default())
+                .await
+                .unwrap()
+                .fed[0]
+                .balance,
+            1100
+        );
+
+        for (charity, balance) in [(110, 990), (165, 825), (275, 550), (550, 0)] {
+            clock.advance(Duration::weeks(1));
+            let feeding = feed(&repository, &clock, 100, 1.0, &CharityRates::default())
+                .await
+                .unwrap();
+            assert_eq!(feeding.taxed[0].1, charity);
+            assert_eq!(feeding.taxed[0].0.balance, balance);
+        }
+
+        clock.advance(Duration::weeks(1));
+        assert_eq!(
+            feed(&repository, &clock, 100, 1.0, &CharityRates::default())
+                .await
+                .unwrap()
+                .removed
+                .len(),
+            1
+        );
+        assert!(repository.find_player("1").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn charity_goes_to_the_house_without_active_players() {
+        let clock = clock();
+        let now = clock.timestamp();
+        let repository = InMemoryPlayerRepository::new();
+        repository.insert(&[player("1", 1000, now - 3 * WEEK)]);
+
+        repository.add_to_charity_pool(50);
+
+        let feeding = feed(&repository, &clock, 100, 1.0, &CharityRates::default())
+            .await
+            .unwrap();
+
+        assert!(feeding.fed.is_empty());
+        assert_eq!(feeding.taxed[0].1, 333);
+        assert_eq!(feeding.leftover, 383);
+        assert_eq!(repository.find_house_balance().await, 383);
+    }
+
+    #[tokio::test]
+    async fn events_multiply_the_amount() {
+        let clock = clock();
+        let repository = InMemoryPlayerRepository::new();
+        repository.insert(&[player("1", 1000, clock.timestamp())]);
+
+        let feeding = feed(&repository, &clock, 100, 2.5, &CharityRates::default())
+            .await
+            .unwrap();
+
+        assert_eq!(feeding.share, 250);
+        assert_eq!(feeding.fed[0].balance, 1250);
+    }
+
+    #[tokio::test]
+    async fn charity_pool_waits_for_somebody_to_feed() {
+        let clock = clock();
+        let repository = InMemoryPlayerRepository::new();
+        repository.add_to_charity_pool(50);
+
+        let feeding = feed(&repository, &clock, 100, 1.0, &CharityRates::default())
+            .await
+            .unwrap();
+
+        assert_eq!(feeding.share, 0);
+        assert_eq!(repository.find_charity_pool().await, 50);
+        assert_eq!(repository.find_house_balance().await, 0);
+    }
+
+    #[tokio::test]
+    async fn feeding_is_saved_only_when_nobody_changed() {
+        let clock = clock();
+        let now = clock.timestamp();
+        let repository = InMemoryPlayerRepository::new();
+        repository.insert(&[player("1", 1000, now), player("2", 2000, now - WEEK)]);
+        repository.add_to_charity_pool(51);
+
+        let unfeeded = find_unfeeded(&repository, &clock).await;
+        let mut feeding = plan(&unfeeded, now, 100, 51, &CharityRates::default());
+
+        // The player wins a flip while the feeding is planned.
+        let mut winner = repository.find_player("1").await.unwrap();
+        winner.balance += 500;
+        assert!(repository.update_player(&mut winner).await);
+
+        assert!(!apply(&mut feeding, &repository, clock.day()).await);
+        assert_eq!(repository.find_player("1").await.unwrap().balance, 1500);
+        assert_eq!(repository.find_player("2").await.unwrap().balance, 2000);
+        assert_eq!(repository.find_charity_pool().await, 51);
+
+        let feeding = feed(&repository, &clock, 100, 1.0, &CharityRates::default())
+            .await
+            .unwrap();
+
+        assert_eq!(feeding.share, 100 + 251);
+        assert_eq!(repository.find_player("1").await.unwrap().balance, 1851);
+        assert_eq!(repository.find_player("2").await.unwrap().balance, 1800);
+        assert_eq!(repository.find_charity_pool().await, 0);
+    }
+}
@@ -0,0 +1,248 @@
+use rand::Rng;
+
+use crate::database::players::{IdleStatus, Player};
+use crate::internal::betting::{BetAmount, MINIMUM_BET};
+use crate::internal::clock::Clock;
+use crate::internal::events;
+use crate::internal::jackpot;
+use crate::internal::settings::Jackpot;
+
+#[derive(Debug, PartialEq, poise::ChoiceParameter)]
+pub enum CoinSide {
+    #[name = "h"]
+    #[name = "heads"]
+    Heads,
+    #[name = "t"]
+    #[name = "tails"]
+    Tails,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum FlipError {
+    BelowMinimum,
+    InsufficientBalance,
+}
+
+#[derive(Debug)]
+pub struct Flip {
+    pub is_win: bool,
+    /// Potatoes won on top of the bet.
+    pub win: i64,
+    /// Change of the house balance, negative when the house pays.
+    pub house_amount: i64,
+    pub contribution: i64,
+    pub was_dead: bool,
+}
+
+/// Amount of the bet, checked against the balance of the player.
+pub fn stake(
+    bet_amount: &BetAmount,
+    player: &Player,
+    rng: &mut impl Rng,
+) -> Result<i64, FlipError> {
+    let amount = bet_amount.amount_with(player.balance, rng);
+
+    if amount < MINIMUM_BET {
+        return Err(FlipError::BelowMinimum);
+    }
+
+    if amount > player.balance {
+        return Err(FlipError::InsufficientBalance);
+    }
+
+    Ok(amount)
+}
+
+/// Potatoes won on top of the bet with the multiplier of the active events.
+pub fn win(amount: i64, multiplier: f64) -> i64 {
+    events::multiply(amount * 2, multiplier) - amount
+}
+
+/// Tosses the coin and updates the balance of the player, saving the player
+/// together with the house is left to the caller.
+pub fn toss(
+    player: &mut Player,
+    amount: i64,
+    guess: &CoinSide,
+    multiplier: f64,
+    jackpot: &Jackpot,
+    rng: &mut impl Rng,
+    clock: &dyn Clock,
+) -> Flip {
+    let side = if rng.random::<bool>() {
+        CoinSide::Heads
+    } else {
+        CoinSide::Tails
+    };
+
+    let is_win = side == *guess;
+    let win = win(amount, multiplier);
+
+    if is_win {
+        player.balance += win;
+    } else {
+        player.balance -= amount;
+    }
+
+    let now = clock.timestamp();
+    let was_dead = matches!(player.idle_status(now), IdleStatus::Dead);
+
+    player.idle_since_ts = now;
+
+    let contribution = if is_win {
+        0
+    } else {
+        jackpot::contribution(amount, jackpot)
+    };
+
+    let house_amount = if is_win { -win } else { amount - contribution };
+
+    Flip {
+        is_win,
+        win,
+        house_amount,
+        contribution,
+        was_dead,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::clock::FakeClock;
+    use chrono::TimeZone;
+    use chrono_tz::Europe::Tallinn;
+
+    /// Always draws the same number, `u64::MAX` lands on heads and 0 on tails.
+    struct FixedRng(u64);
+
+    impl rand::RngCore for FixedRng {
+        fn next_u32(&mut self) -> u32 {
+            self.0 as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0
+        }
+
+        fn fill_bytes(&mut self, dst: &mut [u8]) {
+            dst.fill(self.0 as u8);
+        }
+    }
+
+    const HEADS: u64 = u64::MAX;
+    const TAILS: u64 = 0;
+
+    fn clock() -> FakeClock {
+        FakeClock::new(Tallinn.with_ymd_and_hms(2026, 10, 21, 12, 0, 0).unwrap())
+    }
+
+    fn jackpot() -> Jackpot {
+        Jackpot {
+            contribution: 0.1,
+            win_chance: 0.0,
+        }
+    }
+
+    #[test]
+    fn stake_checks_the_balance() {
+        let player = Player::new("1", 0);
+        let mut rng = FixedRng(TAILS);
+
+        assert_eq!(stake(&BetAmount::All, &player, &mut rng), Ok(5000));
+        assert_eq!(stake(&BetAmount::Half, &player, &mut rng), Ok(2500));
+        assert_eq!(
+            stake(&BetAmount::Percentage(10), &player, &mut rng),
+            Ok(500)
+        );
+        assert_eq!(
+            stake(&BetAmount::Specific(1), &player, &mut rng),
+            Err(FlipError::BelowMinimum)
+        );
+        assert_eq!(
+            stake(&BetAmount::Specific(5001), &player, &mut rng),
+            Err(FlipError::InsufficientBalance)
+        );
+    }
+
+    #[test]
+    fn winning_pays_the_bet_from_the_house() {
+        let clock = clock();
+        let mut player = Player::new("1", 0);
+
+        let flip = toss(
+            &mut player,
+            1000,
+            &CoinSide::Heads,
+            1.0,
+            &jackpot(),
+            &mut FixedRng(HEADS),
+            &clock,
+        );
+
+        assert!(flip.is_win);
+        assert_eq!(flip.win, 1000);
+        assert_eq!(flip.house_amount, -1000);
+        assert_eq!(flip.contribution, 0);
+        assert_eq!(player.balance, 6000);
+        assert_eq!(player.idle_since_ts, clock.timestamp());
+    }
+
+    #[test]
+    fn losing_feeds_the_house_and_the_jackpot() {
+        let mut player = Player::new("1", 0);
+
+        let flip = toss(
+            &mut player,
+            1000,
+            &CoinSide::Heads,
+            1.0,
+            &jackpot(),
+            &mut FixedRng(TAILS),
+            &clock(),
+        );
+
+        assert!(!flip.is_win);
+        assert_eq!(flip.contribution, 100);
+        assert_eq!(flip.house_amount, 900);
+        assert_eq!(player.balance, 4000);
+    }
+
+    #[test]
+    fn events_multiply_the_win() {
+        let mut player = Player::new("1", 0);
+
+        let flip = toss(
+            &mut player,
+            1000,
+            &CoinSide::Tails,
+            1.5,
+            &jackpot(),
+            &mut FixedRng(TAILS),
+            &clock(),
+        );
+
+        assert_eq!(flip.win, 2000);
+        assert_eq!(player.balance, 7000);
+    }
+
+    #[test]
+    fn dead_players_come_back() {
+        let clock = clock();
+        let mut player = Player::new("1", clock.timestamp());
+        clock.advance(chrono::Duration::weeks(5));
+
+        let flip = toss(
+            &mut player,
+            1000,
+            &CoinSide::Heads,
+            1.0,
+            &jackpot(),
+            &mut FixedRng(HEADS),
+            &clock,
+        );
+
+        assert!(flip.was_dead);
+        assert_eq!(player.idle_since_ts, clock.timestamp());
+    }
+}
@@ -0,0 +1,115 @@
+use crate::database::players::{IdleStatus, Player};
+use crate::database::transfers::Transfer;
+use crate::internal::clock::Clock;
+
+#[derive(Debug, PartialEq)]
+pub enum GiveError {
+    InsufficientBalance,
+}
+
+#[derive(Debug)]
+pub struct Gift {
+    pub transfer: Transfer,
+    pub was_dead: bool,
+}
+
+/// Moves the potatoes from the sender to the receiver minus the fee, saving
+/// the players together with the transfer is left to the caller.
+pub fn give(
+    sender: &mut Player,
+    receiver: &mut Player,
+    amount: i64,
+    fee: f64,
+    clock: &dyn Clock,
+) -> Result<Gift, GiveError> {
+    if sender.balance < amount {
+        return Err(GiveError::InsufficientBalance);
+    }
+
+    let now = clock.timestamp();
+    let was_dead = matches!(sender.idle_status(now), IdleStatus::Dead);
+
+    let fee = (amount as f64 * fee.clamp(0.0, 1.0)).floor() as i64;
+
+    sender.balance -= amount;
+    sender.idle_since_ts = now;
+
+    receiver.balance += amount - fee;
+
+    Ok(Gift {
+        transfer: Transfer {
+            sender_id: sender.discord_user_id.clone(),
+            receiver_id: receiver.discord_user_id.clone(),
+            amount,
+            fee,
+            created_ts: now,
+        },
+        was_dead,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::clock::FakeClock;
+    use chrono::TimeZone;
+    use chrono_tz::Europe::Tallinn;
+
+    fn clock() -> FakeClock {
+        FakeClock::new(Tallinn.with_ymd_and_hms(2026, 10, 21, 12, 0, 0).unwrap())
+    }
+
+    #[test]
+    fn fee_is_kept_from_the_receiver() {
+        let clock = clock();
+        let mut sender = Player::new("1", 0);
+        let mut receiver = Player::new("2", 0);
+
+        let gift = give(&mut sender, &mut receiver, 1000, 0.05, &clock).unwrap();
+
+        assert_eq!(gift.transfer.amount, 1000);
+        assert_eq!(gift.transfer.fee, 50);
+        assert_eq!(gift.transfer.sender_id, "1");
+        assert_eq!(gift.transfer.receiver_id, "2");
+        assert_eq!(gift.transfer.created_ts, clock.timestamp());
+        assert_eq!(sender.balance, 4000);
+        assert_eq!(sender.idle_since_ts, clock.timestamp());
+        assert_eq!(receiver.balance, 5950);
+        assert_eq!(receiver.idle_since_ts, 0);
+    }
+
+    #[test]
+    fn fee_is_clamped() {
+        let mut sender = Player::new("1", 0);
+        let mut receiver = Player::new("2", 0);
+
+        let gift = give(&mut sender, &mut receiver, 1000, 2.0, &clock()).unwrap();
+
+        assert_eq!(gift.transfer.fee, 1000);
+        assert_eq!(receiver.balance, 5000);
+    }
+
+    #[test]
+    fn cannot_give_more_than_the_balance() {
+        let mut sender = Player::new("1", 0);
+        let mut receiver = Player::new("2", 0);
+
+        let result = give(&mut sender, &mut receiver, 5001, 0.0, &clock());
+
+        assert!(matches!(result, Err(GiveError::InsufficientBalance)));
+        assert_eq!(sender.balance, 5000);
+        assert_eq!(receiver.balance, 5000);
+    }
+
+    #[test]
+    fn giving_wakes_up_the_dead() {
+        let clock = clock();
+        let mut sender = Player::new("1", clock.timestamp());
+        let mut receiver = Player::new("2", clock.timestamp());
+        clock.advance(chrono::Duration::weeks(5));
+
+        let gift = give(&mut sender, &mut receiver, 1, 0.0, &clock).unwrap();
+
+        assert!(gift.was_dead);
+    }
+}
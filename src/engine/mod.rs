@@ -0,0 +1,4 @@
+pub mod feeding;
+pub mod flip;
+pub mod give;
+pub mod simulation;
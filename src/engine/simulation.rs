@@ -0,0 +1,342 @@
+use chrono::{DateTime, Duration};
+use chrono_tz::Tz;
+use rand::Rng;
+
+use crate::database::players::{CharityRates, Player};
+use crate::database::repository::{InMemoryPlayerRepository, PlayerRepository};
+use crate::engine::feeding;
+use crate::engine::flip::{self, CoinSide};
+use crate::engine::give;
+use crate::internal::betting::BetAmount;
+use crate::internal::clock::{Clock, FakeClock};
+use crate::internal::settings::{FeeDestination, Jackpot};
+
+/// How the players behave in the simulation.
+#[derive(Debug)]
+pub struct Model {
+    pub weeks: u32,
+    /// Potatoes fed to every active player.
+    pub amount: i64,
+    /// Chance of a player playing during a week.
+    pub activity: f64,
+    /// Coin flips of a player in an active week.
+    pub bets: u32,
+    /// Percentage of the balance bet on every flip and given away on every
+    /// gift.
+    pub stake: i8,
+    /// Gifts of a player to a random other player in an active week.
+    pub gives: u32,
+    /// Part of every gift taken as the fee.
+    pub fee: f64,
+    pub fee_to: FeeDestination,
+    pub jackpot: Jackpot,
+    pub charity: CharityRates,
+}
+
+/// State of the economy after the feeding of the week.
+#[derive(Debug)]
+pub struct Week {
+    pub week: u32,
+    pub players: usize,
+    pub removed: usize,
+    pub total: i64,
+    pub median: i64,
+    pub p10: i64,
+    pub p90: i64,
+    pub max: i64,
+    pub gini: f64,
+    /// Potatoes won by the house so far, negative when it has paid out more.
+    pub house: i64,
+    /// Potatoes waiting in the jackpot pool.
+    pub jackpot: i64,
+}
+
+/// Gini coefficient of the balances, 0 when everybody has the same amount and
+/// close to 1 when one player has everything.
+pub fn gini(balances: &[i64]) -> f64 {
+    let mut balances = balances
+        .iter()
+        .map(|balance| (*balance).max(0))
+        .collect::<Vec<_>>();
+    balances.sort_unstable();
+
+    let n = balances.len() as f64;
+    let total = balances.iter().sum::<i64>() as f64;
+
+    if balances.is_empty() || total == 0.0 {
+        return 0.0;
+    }
+
+    let weighted = balances
+        .iter()
+        .enumerate()
+        .map(|(i, balance)| (i + 1) as f64 * *balance as f64)
+        .sum::<f64>();
+
+    2.0 * weighted / (n * total) - (n + 1.0) / n
+}
+
+fn percentile(sorted: &[i64], percent: usize) -> i64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+
+    sorted[(sorted.len() - 1) * percent / 100]
+}
+
+/// Runs the weeks of play and feeding on a copy of the players. The players
+/// flip coins and give gifts in the middle of the week and get fed on Friday,
+/// `start` should be a Friday after the feeding.
+pub async fn simulate(
+    players: &[Player],
+    model: &Model,
+    start: DateTime<Tz>,
+    rng: &mut impl Rng,
+) -> Vec<Week> {
+    let repository = InMemoryPlayerRepository::new();
+    repository.insert(players);
+
+    let clock = FakeClock::new(start);
+    let mut house = 0i64;
+    let mut jackpot = 0i64;
+    let mut weeks = vec![];
+
+    for week in 1..=model.weeks {
+        clock.advance(Duration::days(5));
+
+        let user_ids = repository
+            .load_players_by_balance()
+            .await
+            .into_iter()
+            .map(|player| player.discord_user_id)
+            .collect::<Vec<_>>();
+
+        for user_id in &user_ids {
+            if !rng.random_bool(model.activity.clamp(0.0, 1.0)) {
+                continue;
+            }
+
+            // Gifts of the players before may have changed the balance.
+            let Some(mut player) = repository.find_player(user_id).await else {
+                continue;
+            };
+
+            for _ in 0..model.bets {
+                let bet_amount = BetAmount::Percentage(model.stake);
+                let Ok(amount) = flip::stake(&bet_amount, &player, rng) else {
+                    break;
+                };
+                let guess = if rng.random::<bool>() {
+                    CoinSide::Heads
+                } else {
+                    CoinSide::Tails
+                };
+
+                let flip = flip::toss(
+                    &mut player,
+                    amount,
+                    &guess,
+                    1.0,
+                    &model.jackpot,
+                    rng,
+                    &clock,
+                );
+                house += flip.house_amount;
+                jackpot += flip.contribution;
+
+                if jackpot > 0 && rng.random_bool(model.jackpot.win_chance.clamp(0.0, 1.0)) {
+                    player.balance += jackpot;
+                    jackpot = 0;
+                }
+            }
+
+            repository.update_player(&mut player).await;
+
+            for _ in 0..model.gives {
+                let receiver_id = &user_ids[rng.random_range(0..user_ids.len())];
+                if receiver_id == user_id {
+                    continue;
+                }
+
+                let (Some(mut sender), Some(mut receiver)) = (
+                    repository.find_player(user_id).await,
+                    repository.find_player(receiver_id).await,
+                ) else {
+                    continue;
+                };
+
+                let bet_amount = BetAmount::Percentage(model.stake);
+                let Ok(amount) = flip::stake(&bet_amount, &sender, rng) else {
+                    break;
+                };
+                let Ok(gift) = give::give(&mut sender, &mut receiver, amount, model.fee, &clock)
+                else {
+                    break;
+                };
+
+                repository.update_player(&mut sender).await;
+                repository.update_player(&mut receiver).await;
+
+                match model.fee_to {
+                    FeeDestination::House => house += gift.transfer.fee,
+                    FeeDestination::Charity => repository.add_to_charity_pool(gift.transfer.fee),
+                }
+            }
+        }
+
+        clock.advance(Duration::days(2));
+
+        let feeding = feeding::feed(&repository, &clock, model.amount, 1.0, &model.charity)
+            .await
+            .unwrap_or_default();
+        house += feeding.leftover;
+
+        let mut balances = repository
+            .load_players_by_balance()
+            .await
+            .iter()
+            .map(|player| player.balance)
+            .collect::<Vec<_>>();
+        balances.sort_unstable();
+
+        weeks.push(Week {
+            week,
+            players: balances.len(),
+            removed: feeding.removed.len(),
+            total: balances.iter().sum(),
+            median: percentile(&balances, 50),
+            p10: percentile(&balances, 10),
+            p90: percentile(&balances, 90),
+            max: balances.last().copied().unwrap_or_default(),
+            gini: gini(&balances),
+            house,
+            jackpot,
+        });
+    }
+
+    weeks
+}
+
+/// New players who have just been fed at `ts`.
+pub fn synthetic_players(count: usize, ts: i64) -> Vec<Player> {
+    (1..=count)
+        .map(|id| Player::new(&id.to_string(), ts))
+        .collect()
+}
+
+/// The time the simulation starts at, the Friday noon after the last feeding.
+pub fn start(clock: &dyn Clock) -> DateTime<Tz> {
+    feeding::last_friday(clock.now()) + Duration::hours(12)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use chrono_tz::Europe::Tallinn;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn gini_of_equal_and_unequal_balances() {
+        assert_eq!(gini(&[]), 0.0);
+        assert_eq!(gini(&[0, 0]), 0.0);
+        assert!(gini(&[100, 100, 100, 100]).abs() < 1e-9);
+        assert!((gini(&[0, 0, 0, 100]) - 0.75).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn idle_players_lose_their_potatoes_to_the_house() {
+        let start = Tallinn.with_ymd_and_hms(2026, 10, 23, 12, 0, 0).unwrap();
+        let players = synthetic_players(10, start.timestamp());
+        let model = Model {
+            weeks: 3,
+            amount: 100,
+            activity: 0.0,
+            bets: 5,
+            stake: 10,
+            gives: 0,
+            fee: 0.0,
+            fee_to: FeeDestination::Charity,
+            jackpot: Jackpot {
+                contribution: 0.0,
+                win_chance: 0.0,
+            },
+            charity: CharityRates::default(),
+        };
+
+        let weeks = simulate(&players, &model, start, &mut StdRng::seed_from_u64(1)).await;
+
+        assert_eq!(weeks.len(), 3);
+        assert_eq!(weeks[2].players, 10);
+        assert_eq!(
+            weeks.iter().map(|week| week.total).collect::<Vec<_>>(),
+            vec![45_000, 37_500, 25_000]
+        );
+        assert_eq!(weeks[2].house, 25_000);
+        assert_eq!(weeks[2].gini, 0.0);
+    }
+
+    #[tokio::test]
+    async fn the_same_seed_gives_the_same_weeks() {
+        let start = Tallinn.with_ymd_and_hms(2026, 10, 23, 12, 0, 0).unwrap();
+        let players = synthetic_players(20, start.timestamp());
+        let model = Model {
+            weeks: 6,
+            amount: 100,
+            activity: 0.5,
+            bets: 5,
+            stake: 50,
+            gives: 1,
+            fee: 0.02,
+            fee_to: FeeDestination::Charity,
+            jackpot: Jackpot {
+                contribution: 0.01,
+                win_chance: 0.01,
+            },
+            charity: CharityRates::default(),
+        };
+
+        let first = simulate(&players, &model, start, &mut StdRng::seed_from_u64(7)).await;
+        let second = simulate(&players, &model, start, &mut StdRng::seed_from_u64(7)).await;
+
+        let totals = |weeks: &[Week]| {
+            weeks
+                .iter()
+                .map(|week| (week.total, week.house, week.jackpot, week.removed))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(totals(&first), totals(&second));
+        assert!(first[5].gini > 0.0);
+    }
+
+    #[tokio::test]
+    async fn no_potato_is_lost_to_fees_and_the_jackpot() {
+        let start = Tallinn.with_ymd_and_hms(2026, 10, 23, 12, 0, 0).unwrap();
+        let players = synthetic_players(10, start.timestamp());
+
+        for fee_to in [FeeDestination::Charity, FeeDestination::House] {
+            let model = Model {
+                weeks: 4,
+                amount: 0,
+                activity: 0.7,
+                bets: 3,
+                stake: 20,
+                gives: 2,
+                fee: 0.1,
+                fee_to,
+                jackpot: Jackpot {
+                    contribution: 0.1,
+                    win_chance: 0.2,
+                },
+                charity: CharityRates::default(),
+            };
+
+            let weeks = simulate(&players, &model, start, &mut StdRng::seed_from_u64(3)).await;
+
+            for week in &weeks {
+                assert_eq!(week.total + week.house + week.jackpot, 50_000);
+            }
+        }
+    }
+}
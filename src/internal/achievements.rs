@@ -0,0 +1,242 @@
+use poise::serenity_prelude as serenity;
+use tracing::{error, info, warn};
+
+use crate::database::achievements::{
+    find_achievement_progress, save_achievement_progress, unlock_achievement, AchievementProgress,
+};
+use crate::internal::data::Context;
+
+const WINNING_STREAK_LENGTH: i64 = 5;
+const PHILANTHROPIST_AMOUNT: i64 = 100_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Achievement {
+    LostEverything,
+    WinningStreak,
+    BackFromTheDead,
+    Philanthropist,
+}
+
+pub const ALL_ACHIEVEMENTS: [Achievement; 4] = [
+    Achievement::LostEverything,
+    Achievement::WinningStreak,
+    Achievement::BackFromTheDead,
+    Achievement::Philanthropist,
+];
+
+impl Achievement {
+    pub fn key(&self) -> &'static str {
+        match self {
+            Achievement::LostEverything => "lost_everything",
+            Achievement::WinningStreak => "winning_streak",
+            Achievement::BackFromTheDead => "back_from_the_dead",
+            Achievement::Philanthropist => "philanthropist",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Self> {
+        ALL_ACHIEVEMENTS.into_iter().find(|a| a.key() == key)
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Achievement::LostEverything => "Kõik või mitte midagi",
+            Achievement::WinningStreak => "Kuum käsi",
+            Achievement::BackFromTheDead => "Surnuist ülestõusnud",
+            Achievement::Philanthropist => "Heategija",
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Achievement::LostEverything => "Kaotas `!flip all` peale kõik oma :potato:.".into(),
+            Achievement::WinningStreak => {
+                format!("Võitis {} flipi järjest.", WINNING_STREAK_LENGTH)
+            }
+            Achievement::BackFromTheDead => {
+                "Jõudis :skull: staatusesse ja tuli tagasi mängima.".into()
+            }
+            Achievement::Philanthropist => {
+                format!("Kinkis kokku ära {} :potato:.", PHILANTHROPIST_AMOUNT)
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum GameEvent {
+    Flip { bet_all: bool, is_win: bool },
+    Give { amount: i64 },
+    ReturnedFromDead,
+}
+
+/// Moves the progress of the player along with the events, returns the
+/// achievements they reached, unlocked before or not.
+fn advance(progress: &mut AchievementProgress, events: &[GameEvent]) -> Vec<Achievement> {
+    let mut unlocked = Vec::new();
+
+    for event in events {
+        match *event {
+            GameEvent::Flip { bet_all, is_win } => {
+                if is_win {
+                    progress.flip_win_streak += 1;
+                    if progress.flip_win_streak >= WINNING_STREAK_LENGTH {
+                        unlocked.push(Achievement::WinningStreak);
+                    }
+                } else {
+                    progress.flip_win_streak = 0;
+                    if bet_all {
+                        unlocked.push(Achievement::LostEverything);
+                    }
+                }
+            }
+            GameEvent::Give { amount } => {
+                progress.total_given += amount;
+                if progress.total_given >= PHILANTHROPIST_AMOUNT {
+                    unlocked.push(Achievement::Philanthropist);
+                }
+            }
+            GameEvent::ReturnedFromDead => unlocked.push(Achievement::BackFromTheDead),
+        }
+    }
+
+    unlocked
+}
+
+/// Updates achievement progress of the player and announces every newly
+/// unlocked achievement in the potato channel.
+pub async fn record(ctx: &Context<'_>, user_id: serenity::UserId, events: &[GameEvent]) {
+    if events.is_empty() {
+        return;
+    }
+
+    let database = &ctx.data().database;
+    let discord_user_id = user_id.to_string();
+
+    let mut progress = find_achievement_progress(&discord_user_id, database).await;
+    let unlocked = advance(&mut progress, events);
+
+    if !save_achievement_progress(&progress, database).await {
+        warn!(
+            "Could not save achievement progress of user {}",
+            discord_user_id
+        );
+    }
+
+    let now = ctx.data().clock.timestamp();
+
+    for achievement in unlocked {
+        if !unlock_achievement(&discord_user_id, achievement.key(), now, database).await {
+            continue;
+        }
+
+        info!(
+            "User {} unlocked achievement {}",
+            discord_user_id,
+            achievement.key()
+        );
+
+        let message = serenity::CreateMessage::new().content(format!(
+            ":trophy: {} avas saavutuse **{}** - {}",
+            serenity::Mention::from(user_id),
+            achievement.title(),
+            achievement.description()
+        ));
+
+        if let Err(why) = ctx
+            .data()
+            .potato_channel_id
+            .send_message(ctx, message)
+            .await
+        {
+            error!("Error sending message: {why:?}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::achievements::load_achievements;
+
+    fn progress() -> AchievementProgress {
+        AchievementProgress {
+            discord_user_id: "1".into(),
+            flip_win_streak: 0,
+            total_given: 0,
+        }
+    }
+
+    const WIN: GameEvent = GameEvent::Flip {
+        bet_all: false,
+        is_win: true,
+    };
+
+    #[test]
+    fn winning_streak_is_reached_on_the_fifth_win_in_a_row() {
+        let mut progress = progress();
+
+        assert!(advance(&mut progress, &[WIN; 4]).is_empty());
+        assert_eq!(
+            advance(&mut progress, &[WIN]),
+            vec![Achievement::WinningStreak]
+        );
+    }
+
+    #[test]
+    fn loss_resets_the_streak() {
+        let mut progress = progress();
+        let loss = GameEvent::Flip {
+            bet_all: false,
+            is_win: false,
+        };
+
+        assert!(advance(&mut progress, &[WIN, WIN, WIN, WIN, loss, WIN]).is_empty());
+        assert_eq!(progress.flip_win_streak, 1);
+    }
+
+    #[test]
+    fn losing_everything_needs_betting_all() {
+        let mut progress = progress();
+        let lost_all = GameEvent::Flip {
+            bet_all: true,
+            is_win: false,
+        };
+
+        assert_eq!(
+            advance(&mut progress, &[lost_all]),
+            vec![Achievement::LostEverything]
+        );
+    }
+
+    #[test]
+    fn gifts_add_up_to_philanthropist() {
+        let mut progress = progress();
+
+        assert!(advance(&mut progress, &[GameEvent::Give { amount: 60_000 }]).is_empty());
+        assert_eq!(
+            advance(&mut progress, &[GameEvent::Give { amount: 40_000 }]),
+            vec![Achievement::Philanthropist]
+        );
+    }
+
+    #[tokio::test]
+    async fn achievement_is_unlocked_once_and_progress_is_kept() {
+        let database = crate::database::in_memory().await;
+        let user_id = "1".to_string();
+
+        let mut progress = find_achievement_progress(&user_id, &database).await;
+        advance(&mut progress, &[WIN, WIN, GameEvent::Give { amount: 500 }]);
+        assert!(save_achievement_progress(&progress, &database).await);
+
+        let saved = find_achievement_progress(&user_id, &database).await;
+        assert_eq!(saved.flip_win_streak, 2);
+        assert_eq!(saved.total_given, 500);
+
+        assert!(unlock_achievement(&user_id, "back_from_the_dead", 10, &database).await);
+        assert!(!unlock_achievement(&user_id, "back_from_the_dead", 20, &database).await);
+        let unlocked = load_achievements(&user_id, &database).await;
+        assert_eq!(unlocked.len(), 1);
+        assert_eq!(unlocked[0].unlocked_ts, 10);
+    }
+}
@@ -0,0 +1,249 @@
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, TimeZone};
+use chrono_tz::Tz;
+use sqlx::{Pool, Sqlite};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{error, info, instrument, warn};
+
+use crate::database::backup::backup_database;
+use crate::internal::clock::Clock;
+use crate::internal::settings::Backups;
+
+const FILENAME_FORMAT: &str = "etbot-%Y%m%d-%H%M%S.sqlite";
+
+#[derive(Clone, Debug)]
+pub struct Backup {
+    pub path: PathBuf,
+    pub taken: DateTime<Tz>,
+    pub size: u64,
+}
+
+/// Backups in the directory, the newest first. The names carry the time the
+/// backups were taken in `tz`.
+pub fn list_backups(directory: &Path, tz: &Tz) -> Vec<Backup> {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return vec![];
+    };
+
+    let mut backups = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let taken = NaiveDateTime::parse_from_str(&name, FILENAME_FORMAT).ok()?;
+            Some(Backup {
+                path: entry.path(),
+                taken: tz.from_local_datetime(&taken).earliest()?,
+                size: entry.metadata().map(|metadata| metadata.len()).unwrap_or(0),
+            })
+        })
+        .collect::<Vec<_>>();
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.taken));
+
+    backups
+}
+
+/// Backups not needed anymore: the newest backup of each of the last
+/// `keep_daily` days and of each of the last `keep_weekly` weeks is kept, so
+/// is the newest backup overall. `backups` are the newest first.
+pub fn expired(backups: &[Backup], keep_daily: usize, keep_weekly: usize) -> Vec<Backup> {
+    let mut days = HashSet::<NaiveDate>::new();
+    let mut weeks = HashSet::<(i32, u32)>::new();
+    let mut expired = vec![];
+
+    for (i, backup) in backups.iter().enumerate() {
+        let day = backup.taken.date_naive();
+        let week = (day.iso_week().year(), day.iso_week().week());
+
+        let is_daily = days.len() < keep_daily && days.insert(day);
+        let is_weekly = weeks.len() < keep_weekly && weeks.insert(week);
+
+        if i > 0 && !is_daily && !is_weekly {
+            expired.push(backup.clone());
+        }
+    }
+
+    expired
+}
+
+/// Backs up the database into the backup directory and removes the backups
+/// falling out of the retention.
+#[instrument]
+pub async fn backup_now(
+    database: &Pool<Sqlite>,
+    settings: &Backups,
+    clock: &dyn Clock,
+) -> Option<Backup> {
+    let directory = Path::new(&settings.directory);
+    if let Err(why) = std::fs::create_dir_all(directory) {
+        error!("Could not create {}: {why:?}", directory.display());
+        return None;
+    }
+
+    let taken = clock.now();
+    let path = directory.join(taken.format(FILENAME_FORMAT).to_string());
+
+    info!("Backing up the database to {} ...", path.display());
+
+    if !backup_database(database, &path).await {
+        let _ = std::fs::remove_file(&path);
+        return None;
+    }
+
+    for backup in expired(
+        &list_backups(directory, &taken.timezone()),
+        settings.keep_daily,
+        settings.keep_weekly,
+    ) {
+        info!("Removing old backup {} ...", backup.path.display());
+        if let Err(why) = std::fs::remove_file(&backup.path) {
+            warn!("Could not remove {}: {why:?}", backup.path.display());
+        }
+    }
+
+    Some(Backup {
+        size: std::fs::metadata(&path)
+            .map(|metadata| metadata.len())
+            .unwrap_or(0),
+        path,
+        taken,
+    })
+}
+
+/// Backs up the database whenever the newest backup is older than the
+/// interval, so restarts don't reset the schedule.
+#[derive(Debug)]
+pub struct BackupKeeper {
+    database: Pool<Sqlite>,
+    settings: Backups,
+    clock: Arc<dyn Clock>,
+    is_running: Mutex<bool>,
+}
+
+impl BackupKeeper {
+    pub fn new(database: Pool<Sqlite>, settings: Backups, clock: Arc<dyn Clock>) -> Self {
+        BackupKeeper {
+            database,
+            settings,
+            clock,
+            is_running: Mutex::new(false),
+        }
+    }
+
+    #[instrument]
+    pub fn start(&self) {
+        let mut is_running = self.is_running.lock().unwrap();
+        if *is_running {
+            return;
+        }
+
+        *is_running = true;
+
+        let database = self.database.clone();
+        let settings = self.settings.clone();
+        let clock = self.clock.clone();
+
+        tokio::spawn(async move {
+            let mut interval_timer = tokio::time::interval(duration_str::parse("1m").unwrap());
+
+            loop {
+                interval_timer.tick().await;
+
+                let is_due =
+                    match list_backups(Path::new(&settings.directory), &clock.timezone()).first() {
+                        Some(newest) => (clock.now() - newest.taken)
+                            .to_std()
+                            .is_ok_and(|age| age >= settings.interval),
+                        None => true,
+                    };
+
+                if is_due
+                    && backup_now(&database, &settings, clock.as_ref())
+                        .await
+                        .is_none()
+                {
+                    error!("Scheduled database backup failed");
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use chrono_tz::Europe::Tallinn;
+
+    fn backups(taken: &[DateTime<Tz>]) -> Vec<Backup> {
+        taken
+            .iter()
+            .map(|taken| Backup {
+                path: PathBuf::from(taken.format(FILENAME_FORMAT).to_string()),
+                taken: *taken,
+                size: 0,
+            })
+            .collect()
+    }
+
+    fn names(backups: &[Backup]) -> Vec<String> {
+        backups
+            .iter()
+            .map(|backup| backup.path.display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn keeps_the_newest_backup_of_each_day_and_week() {
+        // Monday noon, then every 12 hours back for four weeks.
+        let now = Tallinn.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap();
+        let all = backups(
+            &(0..56)
+                .map(|i| now - Duration::hours(12 * i))
+                .collect::<Vec<_>>(),
+        );
+
+        let expired = names(&expired(&all, 2, 3));
+        let kept = all
+            .iter()
+            .filter(|backup| !expired.contains(&backup.path.display().to_string()))
+            .map(|backup| backup.taken)
+            .collect::<Vec<_>>();
+
+        // Monday and Sunday, then the Sunday noons ending the previous weeks.
+        assert_eq!(
+            kept,
+            vec![now, now - Duration::days(1), now - Duration::days(8)]
+        );
+    }
+
+    #[test]
+    fn the_newest_backup_is_always_kept() {
+        let now = Tallinn.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap();
+        let all = backups(&[now, now - Duration::days(1)]);
+
+        assert_eq!(names(&expired(&all, 0, 0)), names(&all[1..]));
+    }
+
+    #[test]
+    fn backup_filenames_are_parsed() {
+        let directory = std::env::temp_dir().join(format!("etbot-backups-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        for name in [
+            "etbot-20261018-120000.sqlite",
+            "etbot-20261019-120000.sqlite",
+            "notes.txt",
+        ] {
+            std::fs::write(directory.join(name), b"").unwrap();
+        }
+
+        let listed = list_backups(&directory, &Tallinn);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(listed.len(), 2);
+        assert_eq!(
+            listed[0].taken,
+            Tallinn.with_ymd_and_hms(2026, 10, 19, 12, 0, 0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use rand::Rng;
+use std::str::FromStr;
+
+pub const MINIMUM_BET: i64 = 2;
+
+/// Bet amount as written by the player, shared by every game so they all
+/// accept the same syntax: `all|half|some|<amount>[%]`.
+#[derive(Debug)]
+pub enum BetAmount {
+    Specific(i64),
+    Percentage(i8),
+    Half,
+    All,
+    Some,
+}
+
+#[derive(Debug)]
+pub struct ParseBetAmountError;
+
+impl FromStr for BetAmount {
+    type Err = ParseBetAmountError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().trim() {
+            "all" => Ok(BetAmount::All),
+            "half" => Ok(BetAmount::Half),
+            "some" => Ok(BetAmount::Some),
+            val if val.ends_with('%') => {
+                val.trim_end_matches('%')
+                    .parse::<i8>()
+                    .ok()
+                    .map_or(Err(ParseBetAmountError), |v| match v {
+                        x if (0..=100).contains(&x) => Ok(BetAmount::Percentage(x)),
+                        _ => Err(ParseBetAmountError),
+                    })
+            }
+            val => val
+                .parse::<i64>()
+                .ok()
+                .map_or(Err(ParseBetAmountError), |v| match v {
+                    x if x >= 0 => Ok(BetAmount::Specific(v)),
+                    _ => Err(ParseBetAmountError),
+                }),
+        }
+    }
+}
+
+impl BetAmount {
+    /// Amount of potatoes the bet stands for with the given balance, the
+    /// randomness of `some` is drawn from `rng`.
+    pub fn amount_with(&self, balance: i64, rng: &mut impl Rng) -> i64 {
+        match self {
+            BetAmount::All => balance,
+            BetAmount::Half => balance / 2,
+            BetAmount::Some if balance < MINIMUM_BET => balance,
+            BetAmount::Some => rng.random_range(MINIMUM_BET..=balance),
+            BetAmount::Specific(v) => *v,
+            BetAmount::Percentage(v) => (*v) as i64 * balance / 100i64,
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use rand::seq::SliceRandom;
+use std::cmp::Ordering;
+
+const RANKS: &str = "23456789TJQKA";
+const SUITS: &str = "shdc";
+
+/// Card in `0..52`, rank is `card % 13` (deuce first) and suit `card / 13`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Card(u8);
+
+impl Card {
+    pub fn rank(&self) -> u8 {
+        self.0 % 13
+    }
+
+    pub fn suit(&self) -> u8 {
+        self.0 / 13
+    }
+
+    /// Two character code like `Ah` used for storing cards.
+    pub fn code(&self) -> String {
+        let rank = RANKS.as_bytes()[self.rank() as usize] as char;
+        let suit = SUITS.as_bytes()[self.suit() as usize] as char;
+        format!("{}{}", rank, suit)
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        let mut chars = code.chars();
+        let rank = RANKS.find(chars.next()?)?;
+        let suit = SUITS.find(chars.next()?)?;
+        match chars.next() {
+            Some(_) => None,
+            None => Some(Card((suit * 13 + rank) as u8)),
+        }
+    }
+}
+
+impl std::fmt::Display for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rank = match self.rank() {
+            8 => "10".to_string(),
+            r => (RANKS.as_bytes()[r as usize] as char).to_string(),
+        };
+        let suit = ["♠", "♥", "♦", "♣"][self.suit() as usize];
+        write!(f, "{}{}", rank, suit)
+    }
+}
+
+pub fn shuffled_deck() -> Vec<Card> {
+    let mut deck = (0..52).map(Card).collect::<Vec<_>>();
+    deck.shuffle(&mut rand::rng());
+    deck
+}
+
+pub fn encode_cards(cards: &[Card]) -> String {
+    cards
+        .iter()
+        .map(|card| card.code())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn decode_cards(cards: &str) -> Vec<Card> {
+    cards
+        .split_whitespace()
+        .filter_map(Card::from_code)
+        .collect()
+}
+
+pub fn display_cards(cards: &[Card]) -> String {
+    cards
+        .iter()
+        .map(|card| format!("`{}`", card))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HandCategory {
+    HighCard,
+    Pair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+}
+
+impl HandCategory {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HandCategory::HighCard => "kõrge kaart",
+            HandCategory::Pair => "paar",
+            HandCategory::TwoPair => "kaks paari",
+            HandCategory::ThreeOfAKind => "kolmik",
+            HandCategory::Straight => "rida",
+            HandCategory::Flush => "mast",
+            HandCategory::FullHouse => "maja",
+            HandCategory::FourOfAKind => "nelik",
+            HandCategory::StraightFlush => "mastirida",
+        }
+    }
+}
+
+/// Strength of a five card hand, compares by category and then by the
+/// ranks that decide ties within the category.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HandRank {
+    pub category: HandCategory,
+    tiebreak: Vec<u8>,
+}
+
+impl Ord for HandRank {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.category
+            .cmp(&other.category)
+            .then_with(|| self.tiebreak.cmp(&other.tiebreak))
+    }
+}
+
+impl PartialOrd for HandRank {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn rank_five(cards: &[Card; 5]) -> HandRank {
+    let mut counts = [0u8; 13];
+    for card in cards {
+        counts[card.rank() as usize] += 1;
+    }
+
+    // Ranks ordered by how many times they appear, then by rank.
+    let mut groups = (0..13u8)
+        .filter(|&r| counts[r as usize] > 0)
+        .map(|r| (counts[r as usize], r))
+        .collect::<Vec<_>>();
+    groups.sort_by(|a, b| b.cmp(a));
+
+    let is_flush = cards.iter().all(|card| card.suit() == cards[0].suit());
+    let straight_high = match groups.len() {
+        5 if groups[0].1 - groups[4].1 == 4 => Some(groups[0].1),
+        5 if groups.iter().map(|g| g.1).eq([12, 3, 2, 1, 0]) => Some(3),
+        _ => None,
+    };
+
+    let tiebreak = groups.iter().map(|g| g.1).collect::<Vec<_>>();
+
+    let (category, tiebreak) = match (straight_high, is_flush, groups[0].0, groups[1].0) {
+        (Some(high), true, _, _) => (HandCategory::StraightFlush, vec![high]),
+        (_, _, 4, _) => (HandCategory::FourOfAKind, tiebreak),
+        (_, _, 3, 2) => (HandCategory::FullHouse, tiebreak),
+        (_, true, _, _) => (HandCategory::Flush, tiebreak),
+        (Some(high), _, _, _) => (HandCategory::Straight, vec![high]),
+        (_, _, 3, _) => (HandCategory::ThreeOfAKind, tiebreak),
+        (_, _, 2, 2) => (HandCategory::TwoPair, tiebreak),
+        (_, _, 2, _) => (HandCategory::Pair, tiebreak),
+        _ => (HandCategory::HighCard, tiebreak),
+    };
+
+    HandRank { category, tiebreak }
+}
+
+/// Best five card hand out of five to seven cards.
+pub fn best_hand(cards: &[Card]) -> HandRank {
+    let n = cards.len();
+    let mut best: Option<HandRank> = None;
+
+    for a in 0..n {
+        for b in a + 1..n {
+            for c in b + 1..n {
+                for d in c + 1..n {
+                    for e in d + 1..n {
+                        let rank = rank_five(&[cards[a], cards[b], cards[c], cards[d], cards[e]]);
+                        if best.as_ref().is_none_or(|best| rank > *best) {
+                            best = Some(rank);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    best.expect("at least five cards are needed to rank a hand")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hand(codes: &str) -> HandRank {
+        best_hand(&decode_cards(codes))
+    }
+
+    #[test]
+    fn wheel_is_the_lowest_straight() {
+        let wheel = hand("Ah 2s 3d 4c 5h Kd Ks");
+        let six_high = hand("2s 3d 4c 5h 6s Kd Ks");
+
+        assert_eq!(wheel.category, HandCategory::Straight);
+        assert!(wheel < six_high);
+        assert!(wheel > hand("As Ah Ad 4c 5h 9d Ks"));
+        assert_eq!(
+            hand("Ah 2h 3h 4h 5h Kd Ks").category,
+            HandCategory::StraightFlush
+        );
+    }
+
+    #[test]
+    fn kickers_break_ties() {
+        let board = "As Ad 7c 4h 2s";
+        let king = hand(&format!("{} Kd 9c", board));
+        let queen = hand(&format!("{} Qd Jc", board));
+
+        assert_eq!(king.category, HandCategory::Pair);
+        assert!(king > queen);
+
+        // The kicker only counts when it makes it into the best five cards.
+        let board = "As Ad Kc Qh Js";
+        assert_eq!(
+            hand(&format!("{} 3d 2c", board)),
+            hand(&format!("{} 4d 3c", board))
+        );
+    }
+
+    #[test]
+    fn best_five_of_seven() {
+        assert_eq!(
+            hand("Ks Kd Kc 7h 7s 7d 2c").category,
+            HandCategory::FullHouse
+        );
+        assert_eq!(hand("2h 5h 9h Jh Kh Ah Ad").category, HandCategory::Flush);
+        assert!(hand("Ks Kd 7c 7h 2s 2d Ac") > hand("Ks Kd 7c 7h 2s 2d Qc"));
+    }
+}
@@ -0,0 +1,210 @@
+use poise::serenity_prelude as serenity;
+use tracing::warn;
+
+use crate::internal::data::{Context, Error};
+use crate::internal::settings::{ChannelRule, Channels};
+
+#[derive(Debug, PartialEq)]
+enum Verdict {
+    Allowed,
+    Denied,
+    /// The command can only be used in these channels.
+    OnlyIn(Vec<serenity::ChannelId>),
+}
+
+#[derive(Debug)]
+pub struct ChannelRules {
+    casino_channels: Vec<serenity::ChannelId>,
+    settings: Channels,
+}
+
+impl ChannelRules {
+    pub fn new(potato_channel_id: serenity::ChannelId, settings: Channels) -> Self {
+        let mut casino_channels = vec![potato_channel_id];
+        for id in settings.casino.iter().filter(|id| **id != 0) {
+            let channel_id = serenity::ChannelId::new(*id);
+            if !casino_channels.contains(&channel_id) {
+                casino_channels.push(channel_id);
+            }
+        }
+
+        ChannelRules {
+            casino_channels,
+            settings,
+        }
+    }
+
+    /// Rule of the command, subcommands fall back to the rule of the top
+    /// level command.
+    fn find(&self, qualified_name: &str) -> Option<&ChannelRule> {
+        self.settings.commands.get(qualified_name).or_else(|| {
+            qualified_name
+                .split_whitespace()
+                .next()
+                .and_then(|name| self.settings.commands.get(name))
+        })
+    }
+
+    fn verdict(&self, qualified_name: &str, channel_id: serenity::ChannelId) -> Verdict {
+        let rule = self.find(qualified_name);
+
+        if rule.is_some_and(|rule| rule.deny.contains(&channel_id.get())) {
+            return Verdict::Denied;
+        }
+
+        if rule.is_some_and(|rule| rule.anywhere) {
+            return Verdict::Allowed;
+        }
+
+        let allowed = match rule {
+            Some(rule) if !rule.allow.is_empty() => rule
+                .allow
+                .iter()
+                .filter(|id| **id != 0)
+                .map(|id| serenity::ChannelId::new(*id))
+                .collect(),
+            _ => self.casino_channels.clone(),
+        };
+
+        if allowed.contains(&channel_id) {
+            Verdict::Allowed
+        } else {
+            Verdict::OnlyIn(allowed)
+        }
+    }
+}
+
+/// Command check rejecting commands used outside of their channels, the
+/// player is told where the command can be used instead. Slash commands get
+/// an ephemeral reply, prefix commands get a reaction and a direct message
+/// so that the channel stays clean.
+pub async fn check(ctx: Context<'_>) -> Result<bool, Error> {
+    let verdict = ctx
+        .data()
+        .channels
+        .verdict(&ctx.command().qualified_name, ctx.channel_id());
+
+    let content = match verdict {
+        Verdict::Allowed => return Ok(true),
+        Verdict::Denied => "Seda käsku ei saa selles kanalis kasutada.".to_string(),
+        Verdict::OnlyIn(channels) => format!(
+            "Seda käsku saab kasutada ainult kanalites {}.",
+            channels
+                .iter()
+                .map(|channel_id| serenity::Mention::from(*channel_id).to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+
+    match ctx {
+        poise::Context::Application(_) => {
+            let reply = poise::CreateReply::default()
+                .content(content)
+                .ephemeral(true);
+            ctx.send(reply).await?;
+        }
+        poise::Context::Prefix(prefix) => {
+            if let Err(why) = prefix.msg.react(ctx, '🚫').await {
+                warn!("Error reacting to message: {why:?}");
+            }
+            let message = serenity::CreateMessage::new().content(content);
+            if let Err(why) = ctx.author().direct_message(ctx, message).await {
+                warn!("Error sending direct message: {why:?}");
+            }
+        }
+    }
+
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    const POTATO: serenity::ChannelId = serenity::ChannelId::new(1);
+    const CASINO: serenity::ChannelId = serenity::ChannelId::new(2);
+    const OTHER: serenity::ChannelId = serenity::ChannelId::new(3);
+
+    fn rules(commands: Vec<(&str, ChannelRule)>) -> ChannelRules {
+        ChannelRules::new(
+            POTATO,
+            Channels {
+                casino: vec![CASINO.get(), 0],
+                commands: commands
+                    .into_iter()
+                    .map(|(name, rule)| (name.to_string(), rule))
+                    .collect::<HashMap<_, _>>(),
+            },
+        )
+    }
+
+    #[test]
+    fn commands_without_a_rule_stay_in_the_casino_channels() {
+        let rules = rules(vec![]);
+
+        assert_eq!(rules.verdict("flip", POTATO), Verdict::Allowed);
+        assert_eq!(rules.verdict("flip", CASINO), Verdict::Allowed);
+        assert_eq!(
+            rules.verdict("flip", OTHER),
+            Verdict::OnlyIn(vec![POTATO, CASINO])
+        );
+    }
+
+    #[test]
+    fn allowlist_replaces_the_casino_channels() {
+        let rules = rules(vec![(
+            "poker",
+            ChannelRule {
+                allow: vec![OTHER.get()],
+                ..Default::default()
+            },
+        )]);
+
+        assert_eq!(rules.verdict("poker", OTHER), Verdict::Allowed);
+        assert_eq!(rules.verdict("poker", POTATO), Verdict::OnlyIn(vec![OTHER]));
+    }
+
+    #[test]
+    fn deny_wins_over_anywhere() {
+        let rules = rules(vec![(
+            "balance",
+            ChannelRule {
+                anywhere: true,
+                deny: vec![CASINO.get()],
+                ..Default::default()
+            },
+        )]);
+
+        assert_eq!(rules.verdict("balance", OTHER), Verdict::Allowed);
+        assert_eq!(rules.verdict("balance", CASINO), Verdict::Denied);
+    }
+
+    #[test]
+    fn subcommands_fall_back_to_the_command() {
+        let rules = rules(vec![
+            (
+                "bet",
+                ChannelRule {
+                    anywhere: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                "bet create",
+                ChannelRule {
+                    allow: vec![CASINO.get()],
+                    ..Default::default()
+                },
+            ),
+        ]);
+
+        assert_eq!(rules.verdict("bet list", OTHER), Verdict::Allowed);
+        assert_eq!(
+            rules.verdict("bet create", OTHER),
+            Verdict::OnlyIn(vec![CASINO])
+        );
+    }
+}
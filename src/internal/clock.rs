@@ -0,0 +1,70 @@
+use chrono::{DateTime, Duration, Utc};
+use chrono_tz::Tz;
+use std::fmt::Debug;
+use std::sync::Mutex;
+
+/// Source of the current time, so the time dependent logic can be tested.
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> DateTime<Tz>;
+
+    fn timestamp(&self) -> i64 {
+        self.now().timestamp()
+    }
+
+    fn timestamp_millis(&self) -> i64 {
+        self.now().timestamp_millis()
+    }
+
+    /// Local date as days since the unix epoch, the house ledger and the
+    /// daily losses are kept per local day.
+    fn day(&self) -> i64 {
+        (self.now().date_naive() - DateTime::UNIX_EPOCH.date_naive()).num_days()
+    }
+
+    /// Time zone the weekly schedules are kept in.
+    fn timezone(&self) -> Tz {
+        self.now().timezone()
+    }
+}
+
+#[derive(Debug)]
+pub struct SystemClock {
+    timezone: Tz,
+}
+
+impl SystemClock {
+    pub fn new(timezone: Tz) -> Self {
+        SystemClock { timezone }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Tz> {
+        Utc::now().with_timezone(&self.timezone)
+    }
+}
+
+/// Clock standing still until moved by hand, for tests and simulations.
+#[derive(Debug)]
+pub struct FakeClock {
+    now: Mutex<DateTime<Tz>>,
+}
+
+impl FakeClock {
+    pub fn new(now: DateTime<Tz>) -> Self {
+        FakeClock {
+            now: Mutex::new(now),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now += duration;
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> DateTime<Tz> {
+        *self.now.lock().unwrap()
+    }
+}
@@ -0,0 +1,278 @@
+use poise::serenity_prelude as serenity;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::internal::data::{Context, Error};
+use crate::internal::discord;
+use crate::internal::settings::Cooldown;
+use crate::internal::shared;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+enum Scope {
+    User(serenity::UserId),
+    Guild(serenity::GuildId),
+}
+
+#[derive(Debug)]
+pub struct Cooldowns {
+    settings: HashMap<String, Cooldown>,
+    expires_at: Mutex<HashMap<(String, Scope), Instant>>,
+}
+
+impl Cooldowns {
+    pub fn new(settings: HashMap<String, Cooldown>) -> Self {
+        Cooldowns {
+            settings,
+            expires_at: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Cooldown of the command, subcommands fall back to the cooldown of the
+    /// top level command.
+    fn find(&self, qualified_name: &str) -> Option<(&String, &Cooldown)> {
+        self.settings.get_key_value(qualified_name).or_else(|| {
+            qualified_name
+                .split_whitespace()
+                .next()
+                .and_then(|name| self.settings.get_key_value(name))
+        })
+    }
+
+    /// Scopes of the cooldown the invocation falls under.
+    fn scopes(
+        cooldown: &Cooldown,
+        user_id: serenity::UserId,
+        guild_id: Option<serenity::GuildId>,
+    ) -> Vec<(Scope, Duration)> {
+        [
+            cooldown
+                .user
+                .map(|duration| (Scope::User(user_id), duration)),
+            guild_id.and_then(|guild_id| {
+                cooldown
+                    .guild
+                    .map(|duration| (Scope::Guild(guild_id), duration))
+            }),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    /// Returns the time left when the command is still cooling down, starts
+    /// the cooldown otherwise.
+    fn trigger(
+        &self,
+        qualified_name: &str,
+        user_id: serenity::UserId,
+        guild_id: Option<serenity::GuildId>,
+        now: Instant,
+    ) -> Option<Duration> {
+        let (name, cooldown) = self.find(qualified_name)?;
+        let scopes = Self::scopes(cooldown, user_id, guild_id);
+
+        let mut expires_at = self.expires_at.lock().unwrap();
+        expires_at.retain(|_, expires| *expires > now);
+
+        let remaining = scopes
+            .iter()
+            .filter_map(|(scope, _)| {
+                let key = (name.clone(), *scope);
+                expires_at.get(&key).map(|expires| *expires - now)
+            })
+            .max();
+
+        if remaining.is_some() {
+            return remaining;
+        }
+
+        for (scope, duration) in scopes {
+            expires_at.insert((name.clone(), scope), now + duration);
+        }
+
+        None
+    }
+
+    /// Ends the cooldown started by `trigger` at `triggered_at`, cooldowns
+    /// started since by other invocations are kept.
+    fn release(
+        &self,
+        qualified_name: &str,
+        user_id: serenity::UserId,
+        guild_id: Option<serenity::GuildId>,
+        triggered_at: Instant,
+    ) {
+        let Some((name, cooldown)) = self.find(qualified_name) else {
+            return;
+        };
+
+        let mut expires_at = self.expires_at.lock().unwrap();
+
+        for (scope, duration) in Self::scopes(cooldown, user_id, guild_id) {
+            let key = (name.clone(), scope);
+            if expires_at.get(&key) == Some(&(triggered_at + duration)) {
+                expires_at.remove(&key);
+            }
+        }
+    }
+}
+
+/// Cooldown the invocation started in `check`.
+#[derive(Debug)]
+struct Claimed(Instant);
+
+/// Marks the invocation as refused, for example because of invalid
+/// arguments or an error, and ends the cooldown it started.
+pub async fn refuse(ctx: &Context<'_>) {
+    let Some(triggered_at) = ctx
+        .invocation_data::<Claimed>()
+        .await
+        .map(|claimed| claimed.0)
+    else {
+        return;
+    };
+
+    ctx.data().cooldowns.release(
+        &ctx.command().qualified_name,
+        ctx.author().id,
+        ctx.guild_id(),
+        triggered_at,
+    );
+}
+
+/// Command check rejecting commands used again before their cooldown is
+/// over. Administrators are never held back. The cooldown starts right away
+/// so that invocations running at the same time can't both pass, `refuse`
+/// ends it when the command doesn't go through.
+pub async fn check(ctx: Context<'_>) -> Result<bool, Error> {
+    let cooldowns = &ctx.data().cooldowns;
+    let qualified_name = &ctx.command().qualified_name;
+
+    if cooldowns.find(qualified_name).is_none() || shared::is_admin(&ctx).await {
+        return Ok(true);
+    }
+
+    let now = Instant::now();
+    let Some(remaining) = cooldowns.trigger(qualified_name, ctx.author().id, ctx.guild_id(), now)
+    else {
+        ctx.set_invocation_data(Claimed(now)).await;
+        return Ok(true);
+    };
+
+    discord::failure_message(
+        &ctx,
+        format!(
+            "{} Liiga kiire! Proovi uuesti {} sekundi pärast.",
+            serenity::Mention::from(ctx.author().id),
+            remaining.as_secs_f64().ceil() as u64
+        ),
+    )
+    .await;
+
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER: serenity::UserId = serenity::UserId::new(1);
+    const OTHER_USER: serenity::UserId = serenity::UserId::new(2);
+    const GUILD: Option<serenity::GuildId> = Some(serenity::GuildId::new(10));
+
+    fn cooldowns(user: Option<u64>, guild: Option<u64>) -> Cooldowns {
+        Cooldowns::new(HashMap::from([(
+            "flip".to_string(),
+            Cooldown {
+                user: user.map(Duration::from_secs),
+                guild: guild.map(Duration::from_secs),
+            },
+        )]))
+    }
+
+    #[test]
+    fn user_cooldown_holds_back_only_that_user() {
+        let cooldowns = cooldowns(Some(10), None);
+        let now = Instant::now();
+
+        assert_eq!(cooldowns.trigger("flip", USER, GUILD, now), None);
+        assert_eq!(
+            cooldowns.trigger("flip", USER, GUILD, now + Duration::from_secs(4)),
+            Some(Duration::from_secs(6))
+        );
+        assert_eq!(cooldowns.trigger("flip", OTHER_USER, GUILD, now), None);
+    }
+
+    #[test]
+    fn guild_cooldown_holds_back_everybody_in_the_guild() {
+        let cooldowns = cooldowns(None, Some(30));
+        let now = Instant::now();
+
+        assert_eq!(cooldowns.trigger("flip", USER, GUILD, now), None);
+        assert_eq!(
+            cooldowns.trigger("flip", OTHER_USER, GUILD, now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(cooldowns.trigger("flip", OTHER_USER, None, now), None);
+    }
+
+    #[test]
+    fn cooldown_is_over_after_its_duration() {
+        let cooldowns = cooldowns(Some(10), None);
+        let now = Instant::now();
+
+        assert_eq!(cooldowns.trigger("flip", USER, GUILD, now), None);
+        assert_eq!(
+            cooldowns.trigger("flip", USER, GUILD, now + Duration::from_secs(10)),
+            None
+        );
+    }
+
+    #[test]
+    fn longest_cooldown_is_reported() {
+        let cooldowns = cooldowns(Some(10), Some(30));
+        let now = Instant::now();
+
+        assert_eq!(cooldowns.trigger("flip", USER, GUILD, now), None);
+        assert_eq!(
+            cooldowns.trigger("flip", USER, GUILD, now),
+            Some(Duration::from_secs(30))
+        );
+    }
+
+    #[test]
+    fn subcommands_share_the_cooldown_of_the_command() {
+        let cooldowns = cooldowns(Some(10), None);
+        let now = Instant::now();
+
+        assert_eq!(cooldowns.trigger("flip all", USER, GUILD, now), None);
+        assert!(cooldowns.trigger("flip", USER, GUILD, now).is_some());
+        assert_eq!(cooldowns.trigger("dice", USER, GUILD, now), None);
+    }
+
+    #[test]
+    fn released_cooldown_lets_the_command_run_again() {
+        let cooldowns = cooldowns(Some(10), Some(30));
+        let now = Instant::now();
+
+        assert_eq!(cooldowns.trigger("flip", USER, GUILD, now), None);
+        cooldowns.release("flip", USER, GUILD, now);
+        assert_eq!(cooldowns.trigger("flip", OTHER_USER, GUILD, now), None);
+    }
+
+    #[test]
+    fn release_keeps_the_cooldown_of_a_later_invocation() {
+        let cooldowns = cooldowns(Some(10), None);
+        let now = Instant::now();
+        let later = now + Duration::from_secs(10);
+
+        assert_eq!(cooldowns.trigger("flip", USER, GUILD, now), None);
+        assert_eq!(cooldowns.trigger("flip", USER, GUILD, later), None);
+        cooldowns.release("flip", USER, GUILD, now);
+        assert_eq!(
+            cooldowns.trigger("flip", USER, GUILD, later),
+            Some(Duration::from_secs(10))
+        );
+    }
+}
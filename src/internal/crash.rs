@@ -0,0 +1,379 @@
+use poise::futures_util::StreamExt;
+use poise::serenity_prelude as serenity;
+use rand::Rng;
+use sqlx::{Pool, Sqlite};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::database::crash::{
+    cancel_crash_round, cash_out_crash_bet, find_unfinished_crash_rounds, load_crash_bets, payout,
+    update_crash_round, CrashBet, CrashRound, STATUS_BETTING, STATUS_CANCELLED, STATUS_CRASHED,
+    STATUS_RUNNING,
+};
+use crate::internal::clock::Clock;
+use crate::internal::jackpot::JackpotRoller;
+use crate::internal::metrics;
+use crate::internal::settings::Crash;
+
+const CASHOUT_BUTTON_PREFIX: &str = "crash-cashout-";
+
+/// Draws a crash point (in hundredths) so that the chance of reaching
+/// multiplier `m` is `(1 - house_edge) / m`, making every cash-out strategy
+/// return `1 - house_edge` on average.
+pub fn generate_crash_point(house_edge: f64) -> i64 {
+    let r: f64 = rand::rng().random();
+    let point = (1.0 - house_edge) / (1.0 - r);
+    ((point * 100.0).floor() as i64).max(100)
+}
+
+/// Highest multiplier (in hundredths) at which the net win of the bet, with
+/// the boost of the round, stays within `max_win`.
+pub fn max_cashout(amount: i64, max_win: i64, boost: f64) -> i64 {
+    ((amount + max_win) as f64 * 100.0 / (amount as f64 * boost)).floor() as i64
+}
+
+/// Multiplier (in hundredths) reached `elapsed_ms` after the round started.
+pub fn multiplier_at(elapsed_ms: i64, growth_rate: f64) -> i64 {
+    let secs = elapsed_ms.max(0) as f64 / 1000.0;
+    (100.0 * (growth_rate * secs).exp()).floor() as i64
+}
+
+pub fn format_multiplier(multiplier: i64) -> String {
+    format!("{}.{:02}x", multiplier / 100, multiplier % 100)
+}
+
+pub fn betting_embed(
+    round: &CrashRound,
+    bets: &[CrashBet],
+    settings: &Crash,
+) -> serenity::CreateEmbed {
+    let boost = if round.boost > 1.0 {
+        format!(" Sündmus käib, võidud x{}!", round.boost)
+    } else {
+        String::new()
+    };
+
+    serenity::CreateEmbed::new()
+        .title(":rocket: Crash")
+        .description(format!(
+            "Panuseid saab teha {} sekundit käsuga `!crash <panus>`.{}",
+            settings.betting_time.as_secs(),
+            boost
+        ))
+        .field("Panused", describe_bets(round, bets, false), false)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "Mäng #{}",
+            round.id
+        )))
+        .color(serenity::Color::GOLD)
+}
+
+fn running_embed(round: &CrashRound, bets: &[CrashBet], multiplier: i64) -> serenity::CreateEmbed {
+    serenity::CreateEmbed::new()
+        .title(format!(
+            ":rocket: Crash - {}",
+            format_multiplier(multiplier)
+        ))
+        .field("Panused", describe_bets(round, bets, false), false)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "Mäng #{}",
+            round.id
+        )))
+        .color(serenity::Color::DARK_GREEN)
+}
+
+fn crashed_embed(round: &CrashRound, bets: &[CrashBet]) -> serenity::CreateEmbed {
+    serenity::CreateEmbed::new()
+        .title(format!(
+            ":boom: Crash - kukkus {} peal",
+            format_multiplier(round.crash_point)
+        ))
+        .field("Panused", describe_bets(round, bets, true), false)
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "Mäng #{}",
+            round.id
+        )))
+        .color(serenity::Color::RED)
+}
+
+fn describe_bets(round: &CrashRound, bets: &[CrashBet], crashed: bool) -> String {
+    if bets.is_empty() {
+        return "Panuseid pole.".into();
+    }
+
+    bets.iter()
+        .map(|bet| {
+            let mention = bet
+                .discord_user_id
+                .parse::<u64>()
+                .map(|id| serenity::Mention::from(serenity::UserId::new(id)).to_string())
+                .unwrap_or_else(|_| bet.discord_user_id.clone());
+            match (bet.cashout, crashed) {
+                (Some(cashout), _) => format!(
+                    "{} {} :potato: - võttis välja {} ({} :potato:)",
+                    mention,
+                    bet.amount,
+                    format_multiplier(cashout),
+                    payout(bet.amount, cashout, round.boost)
+                ),
+                (None, true) => format!("{} {} :potato: - kaotas", mention, bet.amount),
+                (None, false) => format!("{} {} :potato:", mention, bet.amount),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn cashout_button(round: &CrashRound, disabled: bool) -> Vec<serenity::CreateActionRow> {
+    vec![serenity::CreateActionRow::Buttons(vec![
+        serenity::CreateButton::new(format!("{}{}", CASHOUT_BUTTON_PREFIX, round.id))
+            .label("Võta välja")
+            .style(serenity::ButtonStyle::Success)
+            .disabled(disabled),
+    ])]
+}
+
+/// Plays out the round: waits for the betting time, then keeps raising the
+/// multiplier and accepting cash-outs until the crash point is reached.
+pub async fn run_round(
+    ctx: serenity::Context,
+    database: Pool<Sqlite>,
+    settings: Crash,
+    clock: Arc<dyn Clock>,
+    jackpot: JackpotRoller,
+    mut round: CrashRound,
+    mut message: serenity::Message,
+) {
+    tokio::time::sleep(settings.betting_time).await;
+
+    let bets = load_crash_bets(round.id, &database).await;
+
+    if bets.is_empty() {
+        round.status = STATUS_CANCELLED.into();
+        if !update_crash_round(&round, &database).await {
+            warn!("Could not cancel crash round {}", round.id);
+        }
+        let embed = serenity::CreateEmbed::new()
+            .title(":rocket: Crash")
+            .description("Keegi ei julgenud panust teha.")
+            .color(serenity::Color::RED);
+        if let Err(why) = message
+            .edit(&ctx, serenity::EditMessage::new().embed(embed))
+            .await
+        {
+            error!("Error editing message: {why:?}");
+        }
+        return;
+    }
+
+    round.status = STATUS_RUNNING.into();
+    round.started_ms = Some(clock.timestamp_millis());
+    if !update_crash_round(&round, &database).await {
+        error!("Could not start crash round {}", round.id);
+        return;
+    }
+
+    info!("Crash round {} started", round.id);
+
+    let edit = serenity::EditMessage::new()
+        .embed(running_embed(&round, &bets, 100))
+        .components(cashout_button(&round, false));
+    if let Err(why) = message.edit(&ctx, edit).await {
+        error!("Error editing message: {why:?}");
+    }
+
+    let mut interactions = serenity::ComponentInteractionCollector::new(&ctx)
+        .message_id(message.id)
+        .stream();
+    let mut interval_timer = tokio::time::interval(settings.tick_interval);
+
+    loop {
+        tokio::select! {
+            _ = interval_timer.tick() => {
+                let multiplier = current_multiplier(&round, &settings, clock.as_ref());
+                let capped = multiplier.min(round.crash_point - 1);
+                cash_out_capped(&database, &round, capped, clock.day()).await;
+                if multiplier >= round.crash_point {
+                    break;
+                }
+                let bets = load_crash_bets(round.id, &database).await;
+                let edit = serenity::EditMessage::new().embed(running_embed(&round, &bets, multiplier));
+                if let Err(why) = message.edit(&ctx, edit).await {
+                    error!("Error editing message: {why:?}");
+                }
+            }
+            Some(interaction) = interactions.next() => {
+                handle_cashout(&ctx, &database, &settings, clock.as_ref(), &round, interaction).await;
+            }
+        }
+    }
+
+    round.status = STATUS_CRASHED.into();
+    if !update_crash_round(&round, &database).await {
+        error!("Could not finish crash round {}", round.id);
+    }
+
+    info!(
+        "Crash round {} crashed at {}",
+        round.id,
+        format_multiplier(round.crash_point)
+    );
+
+    let bets = load_crash_bets(round.id, &database).await;
+    let edit = serenity::EditMessage::new()
+        .embed(crashed_embed(&round, &bets))
+        .components(cashout_button(&round, true));
+    if let Err(why) = message.edit(&ctx, edit).await {
+        error!("Error editing message: {why:?}");
+    }
+
+    for bet in bets.iter() {
+        let lost = if bet.cashout.is_none() { bet.amount } else { 0 };
+        jackpot
+            .settle_in(
+                &ctx.http,
+                &round.channel_id,
+                &bet.discord_user_id,
+                lost,
+                "crash",
+            )
+            .await;
+    }
+}
+
+fn current_multiplier(round: &CrashRound, settings: &Crash, clock: &dyn Clock) -> i64 {
+    let started_ms = round.started_ms.unwrap_or_default();
+    multiplier_at(clock.timestamp_millis() - started_ms, settings.growth_rate)
+}
+
+/// Cashes out the bets that reached the most the house could cover for them,
+/// at that multiplier.
+async fn cash_out_capped(database: &Pool<Sqlite>, round: &CrashRound, multiplier: i64, day: i64) {
+    for bet in load_crash_bets(round.id, database).await {
+        let Some(max_cashout) = bet.max_cashout.filter(|max| *max <= multiplier) else {
+            continue;
+        };
+        if bet.cashout.is_some() {
+            continue;
+        }
+
+        match cash_out_crash_bet(round, &bet.discord_user_id, max_cashout, day, database).await {
+            Some(bet) => metrics::payout("crash", payout(bet.amount, max_cashout, round.boost)),
+            None => warn!(
+                "Could not cash out capped bet of user {} in crash round {}",
+                bet.discord_user_id, round.id
+            ),
+        }
+    }
+}
+
+async fn handle_cashout(
+    ctx: &serenity::Context,
+    database: &Pool<Sqlite>,
+    settings: &Crash,
+    clock: &dyn Clock,
+    round: &CrashRound,
+    interaction: serenity::ComponentInteraction,
+) {
+    if interaction.data.custom_id != format!("{}{}", CASHOUT_BUTTON_PREFIX, round.id) {
+        return;
+    }
+
+    let multiplier = current_multiplier(round, settings, clock);
+    let user_id = interaction.user.id.to_string();
+
+    let content = if multiplier >= round.crash_point {
+        "Liiga hilja, rakett kukkus juba alla!".to_string()
+    } else {
+        match cash_out_crash_bet(round, &user_id, multiplier, clock.day(), database).await {
+            Some(bet) => {
+                let cashout = bet.cashout.unwrap_or(multiplier);
+                let amount = payout(bet.amount, cashout, round.boost);
+                metrics::payout("crash", amount);
+                format!(
+                    "Võtsid välja {} peal ja said {} :potato:.",
+                    format_multiplier(cashout),
+                    amount
+                )
+            }
+            None => "Sul pole selles mängus panust, mida välja võtta.".to_string(),
+        }
+    };
+
+    let response = serenity::CreateInteractionResponse::Message(
+        serenity::CreateInteractionResponseMessage::new()
+            .content(content)
+            .ephemeral(true),
+    );
+
+    if let Err(why) = interaction.create_response(ctx, response).await {
+        error!("Error responding to interaction: {why:?}");
+    }
+}
+
+/// Settles rounds interrupted by a restart: rounds still taking bets are
+/// refunded in full, running rounds keep paid cash-outs and refund the rest.
+pub async fn recover_rounds(ctx: &serenity::Context, database: &Pool<Sqlite>, clock: &dyn Clock) {
+    for mut round in find_unfinished_crash_rounds(database).await {
+        warn!(
+            "Recovering crash round {} left in status {}",
+            round.id, round.status
+        );
+
+        let refunds: i64 = load_crash_bets(round.id, database)
+            .await
+            .iter()
+            .filter(|bet| bet.cashout.is_none())
+            .map(|bet| bet.amount)
+            .sum();
+
+        let was_betting = round.status == STATUS_BETTING;
+
+        round.status = STATUS_CANCELLED.into();
+        if !cancel_crash_round(&round, clock.day(), database).await {
+            error!("Could not cancel crash round {}", round.id);
+            continue;
+        }
+
+        metrics::payout("crash", refunds);
+
+        let (Ok(channel_id), Some(Ok(message_id))) = (
+            round.channel_id.parse::<u64>(),
+            round.message_id.as_ref().map(|id| id.parse::<u64>()),
+        ) else {
+            continue;
+        };
+
+        let description = if was_betting {
+            "Mäng katkes enne algust, kõik panused tagastati."
+        } else {
+            "Mäng katkes, välja võtmata panused tagastati."
+        };
+
+        let embed = serenity::CreateEmbed::new()
+            .title(":rocket: Crash")
+            .description(description)
+            .footer(serenity::CreateEmbedFooter::new(format!(
+                "Mäng #{}",
+                round.id
+            )))
+            .color(serenity::Color::RED);
+
+        let components = if was_betting {
+            vec![]
+        } else {
+            cashout_button(&round, true)
+        };
+
+        let edit = serenity::EditMessage::new()
+            .embed(embed)
+            .components(components);
+
+        if let Err(why) = serenity::ChannelId::new(channel_id)
+            .edit_message(ctx, serenity::MessageId::new(message_id), edit)
+            .await
+        {
+            error!("Error editing message: {why:?}");
+        }
+    }
+}
@@ -1,13 +1,46 @@
 use serenity::all::ChannelId;
+use std::sync::Arc;
 
+use crate::database::repository::PlayerRepository;
+
+use crate::internal::backups::BackupKeeper;
+use crate::internal::channels::ChannelRules;
+use crate::internal::clock::Clock;
+use crate::internal::cooldowns::Cooldowns;
+use crate::internal::events::EventKeeper;
 use crate::internal::feeder::Feeder;
+use crate::internal::jackpot::JackpotRoller;
+use crate::internal::mines::MinesKeeper;
+use crate::internal::role_keeper::RoleKeeper;
+use crate::internal::settings::{
+    Backups, Crash, Dice, Give, House, Jackpot, Limits, Markets, Mines, Poker, Race, Settings,
+};
 
 #[derive(Debug)]
 pub struct Data {
     pub database: sqlx::SqlitePool,
+    pub players: Arc<dyn PlayerRepository>,
+    pub clock: Arc<dyn Clock>,
     pub potato_channel_id: ChannelId,
     pub zero_points_emoji: String,
     pub feeder: Feeder,
+    pub role_keeper: RoleKeeper,
+    pub mines_keeper: MinesKeeper,
+    pub event_keeper: EventKeeper,
+    pub backup_keeper: BackupKeeper,
+    pub crash: Crash,
+    pub dice: Dice,
+    pub poker: Poker,
+    pub race: Race,
+    pub mines: Mines,
+    pub markets: Markets,
+    pub jackpot: Jackpot,
+    pub house: House,
+    pub give: Give,
+    pub limits: Limits,
+    pub backups: Backups,
+    pub cooldowns: Cooldowns,
+    pub channels: ChannelRules,
 }
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
@@ -16,15 +49,47 @@ pub type Context<'a> = poise::Context<'a, Data, Error>;
 impl Data {
     pub fn new(
         database: sqlx::SqlitePool,
-        potato_channel_id: ChannelId,
-        potato_amount: i64,
-        zero_points_emoji: String,
+        players: Arc<dyn PlayerRepository>,
+        clock: Arc<dyn Clock>,
+        settings: &Settings,
     ) -> Self {
+        let potato_channel_id = ChannelId::new(settings.potato_feeder.channel_id);
+
         Self {
             database: database.clone(),
+            players: players.clone(),
+            clock: clock.clone(),
             potato_channel_id,
-            zero_points_emoji,
-            feeder: Feeder::new(potato_channel_id, potato_amount, database),
+            zero_points_emoji: settings.potato_feeder.zero_points_emoji.clone(),
+            feeder: Feeder::new(
+                potato_channel_id,
+                settings.potato_feeder.amount,
+                database.clone(),
+                players,
+                clock.clone(),
+            ),
+            role_keeper: RoleKeeper::new(database.clone(), clock.clone()),
+            mines_keeper: MinesKeeper::new(
+                database.clone(),
+                settings.mines.clone(),
+                JackpotRoller::new(database.clone(), settings.jackpot.clone(), clock.clone()),
+                clock.clone(),
+            ),
+            event_keeper: EventKeeper::new(potato_channel_id, database.clone(), clock.clone()),
+            backup_keeper: BackupKeeper::new(database, settings.backups.clone(), clock),
+            crash: settings.crash.clone(),
+            dice: settings.dice.clone(),
+            poker: settings.poker.clone(),
+            race: settings.race.clone(),
+            mines: settings.mines.clone(),
+            markets: settings.markets.clone(),
+            jackpot: settings.jackpot.clone(),
+            house: settings.house.clone(),
+            give: settings.give.clone(),
+            limits: settings.limits.clone(),
+            backups: settings.backups.clone(),
+            cooldowns: Cooldowns::new(settings.cooldowns.clone()),
+            channels: ChannelRules::new(potato_channel_id, settings.channels.clone()),
         }
     }
 }
@@ -1,7 +1,11 @@
 use poise::serenity_prelude as serenity;
 use tracing::{error, info, instrument};
 
+use super::cooldowns;
 use super::data::{Context, Data, Error};
+use super::health;
+use super::http;
+use super::metrics;
 use super::settings::Settings;
 
 #[instrument(skip(_framework))]
@@ -11,15 +15,50 @@ async fn event_handler(
     _framework: poise::FrameworkContext<'_, Data, Error>,
     data: &Data,
 ) -> Result<(), Error> {
-    if let serenity::FullEvent::Ready { data_about_bot, .. } = event {
-        info!("Logged in as {}", data_about_bot.user.name);
-        data.feeder.start(ctx.clone());
+    match event {
+        serenity::FullEvent::Ready { data_about_bot, .. } => {
+            info!("Logged in as {}", data_about_bot.user.name);
+            data.feeder.start(ctx.clone());
+            data.role_keeper.start(ctx.clone());
+            data.mines_keeper.start(ctx.clone());
+            data.event_keeper.start(ctx.clone());
+            data.backup_keeper.start();
+        }
+        serenity::FullEvent::InteractionCreate {
+            interaction: serenity::Interaction::Component(interaction),
+        } => {
+            crate::internal::poker::handle_interaction(ctx, data, interaction).await;
+            crate::internal::mines::handle_interaction(ctx, data, interaction).await;
+        }
+        _ => {}
     }
     Ok(())
 }
 
+/// Outcome label of a command that failed to run to the end.
+fn error_outcome(error: &poise::FrameworkError<'_, Data, Error>) -> &'static str {
+    match error {
+        poise::FrameworkError::ArgumentParse { .. } => "invalid_arguments",
+        poise::FrameworkError::CooldownHit { .. } => "cooldown",
+        poise::FrameworkError::CommandCheckFailed { .. }
+        | poise::FrameworkError::MissingBotPermissions { .. }
+        | poise::FrameworkError::MissingUserPermissions { .. }
+        | poise::FrameworkError::NotAnOwner { .. }
+        | poise::FrameworkError::GuildOnly { .. }
+        | poise::FrameworkError::DmOnly { .. }
+        | poise::FrameworkError::NsfwOnly { .. }
+        | poise::FrameworkError::SubcommandRequired { .. } => "refused",
+        _ => "error",
+    }
+}
+
 #[instrument]
 async fn on_error(error: poise::FrameworkError<'_, Data, Error>) {
+    if let Some(ctx) = error.ctx() {
+        crate::internal::cooldowns::refuse(&ctx).await;
+        metrics::command(&ctx.command().qualified_name, error_outcome(&error));
+    }
+
     match error {
         poise::FrameworkError::Command { error, ctx, .. } => {
             println!("Error in command `{}`: {:?}", ctx.command().name, error,);
@@ -35,24 +74,52 @@ async fn on_error(error: poise::FrameworkError<'_, Data, Error>) {
 #[instrument]
 pub async fn start_client(data: Data, settings: &Settings) {
     let channel_id = data.potato_channel_id;
+    let database = data.database.clone();
+    let last_run = data.feeder.last_run.clone();
+    let clock = data.clock.clone();
 
     let framework = poise::Framework::builder()
         .options(poise::FrameworkOptions {
             command_check: Some(|ctx: Context| {
-                Box::pin(async move { Ok(ctx.channel_id() == ctx.data().potato_channel_id) })
+                Box::pin(async move {
+                    if !crate::internal::channels::check(ctx).await? {
+                        return Ok(false);
+                    }
+                    crate::internal::cooldowns::check(ctx).await
+                })
             }),
             commands: vec![
+                crate::commands::achievements::achievements(),
+                crate::commands::backup::backup(),
                 crate::commands::balance::balance(),
+                crate::commands::bet::bet(),
+                crate::commands::crash::crash(),
+                crate::commands::craps::craps(),
+                crate::commands::dice::dice(),
+                crate::commands::event::event(),
+                crate::commands::export::export(),
+                crate::commands::feed::feed(),
                 crate::commands::flip::flip(),
                 crate::commands::give::give(),
                 crate::commands::help::help(),
+                crate::commands::house::house(),
+                crate::commands::jackpot::jackpot(),
                 crate::commands::leaderboard::leaderboard(),
+                crate::commands::limits::limits(),
+                crate::commands::mines::mines(),
                 crate::commands::ping::ping(),
+                crate::commands::poker::poker(),
+                crate::commands::race::race(),
+                crate::commands::roles::role(),
+                crate::commands::limits::selfexclude(),
             ],
             event_handler: |ctx, event, framework, data| {
                 Box::pin(event_handler(ctx, event, framework, data))
             },
             on_error: |error| Box::pin(on_error(error)),
+            post_command: |ctx| {
+                Box::pin(async move { metrics::command(&ctx.command().qualified_name, "ok") })
+            },
             prefix_options: poise::PrefixFrameworkOptions {
                 prefix: Some("!".into()),
                 mention_as_prefix: false,
@@ -63,6 +130,10 @@ pub async fn start_client(data: Data, settings: &Settings) {
         .setup(|ctx, _ready, framework| {
             Box::pin(async move {
                 poise::builtins::register_globally(ctx, &framework.options().commands).await?;
+                crate::internal::crash::recover_rounds(ctx, &data.database, data.clock.as_ref())
+                    .await;
+                crate::internal::race::recover_races(ctx, &data.database, data.clock.as_ref())
+                    .await;
                 Ok(data)
             })
         })
@@ -81,6 +152,27 @@ pub async fn start_client(data: Data, settings: &Settings) {
         .await
         .expect("Error creating client");
 
+    let mut routers = vec![];
+    if settings.metrics.enabled {
+        routers.push((
+            settings.metrics.listen.clone(),
+            metrics::router(database.clone(), client.shard_manager.clone()),
+        ));
+    }
+    if settings.health.enabled {
+        routers.push((
+            settings.health.listen.clone(),
+            health::router(
+                database,
+                client.shard_manager.clone(),
+                last_run,
+                clock,
+                &settings.health,
+            ),
+        ));
+    }
+    http::serve_all(routers);
+
     let shard_manager = client.shard_manager.clone();
     let http = client.http.clone();
 
@@ -116,7 +208,15 @@ pub async fn success_message(ctx: &Context<'_>, message: impl Into<String>) {
     }
 }
 
+/// Tells the player the command was refused, refused commands don't start
+/// their cooldown.
 pub async fn failure_message(ctx: &Context<'_>, message: impl Into<String>) {
+    cooldowns::refuse(ctx).await;
+    loss_message(ctx, message).await;
+}
+
+/// Tells the player they lost, the command itself went through.
+pub async fn loss_message(ctx: &Context<'_>, message: impl Into<String>) {
     let embed = serenity::CreateEmbed::new()
         .description(message)
         .color(serenity::Color::RED);
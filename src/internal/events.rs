@@ -0,0 +1,240 @@
+use chrono::{Days, TimeZone};
+use poise::serenity_prelude as serenity;
+use sqlx::{Pool, Sqlite};
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+use tracing::{error, info, instrument, warn};
+
+use crate::database::events::{
+    find_unannounced_events, load_active_events, update_event, Event, TARGET_CRAPS, TARGET_CRASH,
+    TARGET_DICE, TARGET_FEEDER, TARGET_FLIP, TARGET_MINES, TARGET_RACE,
+};
+use crate::internal::clock::Clock;
+
+/// Multiplier of the payouts of the target right now, overlapping events
+/// stack.
+pub async fn multiplier(target: &str, ts: i64, database: &Pool<Sqlite>) -> f64 {
+    load_active_events(target, ts, database)
+        .await
+        .iter()
+        .map(|event| event.multiplier)
+        .product()
+}
+
+/// Applies the multiplier to an amount, never going below the original.
+pub fn multiply(amount: i64, multiplier: f64) -> i64 {
+    ((amount as f64 * multiplier).floor() as i64).max(amount)
+}
+
+pub fn describe_target(target: &str) -> &str {
+    match target {
+        TARGET_FLIP => "Kulli ja kirja võidud",
+        TARGET_DICE => "Täringu võidud",
+        TARGET_CRAPS => "Crapsi võidud",
+        TARGET_CRASH => "Crashi võidud",
+        TARGET_MINES => "Miinide võidud",
+        TARGET_RACE => "Võidusõidu võidud",
+        TARGET_FEEDER => "Kartulijagamine",
+        target => target,
+    }
+}
+
+/// Times of the first weekly occurrence that has not ended by `now`, each
+/// occurrence starts at the same local time a week after the previous one.
+pub fn next_occurrence<Tz: TimeZone>(
+    tz: &Tz,
+    starts_ts: i64,
+    ends_ts: i64,
+    now: i64,
+) -> (i64, i64) {
+    let duration = ends_ts - starts_ts;
+    let mut starts_ts = starts_ts;
+
+    while starts_ts + duration <= now {
+        starts_ts = tz
+            .timestamp_opt(starts_ts, 0)
+            .single()
+            .and_then(|starts_at| starts_at.naive_local().checked_add_days(Days::new(7)))
+            .and_then(|starts_at| tz.from_local_datetime(&starts_at).earliest())
+            .map_or(starts_ts + 7 * 86_400, |starts_at| starts_at.timestamp());
+    }
+
+    (starts_ts, starts_ts + duration)
+}
+
+pub fn format_time<Tz: TimeZone>(ts: i64, tz: &Tz) -> String
+where
+    Tz::Offset: Display,
+{
+    tz.timestamp_opt(ts, 0)
+        .single()
+        .map(|date| date.format("%d.%m.%Y %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+#[derive(Debug)]
+pub struct EventKeeper {
+    channel_id: serenity::ChannelId,
+    database: Pool<Sqlite>,
+    clock: Arc<dyn Clock>,
+    is_running: Mutex<bool>,
+}
+
+impl EventKeeper {
+    pub fn new(
+        channel_id: serenity::ChannelId,
+        database: Pool<Sqlite>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        EventKeeper {
+            channel_id,
+            database,
+            clock,
+            is_running: Mutex::new(false),
+        }
+    }
+
+    #[instrument]
+    pub fn start(&self, ctx: serenity::Context) {
+        let mut is_running = self.is_running.lock().unwrap();
+        if *is_running {
+            return;
+        }
+
+        *is_running = true;
+
+        let channel_id = self.channel_id;
+        let database = self.database.clone();
+        let clock = self.clock.clone();
+
+        tokio::spawn(async move {
+            let mut interval_timer = tokio::time::interval(duration_str::parse("1m").unwrap());
+
+            loop {
+                interval_timer.tick().await;
+
+                announce_events(&ctx, &database, channel_id, clock.as_ref()).await;
+            }
+        });
+    }
+}
+
+async fn announce_events(
+    ctx: &serenity::Context,
+    database: &Pool<Sqlite>,
+    channel_id: serenity::ChannelId,
+    clock: &dyn Clock,
+) {
+    let now = clock.timestamp();
+    let tz = clock.timezone();
+
+    for mut event in find_unannounced_events(now, database).await {
+        let content = if event.ends_ts <= now {
+            // An event missed completely while the bot was away is not worth
+            // announcing at all.
+            let content = event.start_announced.then(|| end_announcement(&event));
+            event.start_announced = true;
+            event.end_announced = true;
+            content
+        } else {
+            event.start_announced = true;
+            Some(start_announcement(&event, &tz))
+        };
+
+        if event.weekly && event.end_announced {
+            (event.starts_ts, event.ends_ts) =
+                next_occurrence(&tz, event.starts_ts, event.ends_ts, now);
+            event.start_announced = false;
+            event.end_announced = false;
+        }
+
+        if !update_event(&event, database).await {
+            warn!("Could not update event {}", event.id);
+            continue;
+        }
+
+        let Some(content) = content else {
+            continue;
+        };
+
+        info!("Announcing event {} ...", event.id);
+
+        let message = serenity::CreateMessage::new().content(content);
+        if let Err(why) = channel_id.send_message(&ctx.http, message).await {
+            error!("Error sending message: {why:?}");
+        }
+    }
+}
+
+fn start_announcement(event: &Event, tz: &chrono_tz::Tz) -> String {
+    format!(
+        "@everyone :tada: Algas **{}**! {}: x{} kuni {}.",
+        event.name,
+        describe_target(&event.target),
+        event.multiplier,
+        format_time(event.ends_ts, tz)
+    )
+}
+
+fn end_announcement(event: &Event) -> String {
+    format!(":checkered_flag: **{}** on läbi.", event.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, Utc};
+    use chrono_tz::Europe::Tallinn;
+
+    use super::*;
+
+    const WEEK: i64 = 7 * 86_400;
+
+    #[test]
+    fn running_occurrence_stays() {
+        assert_eq!(next_occurrence(&Utc, 1_000, 8_200, 5_000), (1_000, 8_200));
+    }
+
+    #[test]
+    fn ended_occurrence_moves_a_week() {
+        assert_eq!(
+            next_occurrence(&Utc, 1_000, 8_200, 8_200),
+            (1_000 + WEEK, 8_200 + WEEK)
+        );
+    }
+
+    #[test]
+    fn missed_occurrences_are_skipped() {
+        let offset = FixedOffset::east_opt(3 * 3_600).unwrap();
+
+        assert_eq!(
+            next_occurrence(&offset, 1_000, 8_200, 1_000 + 3 * WEEK),
+            (1_000 + 3 * WEEK, 8_200 + 3 * WEEK)
+        );
+    }
+
+    #[test]
+    fn occurrences_keep_the_local_time_when_the_clocks_go_back() {
+        let starts_at = Tallinn.with_ymd_and_hms(2026, 10, 23, 18, 0, 0).unwrap();
+        let next = Tallinn.with_ymd_and_hms(2026, 10, 30, 18, 0, 0).unwrap();
+        let starts_ts = starts_at.timestamp();
+
+        assert_eq!(
+            next_occurrence(&Tallinn, starts_ts, starts_ts + 3_600, starts_ts + 3_600),
+            (next.timestamp(), next.timestamp() + 3_600)
+        );
+        assert_eq!(next.timestamp() - starts_ts, WEEK + 3_600);
+    }
+
+    #[test]
+    fn occurrences_keep_the_local_time_when_the_clocks_spring_forward() {
+        let starts_at = Tallinn.with_ymd_and_hms(2027, 3, 26, 18, 0, 0).unwrap();
+        let next = Tallinn.with_ymd_and_hms(2027, 4, 2, 18, 0, 0).unwrap();
+        let starts_ts = starts_at.timestamp();
+
+        assert_eq!(
+            next_occurrence(&Tallinn, starts_ts, starts_ts + 3_600, starts_ts + 3_600),
+            (next.timestamp(), next.timestamp() + 3_600)
+        );
+        assert_eq!(next.timestamp() - starts_ts, WEEK - 3_600);
+    }
+}
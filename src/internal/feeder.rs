@@ -1,10 +1,17 @@
-use chrono::{Datelike, Days, Local, NaiveTime, TimeZone, Weekday};
+use chrono::DateTime;
+use chrono_tz::Tz;
 use poise::serenity_prelude as serenity;
 use sqlx::{Pool, Sqlite};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use tracing::{error, info, instrument, warn};
 
-use crate::database::players::{find_unfeeded_players, remove_player, update_player, IdleStatus};
+use crate::database::events::TARGET_FEEDER;
+use crate::database::players::{CharityRates, Player};
+use crate::database::repository::PlayerRepository;
+use crate::engine::feeding::{self, Feeding};
+use crate::internal::clock::{Clock, FakeClock};
+use crate::internal::events;
+use crate::internal::metrics;
 
 #[derive(Debug)]
 pub struct Error {}
@@ -17,24 +24,61 @@ impl std::fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
+/// When the feeder last ran without failing, shared with the health checks.
+#[derive(Clone, Debug, Default)]
+pub struct LastRun(Arc<Mutex<Option<i64>>>);
+
+impl LastRun {
+    pub fn get(&self) -> Option<i64> {
+        *self.0.lock().unwrap()
+    }
+
+    fn set(&self, ts: i64) {
+        *self.0.lock().unwrap() = Some(ts);
+    }
+}
+
 #[derive(Debug)]
 pub struct Feeder {
     channel_id: serenity::ChannelId,
     amount: i64,
     database: Pool<Sqlite>,
+    players: Arc<dyn PlayerRepository>,
+    clock: Arc<dyn Clock>,
+    pub last_run: LastRun,
     is_running: Mutex<bool>,
 }
 
 impl Feeder {
-    pub fn new(channel_id: serenity::ChannelId, amount: i64, database: Pool<Sqlite>) -> Self {
+    pub fn new(
+        channel_id: serenity::ChannelId,
+        amount: i64,
+        database: Pool<Sqlite>,
+        players: Arc<dyn PlayerRepository>,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
         Feeder {
             channel_id,
             amount,
             database,
+            players,
+            clock,
+            last_run: LastRun::default(),
             is_running: Mutex::new(false),
         }
     }
 
+    /// What the next feeding would do and when.
+    pub async fn preview(&self) -> (DateTime<Tz>, Feeding) {
+        preview(
+            &self.database,
+            self.players.as_ref(),
+            self.clock.as_ref(),
+            self.amount,
+        )
+        .await
+    }
+
     #[instrument]
     pub fn start(&self, ctx: serenity::Context) {
         let mut is_running = self.is_running.lock().unwrap();
@@ -47,6 +91,9 @@ impl Feeder {
         let channel_id = self.channel_id;
         let amount = self.amount;
         let database = self.database.clone();
+        let players = self.players.clone();
+        let clock = self.clock.clone();
+        let last_run = self.last_run.clone();
 
         tokio::spawn(async move {
             let message = serenity::CreateMessage::new()
@@ -61,7 +108,19 @@ impl Feeder {
             loop {
                 interval_timer.tick().await;
 
-                let _ = do_feeding(&ctx, &database, channel_id, amount).await;
+                let result = do_feeding(
+                    &ctx,
+                    &database,
+                    players.as_ref(),
+                    clock.as_ref(),
+                    channel_id,
+                    amount,
+                )
+                .await;
+
+                if result.is_ok() {
+                    last_run.set(clock.timestamp());
+                }
             }
         });
     }
@@ -70,6 +129,8 @@ impl Feeder {
 async fn do_feeding(
     ctx: &serenity::Context,
     database: &Pool<Sqlite>,
+    players: &dyn PlayerRepository,
+    clock: &dyn Clock,
     channel_id: serenity::ChannelId,
     amount: i64,
 ) -> Result<(), Error> {
@@ -77,95 +138,44 @@ async fn do_feeding(
 
     let _ = channel_id.start_typing(&ctx.http);
 
-    let now = Local::now();
-
-    let mut last_friday = now.date_naive();
-    while last_friday.weekday() != Weekday::Fri {
-        last_friday = last_friday.checked_sub_days(Days::new(1)).unwrap();
-    }
+    let started = std::time::Instant::now();
 
-    let dt = Local
-        .from_local_datetime(&last_friday.and_time(NaiveTime::MIN))
-        .unwrap();
+    let feeding = feed(database, players, clock, amount)
+        .await
+        .ok_or(Error {})?;
 
-    let tx = database.begin().await.map_err(|_| Error {})?;
+    let mention = |player: &Player| {
+        serenity::Mention::from(serenity::UserId::new(
+            player.discord_user_id.parse::<u64>().unwrap(),
+        ))
+    };
 
     let mut messages = Vec::<serenity::CreateMessage>::new();
-    let mut charity_sum = 0i64;
-
-    let all_players = find_unfeeded_players(dt.timestamp(), database).await;
-    let (active_players, idle_players) =
-        all_players
-            .iter()
-            .fold((vec![], vec![]), |(mut a1, mut a2), x| {
-                if let IdleStatus::Active = x.idle_status(now.timestamp()) {
-                    a1.push(x);
-                } else {
-                    a2.push(x);
-                }
-                (a1, a2)
-            });
-
-    for p in idle_players {
-        let mut player = p.clone();
-        if player.balance < 1 {
-            while !remove_player(&mut player, database).await {
-                warn!("Could not remove player {}", player.discord_user_id);
-            }
-            let mention = serenity::Mention::from(serenity::UserId::new(
-                player.discord_user_id.parse::<u64>().unwrap(),
-            ));
-            messages.push(
-                serenity::CreateMessage::new()
-                    .content(format!("{} visati kartulikasiinost välja.", mention)),
-            );
-            continue;
-        }
-        let charity = player.charity_amount(now.timestamp());
-        info!(
-            "Taking {} potatoes from user {} for charity ...",
-            charity, player.discord_user_id
-        );
-        charity_sum += charity;
-        player.balance -= charity;
-        player.last_feed_ts = now.timestamp();
-        while !update_player(&mut player, database).await {
-            warn!("Could not update player {}", player.discord_user_id);
-        }
-        let mention = serenity::Mention::from(serenity::UserId::new(
-            player.discord_user_id.parse::<u64>().unwrap(),
-        ));
+
+    for player in &feeding.removed {
         messages.push(serenity::CreateMessage::new().content(format!(
-            "{} kartulisalvest võeti {} :potato: teistele jagamiseks.",
-            mention, charity
+            "{} visati kartulikasiinost välja.",
+            mention(player)
         )));
     }
 
-    let num_active_players = active_players.len() as i64;
-    let total_amount: i64 = match num_active_players {
-        0 => charity_sum,
-        _ => amount + charity_sum / num_active_players,
-    };
+    for (player, charity) in &feeding.taxed {
+        messages.push(serenity::CreateMessage::new().content(format!(
+            "{} kartulisalvest võeti {} :potato: teistele jagamiseks.",
+            mention(player),
+            charity
+        )));
+    }
 
-    for p in active_players {
-        let mut player = p.clone();
-        info!("Feeding potatoes to user {} ...", player.discord_user_id);
-        player.balance += total_amount;
-        player.last_feed_ts = now.timestamp();
-        while !update_player(&mut player, database).await {
-            warn!("Could not update player {}", player.discord_user_id);
-        }
-        let mention = serenity::Mention::from(serenity::UserId::new(
-            player.discord_user_id.parse::<u64>().unwrap(),
-        ));
+    for player in &feeding.fed {
         messages.push(serenity::CreateMessage::new().content(format!(
             "{} kartulisalve lisati {} :potato:.",
-            mention, total_amount
+            mention(player),
+            feeding.share
         )));
-        info!("User {} has much more potatoes now", player.discord_user_id);
     }
 
-    tx.commit().await.map_err(|_| Error {})?;
+    metrics::feeder_run(started.elapsed());
 
     for message in messages {
         if let Err(why) = channel_id.send_message(&ctx, message).await {
@@ -175,3 +185,120 @@ async fn do_feeding(
 
     Ok(())
 }
+
+/// Multiplier of the amount fed at `ts` from the running events.
+async fn multiplier(database: &Pool<Sqlite>, ts: i64) -> f64 {
+    events::multiplier(TARGET_FEEDER, ts, database).await
+}
+
+/// Feeds the players not fed since the last Friday with the events running
+/// right now.
+pub async fn feed(
+    database: &Pool<Sqlite>,
+    players: &dyn PlayerRepository,
+    clock: &dyn Clock,
+    amount: i64,
+) -> Option<Feeding> {
+    let multiplier = multiplier(database, clock.timestamp()).await;
+
+    feeding::feed(players, clock, amount, multiplier, &CharityRates::default()).await
+}
+
+/// What the next feeding would do and when, nothing is saved. Players due
+/// already are fed right away, otherwise the feeding is on the next Friday.
+pub async fn preview(
+    database: &Pool<Sqlite>,
+    players: &dyn PlayerRepository,
+    clock: &dyn Clock,
+    amount: i64,
+) -> (DateTime<Tz>, Feeding) {
+    let mut at = clock.now();
+    if feeding::find_unfeeded(players, clock).await.is_empty() {
+        at = feeding::next_friday(at);
+    }
+
+    let clock = FakeClock::new(at);
+    let unfeeded = feeding::find_unfeeded(players, &clock).await;
+
+    if unfeeded.is_empty() {
+        return (at, Feeding::default());
+    }
+
+    let charity_pool = players.find_charity_pool().await;
+    let amount = events::multiply(amount, multiplier(database, at.timestamp()).await);
+
+    (
+        at,
+        feeding::plan(
+            &unfeeded,
+            at.timestamp(),
+            amount,
+            charity_pool,
+            &CharityRates::default(),
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use chrono_tz::Europe::Tallinn;
+
+    use super::*;
+    use crate::database::charity::change_charity_pool;
+    use crate::database::events::{create_event, Event};
+    use crate::database::repository::SqlitePlayerRepository;
+
+    #[tokio::test]
+    async fn feeds_with_the_charity_pool_and_the_events() {
+        let database = crate::database::in_memory().await;
+        let players = SqlitePlayerRepository::new(database.clone());
+        let clock = FakeClock::new(Tallinn.with_ymd_and_hms(2026, 10, 23, 12, 0, 0).unwrap());
+        let now = clock.timestamp();
+
+        for user_id in ["1", "2", "3"] {
+            let mut player = players.create_player(user_id, now).await.unwrap();
+            player.last_feed_ts = 0;
+            assert!(players.update_player(&mut player).await);
+        }
+
+        let mut connection = database.acquire().await.unwrap();
+        assert!(change_charity_pool(31, &mut connection).await);
+        drop(connection);
+
+        let mut event = Event {
+            id: 0,
+            name: "Õnnetund".to_string(),
+            target: TARGET_FEEDER.to_string(),
+            multiplier: 2.0,
+            starts_ts: now - 60,
+            ends_ts: now + 60,
+            created_by: "1".to_string(),
+            start_announced: false,
+            end_announced: false,
+            weekly: false,
+        };
+        assert!(create_event(&mut event, &database).await);
+
+        let house = players.find_house_balance().await;
+
+        let (at, preview) = preview(&database, &players, &clock, 100).await;
+        assert_eq!(at, clock.now());
+        assert_eq!(preview.share, 210);
+
+        let feeding = feed(&database, &players, &clock, 100).await.unwrap();
+
+        // 31 shared between three players, the odd one goes to the house.
+        assert_eq!(feeding.fed.len(), 3);
+        assert_eq!(feeding.share, 210);
+        assert_eq!(players.find_player("1").await.unwrap().balance, 5210);
+        assert_eq!(players.find_charity_pool().await, 0);
+        assert_eq!(players.find_house_balance().await, house + 1);
+
+        assert!(feed(&database, &players, &clock, 100)
+            .await
+            .unwrap()
+            .fed
+            .is_empty());
+    }
+}
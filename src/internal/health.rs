@@ -0,0 +1,169 @@
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use poise::serenity_prelude as serenity;
+use serde::Serialize;
+use sqlx::{Pool, Sqlite};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::database;
+use crate::internal::clock::Clock;
+use crate::internal::feeder::LastRun;
+use crate::internal::settings::Health;
+
+/// How long the database gets to answer before it counts as unreachable.
+const DATABASE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize)]
+struct Shard {
+    id: u32,
+    stage: String,
+    latency_ms: Option<u128>,
+}
+
+#[derive(Debug, Serialize)]
+struct Gateway {
+    ok: bool,
+    shards: Vec<Shard>,
+}
+
+#[derive(Debug, Serialize)]
+struct DatabaseStatus {
+    ok: bool,
+    reachable: bool,
+    pending_migrations: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct FeederStatus {
+    ok: bool,
+    last_run_ts: Option<i64>,
+    age_secs: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    ready: bool,
+    gateway: Gateway,
+    database: DatabaseStatus,
+    feeder: FeederStatus,
+}
+
+/// Whether the feeder ran recently enough, it runs every 30 seconds while the
+/// bot is connected.
+fn feeder_ok(last_run_ts: Option<i64>, now: i64, max_age: Duration) -> bool {
+    last_run_ts.is_some_and(|ts| now - ts <= max_age.as_secs() as i64)
+}
+
+#[derive(Clone)]
+struct HealthState {
+    database: Pool<Sqlite>,
+    shard_manager: Arc<serenity::ShardManager>,
+    last_run: LastRun,
+    clock: Arc<dyn Clock>,
+    feeder_max_age: Duration,
+}
+
+async fn report(state: &HealthState) -> Report {
+    let shards = state
+        .shard_manager
+        .runners
+        .lock()
+        .await
+        .iter()
+        .map(|(id, runner)| Shard {
+            id: id.0,
+            stage: runner.stage.to_string(),
+            latency_ms: runner.latency.map(|latency| latency.as_millis()),
+        })
+        .collect::<Vec<_>>();
+    let gateway = Gateway {
+        ok: !shards.is_empty() && shards.iter().all(|shard| shard.stage == "connected"),
+        shards,
+    };
+
+    let reachable = tokio::time::timeout(DATABASE_TIMEOUT, database::is_reachable(&state.database))
+        .await
+        .unwrap_or(false);
+    let pending_migrations = if reachable {
+        database::pending_migrations(&state.database).await
+    } else {
+        None
+    };
+    let database = DatabaseStatus {
+        ok: reachable && pending_migrations == Some(0),
+        reachable,
+        pending_migrations,
+    };
+
+    let now = state.clock.timestamp();
+    let last_run_ts = state.last_run.get();
+    let feeder = FeederStatus {
+        ok: feeder_ok(last_run_ts, now, state.feeder_max_age),
+        last_run_ts,
+        age_secs: last_run_ts.map(|ts| now - ts),
+    };
+
+    Report {
+        ready: gateway.ok && database.ok && feeder.ok,
+        gateway,
+        database,
+        feeder,
+    }
+}
+
+/// Answers as long as the process runs, with the full report for debugging.
+async fn healthz(State(state): State<HealthState>) -> impl IntoResponse {
+    Json(report(&state).await)
+}
+
+/// Answers 503 until the gateway is connected, the database is migrated and
+/// the feeder keeps running.
+async fn readyz(State(state): State<HealthState>) -> impl IntoResponse {
+    let report = report(&state).await;
+    let status = if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(report))
+}
+
+/// Serves the liveness check at `/healthz` and the readiness check at
+/// `/readyz`.
+pub fn router(
+    database: Pool<Sqlite>,
+    shard_manager: Arc<serenity::ShardManager>,
+    last_run: LastRun,
+    clock: Arc<dyn Clock>,
+    settings: &Health,
+) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(HealthState {
+            database,
+            shard_manager,
+            last_run,
+            clock,
+            feeder_max_age: settings.feeder_max_age,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feeder_needs_a_recent_run() {
+        let max_age = Duration::from_secs(300);
+
+        assert!(!feeder_ok(None, 1_000, max_age));
+        assert!(feeder_ok(Some(700), 1_000, max_age));
+        assert!(!feeder_ok(Some(699), 1_000, max_age));
+    }
+}
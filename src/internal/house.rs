@@ -0,0 +1,52 @@
+use poise::serenity_prelude as serenity;
+
+use crate::internal::data::Context;
+use crate::internal::discord;
+
+/// Largest net win the house is willing to risk on a single bet.
+pub async fn max_win(ctx: &Context<'_>) -> i64 {
+    let balance = ctx.data().players.find_house_balance().await;
+    (balance as f64 * ctx.data().house.max_payout_ratio)
+        .floor()
+        .max(0.0) as i64
+}
+
+/// Checks that the house can cover the win of the bet and tells the player
+/// when it can't.
+pub async fn covers(ctx: &Context<'_>, win: i64) -> bool {
+    let max_win = max_win(ctx).await;
+
+    if win <= max_win {
+        return true;
+    }
+
+    refuse(ctx, max_win).await;
+
+    false
+}
+
+/// Largest net win the house can cover for a bet whose win is not known up
+/// front, tells the player when not even `min_win` is covered.
+pub async fn win_cap(ctx: &Context<'_>, min_win: i64) -> Option<i64> {
+    let max_win = max_win(ctx).await;
+
+    if min_win <= max_win {
+        return Some(max_win);
+    }
+
+    refuse(ctx, max_win).await;
+
+    None
+}
+
+async fn refuse(ctx: &Context<'_>, max_win: i64) {
+    discord::failure_message(
+        ctx,
+        format!(
+            "{} Maja ei suuda nii suurt võitu katta, suurim võimalik võit on {} :potato:.",
+            serenity::Mention::from(ctx.author().id),
+            max_win
+        ),
+    )
+    .await;
+}
@@ -0,0 +1,39 @@
+use axum::Router;
+use tracing::{error, info};
+
+/// Serves the router on the address, in the same runtime as the bot.
+pub fn serve(listen: &str, router: Router) {
+    let listen = listen.to_string();
+
+    tokio::spawn(async move {
+        let listener = match tokio::net::TcpListener::bind(&listen).await {
+            Ok(listener) => listener,
+            Err(why) => {
+                error!("Could not listen on {listen}: {why:?}");
+                return;
+            }
+        };
+
+        info!("Serving HTTP on {listen}");
+
+        if let Err(why) = axum::serve(listener, router).await {
+            error!("HTTP server stopped: {why:?}");
+        }
+    });
+}
+
+/// Serves every router, the ones on the same address from a single server.
+pub fn serve_all(routers: Vec<(String, Router)>) {
+    let mut servers: Vec<(String, Router)> = vec![];
+
+    for (listen, router) in routers {
+        match servers.iter_mut().find(|(address, _)| *address == listen) {
+            Some((_, merged)) => *merged = std::mem::take(merged).merge(router),
+            None => servers.push((listen, router)),
+        }
+    }
+
+    for (listen, router) in servers {
+        serve(&listen, router);
+    }
+}
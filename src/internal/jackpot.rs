@@ -0,0 +1,202 @@
+use poise::serenity_prelude as serenity;
+use rand::Rng;
+use sqlx::{Pool, Sqlite};
+use std::sync::Arc;
+use tracing::error;
+
+use crate::database::house::contribute_to_jackpot;
+use crate::database::jackpot::win_jackpot;
+use crate::internal::clock::Clock;
+use crate::internal::data::{Context, Data};
+use crate::internal::discord;
+use crate::internal::settings::Jackpot;
+
+/// Part of a losing bet that goes into the jackpot pool.
+pub fn contribution(amount: i64, settings: &Jackpot) -> i64 {
+    (amount as f64 * settings.contribution.clamp(0.0, 1.0)).floor() as i64
+}
+
+/// Jackpot side of the bets, shared by every game once a bet is settled.
+/// Cloned into the games played out in the background.
+#[derive(Clone, Debug)]
+pub struct JackpotRoller {
+    database: Pool<Sqlite>,
+    settings: Jackpot,
+    clock: Arc<dyn Clock>,
+}
+
+impl JackpotRoller {
+    pub fn new(database: Pool<Sqlite>, settings: Jackpot, clock: Arc<dyn Clock>) -> Self {
+        JackpotRoller {
+            database,
+            settings,
+            clock,
+        }
+    }
+
+    pub fn from_data(data: &Data) -> Self {
+        Self::new(
+            data.database.clone(),
+            data.jackpot.clone(),
+            data.clock.clone(),
+        )
+    }
+
+    /// `lost` is the stake of a lost bet the house has already taken, its
+    /// contribution moves from the house into the pool. Games settling the
+    /// bet with `settle_player_bet` hand the contribution over there and pass
+    /// 0. Then the player gets a chance to win the whole pool, returns the
+    /// announcement of the win.
+    pub async fn settle(&self, user_id: serenity::UserId, lost: i64, game: &str) -> Option<String> {
+        let now = self.clock.timestamp();
+        let contribution = contribution(lost, &self.settings);
+        if contribution > 0
+            && !contribute_to_jackpot(contribution, game, self.clock.day(), &self.database).await
+        {
+            error!(
+                "Could not put {} potatoes of user {} into the jackpot",
+                contribution, user_id
+            );
+        }
+
+        if !rand::rng().random_bool(self.settings.win_chance.clamp(0.0, 1.0)) {
+            return None;
+        }
+
+        let amount = win_jackpot(&user_id.to_string(), game, now, &self.database).await?;
+
+        Some(format!(
+            ":slot_machine: {} võitis JACKPOTI {} :potato:!",
+            serenity::Mention::from(user_id),
+            amount
+        ))
+    }
+
+    /// `settle` for the games played out in the background, the win is
+    /// announced in the channel of the game.
+    pub async fn settle_in(
+        &self,
+        http: &serenity::Http,
+        channel_id: &str,
+        user_id: &str,
+        lost: i64,
+        game: &str,
+    ) {
+        let Ok(user_id) = user_id.parse::<u64>() else {
+            return;
+        };
+
+        let Some(message) = self
+            .settle(serenity::UserId::new(user_id), lost, game)
+            .await
+        else {
+            return;
+        };
+
+        let Ok(channel_id) = channel_id.parse::<u64>() else {
+            return;
+        };
+
+        let embed = serenity::CreateEmbed::new()
+            .description(message)
+            .color(serenity::Color::DARK_GREEN);
+
+        if let Err(why) = serenity::ChannelId::new(channel_id)
+            .send_message(http, serenity::CreateMessage::new().embed(embed))
+            .await
+        {
+            error!("Error sending message: {why:?}");
+        }
+    }
+}
+
+/// `JackpotRoller::settle` for commands, the win is announced in the channel
+/// of the command.
+pub async fn roll(ctx: &Context<'_>, lost: i64, game: &str) {
+    let roller = JackpotRoller::from_data(ctx.data());
+
+    if let Some(message) = roller.settle(ctx.author().id, lost, game).await {
+        discord::success_message(ctx, message).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::house::find_house_balance;
+    use crate::database::jackpot::{find_jackpot_pool, find_last_jackpot_win};
+    use crate::database::players::{create_player, find_player};
+    use crate::internal::clock::FakeClock;
+    use chrono::TimeZone;
+
+    const USER: serenity::UserId = serenity::UserId::new(1);
+
+    async fn roller(win_chance: f64) -> JackpotRoller {
+        let clock = FakeClock::new(
+            chrono_tz::Europe::Tallinn
+                .with_ymd_and_hms(2026, 10, 19, 12, 0, 0)
+                .unwrap(),
+        );
+        let roller = JackpotRoller::new(
+            crate::database::in_memory().await,
+            Jackpot {
+                contribution: 0.1,
+                win_chance,
+            },
+            Arc::new(clock),
+        );
+        create_player(&USER.to_string(), 0, &roller.database)
+            .await
+            .unwrap();
+        roller
+    }
+
+    #[test]
+    fn contribution_is_a_share_of_the_lost_bet() {
+        let settings = Jackpot {
+            contribution: 0.01,
+            win_chance: 0.0,
+        };
+
+        assert_eq!(contribution(1000, &settings), 10);
+        assert_eq!(contribution(99, &settings), 0);
+    }
+
+    #[tokio::test]
+    async fn lost_bet_moves_its_contribution_from_the_house() {
+        let roller = roller(0.0).await;
+        let house = find_house_balance(&roller.database).await;
+        let pool = find_jackpot_pool(&roller.database).await;
+
+        assert_eq!(roller.settle(USER, 500, "race").await, None);
+
+        assert_eq!(find_house_balance(&roller.database).await, house - 50);
+        assert_eq!(find_jackpot_pool(&roller.database).await, pool + 50);
+    }
+
+    #[tokio::test]
+    async fn win_pays_out_the_whole_pool_once() {
+        let roller = roller(1.0).await;
+        let pool = find_jackpot_pool(&roller.database).await;
+
+        assert!(roller.settle(USER, 500, "race").await.is_some());
+        assert_eq!(find_jackpot_pool(&roller.database).await, 0);
+        assert_eq!(
+            find_player(&USER.to_string(), &roller.database)
+                .await
+                .unwrap()
+                .balance,
+            5000 + pool + 50
+        );
+        assert_eq!(
+            find_last_jackpot_win(&roller.database)
+                .await
+                .unwrap()
+                .amount,
+            pool + 50
+        );
+
+        // Nothing is left to win until the pool fills up again.
+        assert_eq!(roller.settle(USER, 0, "race").await, None);
+    }
+}
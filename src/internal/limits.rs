@@ -0,0 +1,168 @@
+use poise::serenity_prelude as serenity;
+use sqlx::{Pool, Sqlite};
+
+use crate::database::limits::{find_player_limits, find_player_loss};
+use crate::internal::clock::Clock;
+use crate::internal::data::Context;
+use crate::internal::discord;
+use crate::internal::shared;
+
+/// Why the player can't bet `amount` right now, `None` when they can. Every
+/// game asks this before taking the bet.
+pub async fn bet_refusal(
+    user_id: &String,
+    amount: i64,
+    clock: &dyn Clock,
+    database: &Pool<Sqlite>,
+) -> Option<String> {
+    let now = clock.timestamp();
+    let mut limits = find_player_limits(user_id, database).await?;
+    limits.apply_due_changes(now);
+
+    if limits.is_excluded(now) {
+        return Some(format!(
+            "Oled end mängimast välja arvanud, panustada saad jälle {} pärast.",
+            shared::format_duration(limits.excluded_until_ts - now)
+        ));
+    }
+
+    if limits.daily_loss_limit > 0 {
+        let loss = find_player_loss(user_id, clock.day(), database).await;
+        let left = (limits.daily_loss_limit - loss).max(0);
+
+        if amount > left {
+            return Some(format!(
+                "Panus ületaks su päevase kaotuslimiidi, täna saad kaotada veel {} :potato:.",
+                left
+            ));
+        }
+    }
+
+    None
+}
+
+/// Checks the limits of the author before they bet and tells them when the
+/// bet is not allowed.
+pub async fn allows_bet(ctx: &Context<'_>, amount: i64) -> bool {
+    let user_id = ctx.author().id.to_string();
+
+    let Some(reason) = bet_refusal(
+        &user_id,
+        amount,
+        ctx.data().clock.as_ref(),
+        &ctx.data().database,
+    )
+    .await
+    else {
+        return true;
+    };
+
+    discord::failure_message(
+        ctx,
+        format!("{} {}", serenity::Mention::from(ctx.author().id), reason),
+    )
+    .await;
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::house::settle_player_bet;
+    use crate::database::limits::{save_player_limits, PlayerLimits};
+    use crate::database::players::{create_player, find_player};
+    use crate::internal::clock::FakeClock;
+    use chrono::{Duration, TimeZone};
+
+    /// Half an hour before midnight in Tallinn, 20:30 in UTC.
+    fn late_evening() -> FakeClock {
+        FakeClock::new(
+            chrono_tz::Europe::Tallinn
+                .with_ymd_and_hms(2026, 10, 19, 23, 30, 0)
+                .unwrap(),
+        )
+    }
+
+    async fn limited(limits: PlayerLimits) -> Pool<Sqlite> {
+        let database = crate::database::in_memory().await;
+        create_player(&limits.discord_user_id, 0, &database)
+            .await
+            .unwrap();
+        let mut limits = limits;
+        assert!(save_player_limits(&mut limits, &database).await);
+        database
+    }
+
+    fn daily_loss_limit(limit: i64) -> PlayerLimits {
+        let mut limits = PlayerLimits::new("1");
+        limits.daily_loss_limit = limit;
+        limits
+    }
+
+    async fn lose(amount: i64, clock: &dyn Clock, database: &Pool<Sqlite>) {
+        let mut player = find_player(&"1".to_string(), database).await.unwrap();
+        player.balance -= amount;
+        assert!(settle_player_bet(&mut player, amount, 0, "dice", clock.day(), database).await);
+    }
+
+    #[tokio::test]
+    async fn bet_over_the_daily_loss_limit_is_refused() {
+        let clock = late_evening();
+        let database = limited(daily_loss_limit(1000)).await;
+        let user_id = "1".to_string();
+
+        lose(800, &clock, &database).await;
+
+        let refusal = bet_refusal(&user_id, 300, &clock, &database).await.unwrap();
+        assert!(refusal.contains("täna saad kaotada veel 200 :potato:"));
+        assert!(bet_refusal(&user_id, 200, &clock, &database)
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn daily_loss_starts_over_at_local_midnight() {
+        let clock = late_evening();
+        let database = limited(daily_loss_limit(1000)).await;
+        let user_id = "1".to_string();
+
+        lose(1000, &clock, &database).await;
+        assert!(bet_refusal(&user_id, 1, &clock, &database).await.is_some());
+
+        // Half past midnight in Tallinn, still the same day in UTC.
+        clock.advance(Duration::hours(1));
+        assert_eq!(
+            clock.now().naive_utc().date(),
+            clock.now().date_naive().pred_opt().unwrap()
+        );
+
+        assert!(bet_refusal(&user_id, 1000, &clock, &database)
+            .await
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn excluded_player_is_refused_until_the_exclusion_ends() {
+        let clock = late_evening();
+        let mut limits = PlayerLimits::new("1");
+        limits.excluded_until_ts = clock.timestamp() + 3600;
+        let database = limited(limits).await;
+        let user_id = "1".to_string();
+
+        assert!(bet_refusal(&user_id, 1, &clock, &database).await.is_some());
+
+        clock.advance(Duration::hours(1));
+        assert!(bet_refusal(&user_id, 1, &clock, &database).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn player_without_limits_can_bet_anything() {
+        let clock = late_evening();
+        let database = crate::database::in_memory().await;
+
+        assert!(bet_refusal(&"1".to_string(), 1_000_000, &clock, &database)
+            .await
+            .is_none());
+    }
+}
@@ -0,0 +1,225 @@
+use poise::serenity_prelude as serenity;
+use tracing::error;
+
+use crate::database::markets::{
+    Market, MarketOutcome, MarketPosition, STATUS_CANCELLED, STATUS_CLOSED, STATUS_OPEN,
+    STATUS_RESOLVED,
+};
+
+fn outcome_pool(positions: &[MarketPosition], outcome: i64) -> i64 {
+    positions
+        .iter()
+        .filter(|position| position.outcome == outcome)
+        .map(|position| position.amount)
+        .sum()
+}
+
+/// Current pari-mutuel odds of the outcome, `None` while nobody has bet on it.
+fn odds(positions: &[MarketPosition], outcome: i64, house_take: f64) -> Option<f64> {
+    let pool = positions
+        .iter()
+        .map(|position| position.amount)
+        .sum::<i64>();
+    let outcome_pool = outcome_pool(positions, outcome);
+
+    if outcome_pool == 0 {
+        return None;
+    }
+
+    Some(pool as f64 * (1.0 - house_take) / outcome_pool as f64)
+}
+
+/// Finds the outcome by its number or name.
+pub fn find_outcome<'a>(outcomes: &'a [MarketOutcome], input: &str) -> Option<&'a MarketOutcome> {
+    let input = input.trim();
+
+    outcomes.iter().find(|outcome| {
+        input.parse::<i64>().ok() == Some(outcome.outcome)
+            || outcome.name.to_lowercase() == input.to_lowercase()
+    })
+}
+
+/// Splits the pool minus the house take between the positions on the winning
+/// outcome in proportion to their size. Everybody is refunded when nobody bet
+/// on it. Returns the positions with payouts and the amount kept by the house.
+pub fn settle(
+    positions: &[MarketPosition],
+    winning_outcome: i64,
+    house_take: f64,
+) -> (Vec<MarketPosition>, i64) {
+    let pool = positions
+        .iter()
+        .map(|position| position.amount)
+        .sum::<i64>();
+    let winner_pool = outcome_pool(positions, winning_outcome);
+
+    if winner_pool == 0 {
+        return (refund(positions), 0);
+    }
+
+    let distributable = pool - (pool as f64 * house_take).floor() as i64;
+
+    let settled = positions
+        .iter()
+        .map(|position| {
+            let payout = if position.outcome == winning_outcome {
+                (distributable as i128 * position.amount as i128 / winner_pool as i128) as i64
+            } else {
+                0
+            };
+            MarketPosition {
+                payout: Some(payout),
+                ..position.clone()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let paid = settled
+        .iter()
+        .map(|position| position.payout.unwrap_or_default())
+        .sum::<i64>();
+
+    (settled, pool - paid)
+}
+
+pub fn refund(positions: &[MarketPosition]) -> Vec<MarketPosition> {
+    positions
+        .iter()
+        .map(|position| MarketPosition {
+            payout: Some(position.amount),
+            ..position.clone()
+        })
+        .collect()
+}
+
+fn mention(user_id: &str) -> String {
+    user_id
+        .parse::<u64>()
+        .map(|id| serenity::Mention::from(serenity::UserId::new(id)).to_string())
+        .unwrap_or_else(|_| user_id.to_string())
+}
+
+fn describe_outcomes(
+    market: &Market,
+    outcomes: &[MarketOutcome],
+    positions: &[MarketPosition],
+    house_take: f64,
+) -> String {
+    outcomes
+        .iter()
+        .map(|outcome| {
+            let marker = if market.winning_outcome == Some(outcome.outcome) {
+                ":white_check_mark: "
+            } else {
+                ""
+            };
+            let odds = odds(positions, outcome.outcome, house_take)
+                .map_or_else(|| "-".to_string(), |odds| format!("x{:.2}", odds));
+            format!(
+                "{}`{}` **{}**: {} :potato: ({})",
+                marker,
+                outcome.outcome,
+                outcome.name,
+                outcome_pool(positions, outcome.outcome),
+                odds
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn market_embed(
+    market: &Market,
+    outcomes: &[MarketOutcome],
+    positions: &[MarketPosition],
+    house_take: f64,
+) -> serenity::CreateEmbed {
+    let pool = positions
+        .iter()
+        .map(|position| position.amount)
+        .sum::<i64>();
+
+    let (description, color) = match market.status.as_str() {
+        STATUS_OPEN => (
+            format!("Panusta käsuga `!bet place {} <valik> <panus>`.", market.id),
+            serenity::Color::GOLD,
+        ),
+        STATUS_CLOSED => (
+            "Panuseid enam ei võeta, tulemust oodatakse.".to_string(),
+            serenity::Color::ORANGE,
+        ),
+        STATUS_RESOLVED => {
+            let winners = positions
+                .iter()
+                .filter(|position| Some(position.outcome) == market.winning_outcome)
+                .map(|position| {
+                    format!(
+                        "{} {} :potato: → {} :potato:",
+                        mention(&position.discord_user_id),
+                        position.amount,
+                        position.payout.unwrap_or_default()
+                    )
+                })
+                .collect::<Vec<_>>();
+            let winners = if winners.is_empty() {
+                "Keegi ei panustanud võitjale, kõik panused tagastati.".to_string()
+            } else {
+                winners.join("\n")
+            };
+            (winners, serenity::Color::BLUE)
+        }
+        STATUS_CANCELLED => (
+            "Turg tühistati, kõik panused tagastati.".to_string(),
+            serenity::Color::RED,
+        ),
+        _ => (String::new(), serenity::Color::GOLD),
+    };
+
+    serenity::CreateEmbed::new()
+        .title(format!(":crystal_ball: {}", market.question))
+        .description(description)
+        .field(
+            "Valikud",
+            describe_outcomes(market, outcomes, positions, house_take),
+            false,
+        )
+        .field(
+            "Pank",
+            if market.house_take > 0 {
+                format!(
+                    "{} :potato: (maja osa {} :potato:)",
+                    pool, market.house_take
+                )
+            } else {
+                format!("{} :potato:", pool)
+            },
+            false,
+        )
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "Turg #{}",
+            market.id
+        )))
+        .color(color)
+}
+
+/// Updates the message of the market with the latest odds.
+pub async fn update_market_message(
+    http: &serenity::Http,
+    market: &Market,
+    embed: serenity::CreateEmbed,
+) {
+    let (Ok(channel_id), Some(Ok(message_id))) = (
+        market.channel_id.parse::<u64>(),
+        market.message_id.as_ref().map(|id| id.parse::<u64>()),
+    ) else {
+        return;
+    };
+
+    let edit = serenity::EditMessage::new().embed(embed);
+    if let Err(why) = serenity::ChannelId::new(channel_id)
+        .edit_message(http, serenity::MessageId::new(message_id), edit)
+        .await
+    {
+        error!("Error editing message: {why:?}");
+    }
+}
@@ -0,0 +1,176 @@
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use poise::serenity_prelude as serenity;
+use prometheus::{
+    register_gauge_vec, register_histogram, register_int_counter_vec, register_int_gauge, Encoder,
+    GaugeVec, Histogram, IntCounterVec, IntGauge, TextEncoder,
+};
+use sqlx::{Pool, Sqlite};
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+use tracing::field::{Field, Visit};
+use tracing::{error, Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+use crate::database::players::find_circulation;
+
+static COMMANDS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "etbot_commands_total",
+        "Commands executed by name and outcome.",
+        &["command", "outcome"]
+    )
+    .unwrap()
+});
+
+static BETS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!("etbot_bets_total", "Bets placed per game.", &["game"]).unwrap()
+});
+
+static BET_POTATOES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "etbot_bet_potatoes_total",
+        "Potatoes bet per game.",
+        &["game"]
+    )
+    .unwrap()
+});
+
+static PAYOUT_POTATOES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register_int_counter_vec!(
+        "etbot_payout_potatoes_total",
+        "Potatoes paid out per game, refunds included.",
+        &["game"]
+    )
+    .unwrap()
+});
+
+static CIRCULATION: LazyLock<IntGauge> = LazyLock::new(|| {
+    register_int_gauge!(
+        "etbot_potatoes_in_circulation",
+        "Potatoes held by the players."
+    )
+    .unwrap()
+});
+
+static FEEDER_DURATION: LazyLock<Histogram> = LazyLock::new(|| {
+    register_histogram!(
+        "etbot_feeder_run_duration_seconds",
+        "Duration of the feeder runs."
+    )
+    .unwrap()
+});
+
+static QUERY_DURATION: LazyLock<Histogram> = LazyLock::new(|| {
+    register_histogram!(
+        "etbot_database_query_duration_seconds",
+        "Duration of the database queries.",
+        vec![0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0]
+    )
+    .unwrap()
+});
+
+static GATEWAY_LATENCY: LazyLock<GaugeVec> = LazyLock::new(|| {
+    register_gauge_vec!(
+        "etbot_gateway_latency_seconds",
+        "Last heartbeat latency of the gateway shards.",
+        &["shard"]
+    )
+    .unwrap()
+});
+
+pub fn command(name: &str, outcome: &str) {
+    COMMANDS.with_label_values(&[name, outcome]).inc();
+}
+
+pub fn bet(game: &str, amount: i64) {
+    BETS.with_label_values(&[game]).inc();
+    BET_POTATOES
+        .with_label_values(&[game])
+        .inc_by(amount.max(0) as u64);
+}
+
+pub fn payout(game: &str, amount: i64) {
+    PAYOUT_POTATOES
+        .with_label_values(&[game])
+        .inc_by(amount.max(0) as u64);
+}
+
+pub fn feeder_run(duration: Duration) {
+    FEEDER_DURATION.observe(duration.as_secs_f64());
+}
+
+/// Records the duration of every statement logged by sqlx, the `sqlx::query`
+/// events need to be let through at the debug level.
+pub struct QueryLatency;
+
+struct ElapsedSecs(Option<f64>);
+
+impl Visit for ElapsedSecs {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        if field.name() == "elapsed_secs" {
+            self.0 = Some(value);
+        }
+    }
+
+    fn record_debug(&mut self, _: &Field, _: &dyn std::fmt::Debug) {}
+}
+
+impl<S: Subscriber> Layer<S> for QueryLatency {
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        let mut elapsed = ElapsedSecs(None);
+        event.record(&mut elapsed);
+
+        if let Some(secs) = elapsed.0 {
+            QUERY_DURATION.observe(secs);
+        }
+    }
+}
+
+#[derive(Clone)]
+struct MetricsState {
+    database: Pool<Sqlite>,
+    shard_manager: Arc<serenity::ShardManager>,
+}
+
+async fn render(State(state): State<MetricsState>) -> impl IntoResponse {
+    CIRCULATION.set(find_circulation(&state.database).await);
+
+    for (shard_id, runner) in state.shard_manager.runners.lock().await.iter() {
+        if let Some(latency) = runner.latency {
+            GATEWAY_LATENCY
+                .with_label_values(&[&shard_id.to_string()])
+                .set(latency.as_secs_f64());
+        }
+    }
+
+    let encoder = TextEncoder::new();
+    let mut body = vec![];
+    if let Err(why) = encoder.encode(&prometheus::gather(), &mut body) {
+        error!("Could not encode metrics: {why:?}");
+    }
+
+    ([(CONTENT_TYPE, encoder.format_type().to_string())], body)
+}
+
+/// Serves the metrics at `/metrics`.
+pub fn router(database: Pool<Sqlite>, shard_manager: Arc<serenity::ShardManager>) -> Router {
+    // The metrics show up before their first use.
+    LazyLock::force(&COMMANDS);
+    LazyLock::force(&BETS);
+    LazyLock::force(&BET_POTATOES);
+    LazyLock::force(&PAYOUT_POTATOES);
+    LazyLock::force(&FEEDER_DURATION);
+    LazyLock::force(&QUERY_DURATION);
+    LazyLock::force(&GATEWAY_LATENCY);
+
+    Router::new()
+        .route("/metrics", get(render))
+        .with_state(MetricsState {
+            database,
+            shard_manager,
+        })
+}
@@ -0,0 +1,468 @@
+use poise::serenity_prelude as serenity;
+use sqlx::{Pool, Sqlite};
+use std::sync::{Arc, Mutex};
+use tracing::{error, info, instrument, warn};
+
+use crate::database::mines::{
+    find_expired_mines_games, find_mines_game, save_mines_game, settle_mines_game, MinesGame,
+    STATUS_ACTIVE, STATUS_CASHED_OUT, STATUS_FORFEITED, STATUS_LOST, STATUS_REFUNDED,
+};
+use crate::internal::clock::Clock;
+use crate::internal::data::Data;
+use crate::internal::events;
+use crate::internal::jackpot::JackpotRoller;
+use crate::internal::metrics;
+use crate::internal::settings::Mines;
+
+pub const GRID_SIZE: i64 = 5;
+pub const TILES: i64 = GRID_SIZE * GRID_SIZE;
+
+const BUTTON_PREFIX: &str = "mines-";
+
+pub fn encode_tiles(tiles: &[i64]) -> String {
+    tiles
+        .iter()
+        .map(|tile| tile.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub fn decode_tiles(tiles: &str) -> Vec<i64> {
+    tiles
+        .split(',')
+        .filter_map(|tile| tile.parse::<i64>().ok())
+        .collect()
+}
+
+/// Picks the tiles hiding the mines.
+pub fn place_mines(mines: i64) -> Vec<i64> {
+    rand::seq::index::sample(&mut rand::rng(), TILES as usize, mines as usize)
+        .into_iter()
+        .map(|tile| tile as i64)
+        .collect()
+}
+
+/// Multiplier (in hundredths) after opening `revealed` safe tiles: the inverse
+/// of the chance of getting that far, minus the house edge.
+pub fn multiplier(mines: i64, revealed: i64, house_edge: f64) -> i64 {
+    if revealed == 0 {
+        return 100;
+    }
+
+    let fair = (0..revealed).fold(1.0, |acc, i| {
+        acc * (TILES - i) as f64 / (TILES - mines - i) as f64
+    });
+
+    ((fair * (1.0 - house_edge) * 100.0).floor() as i64).max(100)
+}
+
+fn format_multiplier(multiplier: i64) -> String {
+    format!("{}.{:02}x", multiplier / 100, multiplier % 100)
+}
+
+fn payout(amount: i64, multiplier: i64) -> i64 {
+    amount * multiplier / 100
+}
+
+/// Net win of opening the first safe tile, the least the house has to cover
+/// for the game to be worth playing.
+pub fn first_win(amount: i64, mines: i64, house_edge: f64, boost: f64) -> i64 {
+    events::multiply(payout(amount, multiplier(mines, 1, house_edge)), boost) - amount
+}
+
+/// What cashing out pays right now with the boost of the game, capped at the
+/// most the house could cover when the bet was placed.
+fn cash_out_amount(game: &MinesGame, revealed: i64, house_edge: f64) -> i64 {
+    let amount = events::multiply(
+        payout(game.amount, multiplier(game.mines, revealed, house_edge)),
+        game.boost,
+    );
+    game.max_payout
+        .map_or(amount, |max_payout| amount.min(max_payout))
+}
+
+pub fn game_embed(game: &MinesGame, settings: &Mines) -> serenity::CreateEmbed {
+    let revealed = decode_tiles(&game.revealed_tiles).len() as i64;
+    let current = multiplier(game.mines, revealed, settings.house_edge);
+    let mention = game
+        .discord_user_id
+        .parse::<u64>()
+        .map(|id| serenity::Mention::from(serenity::UserId::new(id)).to_string())
+        .unwrap_or_else(|_| game.discord_user_id.clone());
+
+    let (description, color) = match game.status.as_str() {
+        STATUS_ACTIVE => (
+            format!(
+                "{} ava ruute nuppudega. Võidu võtad välja käsuga `!mines cashout`.",
+                mention
+            ),
+            serenity::Color::GOLD,
+        ),
+        STATUS_CASHED_OUT => (
+            format!(
+                "{} võttis välja {} :potato:.",
+                mention,
+                game.payout.unwrap_or_default()
+            ),
+            serenity::Color::DARK_GREEN,
+        ),
+        STATUS_LOST => (
+            format!(
+                "{} astus miini otsa ja kaotas {} :potato:.",
+                mention, game.amount
+            ),
+            serenity::Color::RED,
+        ),
+        STATUS_REFUNDED => (
+            format!(
+                "{} mäng aegus enne esimest käiku, panus tagastati.",
+                mention
+            ),
+            serenity::Color::LIGHT_GREY,
+        ),
+        _ => (
+            format!("{} mäng aegus, panus läks kaotsi.", mention),
+            serenity::Color::RED,
+        ),
+    };
+
+    let mut embed = serenity::CreateEmbed::new()
+        .title(":bomb: Miinid")
+        .description(description)
+        .field("Panus", format!("{} :potato:", game.amount), true)
+        .field("Miine", game.mines.to_string(), true)
+        .field("Kordaja", format_multiplier(current), true);
+
+    if game.boost > 1.0 {
+        embed = embed.field("Sündmus", format!("võidud x{}", game.boost), true);
+    }
+
+    if game.status == STATUS_ACTIVE && revealed < TILES - game.mines {
+        let next = multiplier(game.mines, revealed + 1, settings.house_edge);
+        embed = embed
+            .field(
+                "Väljavõtt",
+                format!(
+                    "{} :potato:",
+                    cash_out_amount(game, revealed, settings.house_edge)
+                ),
+                true,
+            )
+            .field("Järgmine ruut", format_multiplier(next), true);
+        if let Some(max_payout) = game.max_payout {
+            embed = embed.field("Suurim väljavõtt", format!("{} :potato:", max_payout), true);
+        }
+    }
+
+    embed
+        .footer(serenity::CreateEmbedFooter::new(format!(
+            "Mäng #{}",
+            game.id
+        )))
+        .color(color)
+}
+
+pub fn game_components(game: &MinesGame) -> Vec<serenity::CreateActionRow> {
+    let mines = decode_tiles(&game.mine_tiles);
+    let revealed = decode_tiles(&game.revealed_tiles);
+    let is_active = game.status == STATUS_ACTIVE;
+
+    (0..GRID_SIZE)
+        .map(|row| {
+            let buttons = (0..GRID_SIZE)
+                .map(|column| {
+                    let tile = row * GRID_SIZE + column;
+                    let button = serenity::CreateButton::new(format!(
+                        "{}{}-{}",
+                        BUTTON_PREFIX, game.id, tile
+                    ));
+                    let is_mine = mines.contains(&tile);
+                    let is_revealed = revealed.contains(&tile);
+
+                    match (is_active, is_mine, is_revealed) {
+                        (true, _, false) => {
+                            button.label("?").style(serenity::ButtonStyle::Secondary)
+                        }
+                        (_, false, true) => button
+                            .emoji('🥔')
+                            .style(serenity::ButtonStyle::Success)
+                            .disabled(true),
+                        (false, true, true) => button
+                            .emoji('💥')
+                            .style(serenity::ButtonStyle::Danger)
+                            .disabled(true),
+                        (false, true, false) => button
+                            .emoji('💣')
+                            .style(serenity::ButtonStyle::Secondary)
+                            .disabled(true),
+                        (_, false, false) | (true, true, true) => button
+                            .emoji('🥔')
+                            .style(serenity::ButtonStyle::Secondary)
+                            .disabled(true),
+                    }
+                })
+                .collect();
+            serenity::CreateActionRow::Buttons(buttons)
+        })
+        .collect()
+}
+
+/// Opens the tile, the game is lost on a mine and cashed out automatically
+/// once every safe tile has been opened.
+pub async fn reveal(
+    database: &Pool<Sqlite>,
+    settings: &Mines,
+    game_id: i64,
+    user_id: &String,
+    tile: i64,
+    clock: &dyn Clock,
+) -> Result<MinesGame, String> {
+    let Some(mut game) = find_mines_game(game_id, database).await else {
+        return Err("Mängu ei leitud.".into());
+    };
+
+    if &game.discord_user_id != user_id {
+        return Err("See pole sinu mäng.".into());
+    }
+
+    if game.status != STATUS_ACTIVE {
+        return Err("Mäng on juba läbi.".into());
+    }
+
+    let mut revealed = decode_tiles(&game.revealed_tiles);
+    if revealed.contains(&tile) {
+        return Err("See ruut on juba avatud.".into());
+    }
+
+    revealed.push(tile);
+    game.revealed_tiles = encode_tiles(&revealed);
+    game.updated_ts = clock.timestamp();
+
+    if decode_tiles(&game.mine_tiles).contains(&tile) {
+        game.status = STATUS_LOST.into();
+        game.payout = Some(0);
+        if !save_mines_game(&mut game, database).await {
+            return Err("Mäng muutus vahepeal, proovi uuesti.".into());
+        }
+        return Ok(game);
+    }
+
+    if revealed.len() as i64 == TILES - game.mines {
+        return cash_out(database, settings, game, clock).await;
+    }
+
+    if !save_mines_game(&mut game, database).await {
+        return Err("Mäng muutus vahepeal, proovi uuesti.".into());
+    }
+
+    Ok(game)
+}
+
+/// Ends the game and pays out the bet with the current multiplier, at most
+/// the `max_payout` of the game.
+pub async fn cash_out(
+    database: &Pool<Sqlite>,
+    settings: &Mines,
+    mut game: MinesGame,
+    clock: &dyn Clock,
+) -> Result<MinesGame, String> {
+    let revealed = decode_tiles(&game.revealed_tiles).len() as i64;
+    let amount = cash_out_amount(&game, revealed, settings.house_edge);
+
+    game.status = STATUS_CASHED_OUT.into();
+    game.payout = Some(amount);
+    game.updated_ts = clock.timestamp();
+
+    if !settle_mines_game(&mut game, clock.day(), database).await {
+        return Err("Mäng muutus vahepeal, proovi uuesti.".into());
+    }
+    metrics::payout("mines", amount);
+
+    Ok(game)
+}
+
+/// Updates the game message outside of an interaction.
+pub async fn update_game_message(ctx: &serenity::Context, game: &MinesGame, settings: &Mines) {
+    let (Ok(channel_id), Some(Ok(message_id))) = (
+        game.channel_id.parse::<u64>(),
+        game.message_id.as_ref().map(|id| id.parse::<u64>()),
+    ) else {
+        return;
+    };
+
+    let edit = serenity::EditMessage::new()
+        .embed(game_embed(game, settings))
+        .components(game_components(game));
+
+    if let Err(why) = serenity::ChannelId::new(channel_id)
+        .edit_message(ctx, serenity::MessageId::new(message_id), edit)
+        .await
+    {
+        error!("Error editing message: {why:?}");
+    }
+}
+
+pub async fn handle_interaction(
+    ctx: &serenity::Context,
+    data: &Data,
+    interaction: &serenity::ComponentInteraction,
+) {
+    let Some((game_id, tile)) = interaction
+        .data
+        .custom_id
+        .strip_prefix(BUTTON_PREFIX)
+        .and_then(|rest| rest.split_once('-'))
+        .and_then(|(id, tile)| Some((id.parse::<i64>().ok()?, tile.parse::<i64>().ok()?)))
+    else {
+        return;
+    };
+
+    let user_id = interaction.user.id.to_string();
+
+    let result = reveal(
+        &data.database,
+        &data.mines,
+        game_id,
+        &user_id,
+        tile,
+        data.clock.as_ref(),
+    )
+    .await;
+
+    let response = match &result {
+        Ok(game) => serenity::CreateInteractionResponse::UpdateMessage(
+            serenity::CreateInteractionResponseMessage::new()
+                .embed(game_embed(game, &data.mines))
+                .components(game_components(game)),
+        ),
+        Err(message) => serenity::CreateInteractionResponse::Message(
+            serenity::CreateInteractionResponseMessage::new()
+                .content(message)
+                .ephemeral(true),
+        ),
+    };
+
+    if let Err(why) = interaction.create_response(ctx, response).await {
+        error!("Error responding to interaction: {why:?}");
+    }
+
+    if let Ok(game) = result {
+        if game.status != STATUS_ACTIVE {
+            settle_jackpot(ctx, &JackpotRoller::from_data(data), &game).await;
+        }
+    }
+}
+
+/// Jackpot side of a finished game, a game without a payout was lost.
+async fn settle_jackpot(ctx: &serenity::Context, jackpot: &JackpotRoller, game: &MinesGame) {
+    let lost = if game.payout.unwrap_or_default() == 0 {
+        game.amount
+    } else {
+        0
+    };
+
+    jackpot
+        .settle_in(
+            &ctx.http,
+            &game.channel_id,
+            &game.discord_user_id,
+            lost,
+            "mines",
+        )
+        .await;
+}
+
+/// Closes games left untouched for longer than the timeout: bets of games
+/// without a single opened tile are refunded, the rest are forfeited.
+#[derive(Debug)]
+pub struct MinesKeeper {
+    database: Pool<Sqlite>,
+    settings: Mines,
+    jackpot: JackpotRoller,
+    clock: Arc<dyn Clock>,
+    is_running: Mutex<bool>,
+}
+
+impl MinesKeeper {
+    pub fn new(
+        database: Pool<Sqlite>,
+        settings: Mines,
+        jackpot: JackpotRoller,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        MinesKeeper {
+            database,
+            settings,
+            jackpot,
+            clock,
+            is_running: Mutex::new(false),
+        }
+    }
+
+    #[instrument]
+    pub fn start(&self, ctx: serenity::Context) {
+        let mut is_running = self.is_running.lock().unwrap();
+        if *is_running {
+            return;
+        }
+
+        *is_running = true;
+
+        let database = self.database.clone();
+        let settings = self.settings.clone();
+        let jackpot = self.jackpot.clone();
+        let clock = self.clock.clone();
+
+        tokio::spawn(async move {
+            let mut interval_timer = tokio::time::interval(duration_str::parse("1m").unwrap());
+
+            loop {
+                interval_timer.tick().await;
+
+                expire_games(&ctx, &database, &settings, &jackpot, clock.as_ref()).await;
+            }
+        });
+    }
+}
+
+async fn expire_games(
+    ctx: &serenity::Context,
+    database: &Pool<Sqlite>,
+    settings: &Mines,
+    jackpot: &JackpotRoller,
+    clock: &dyn Clock,
+) {
+    let now = clock.timestamp();
+    let before_ts = now - settings.timeout.as_secs() as i64;
+
+    for mut game in find_expired_mines_games(before_ts, database).await {
+        let is_untouched = decode_tiles(&game.revealed_tiles).is_empty();
+
+        if is_untouched {
+            game.status = STATUS_REFUNDED.into();
+            game.payout = Some(game.amount);
+        } else {
+            game.status = STATUS_FORFEITED.into();
+            game.payout = Some(0);
+        }
+        game.updated_ts = now;
+
+        // The player made a move in the meantime.
+        if !settle_mines_game(&mut game, clock.day(), database).await {
+            warn!("Could not expire mines game {}", game.id);
+            continue;
+        }
+
+        info!("Mines game {} expired as {}", game.id, game.status);
+
+        if is_untouched {
+            metrics::payout("mines", game.amount);
+        }
+
+        update_game_message(ctx, &game, settings).await;
+
+        if !is_untouched {
+            settle_jackpot(ctx, jackpot, &game).await;
+        }
+    }
+}
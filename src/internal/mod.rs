@@ -1,6 +1,28 @@
+pub mod achievements;
+pub mod backups;
+pub mod betting;
+pub mod cards;
+pub mod channels;
+pub mod clock;
+pub mod cooldowns;
+pub mod crash;
 pub mod data;
 pub mod discord;
 pub mod errors;
+pub mod events;
 pub mod feeder;
+pub mod health;
+pub mod house;
+pub mod http;
+pub mod jackpot;
+pub mod limits;
+pub mod markets;
+pub mod metrics;
+pub mod mines;
+pub mod poker;
+pub mod race;
+pub mod role_keeper;
 pub mod settings;
 pub mod shared;
+pub mod snapshot;
+pub mod transfers;
@@ -0,0 +1,961 @@
+use poise::serenity_prelude as serenity;
+use std::collections::BTreeMap;
+use tracing::{error, info, warn};
+
+use crate::database::poker::{
+    find_poker_table, load_poker_seats, settle_poker_table, PokerSeat, PokerTable, STATUS_CLOSED,
+    STATUS_PLAYING, STATUS_WAITING,
+};
+use crate::internal::cards::{self, Card};
+use crate::internal::data::Data;
+use crate::internal::limits;
+use crate::internal::metrics;
+use crate::internal::settings::Poker;
+
+const BUTTON_PREFIX: &str = "poker:";
+
+const STREET_PREFLOP: &str = "preflop";
+const STREET_FLOP: &str = "flop";
+const STREET_TURN: &str = "turn";
+const STREET_RIVER: &str = "river";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TableCommand {
+    Join,
+    Leave,
+    Deal,
+    Cards,
+    Fold,
+    Call,
+    /// Raise to the given total bet, minimum raise when missing.
+    Raise(Option<i64>),
+    AllIn,
+    Close,
+}
+
+impl TableCommand {
+    fn key(&self) -> &'static str {
+        match self {
+            TableCommand::Join => "join",
+            TableCommand::Leave => "leave",
+            TableCommand::Deal => "deal",
+            TableCommand::Cards => "cards",
+            TableCommand::Fold => "fold",
+            TableCommand::Call => "call",
+            TableCommand::Raise(_) => "raise",
+            TableCommand::AllIn => "allin",
+            TableCommand::Close => "close",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        match key {
+            "join" => Some(TableCommand::Join),
+            "leave" => Some(TableCommand::Leave),
+            "deal" => Some(TableCommand::Deal),
+            "cards" => Some(TableCommand::Cards),
+            "fold" => Some(TableCommand::Fold),
+            "call" => Some(TableCommand::Call),
+            "raise" => Some(TableCommand::Raise(None)),
+            "allin" => Some(TableCommand::AllIn),
+            "close" => Some(TableCommand::Close),
+            _ => None,
+        }
+    }
+}
+
+/// Outcome of a table command that has been saved to the database.
+pub struct TableUpdate {
+    pub table: PokerTable,
+    pub seats: Vec<PokerSeat>,
+    pub hand_started: bool,
+}
+
+fn seat_mut(seats: &mut [PokerSeat], seat: i64) -> &mut PokerSeat {
+    seats
+        .iter_mut()
+        .find(|s| s.seat == seat)
+        .expect("seat should exist")
+}
+
+/// First seat after `after` (going around the table) that matches `predicate`.
+fn next_seat(
+    seats: &[PokerSeat],
+    after: i64,
+    predicate: impl Fn(&PokerSeat) -> bool,
+) -> Option<i64> {
+    seats
+        .iter()
+        .filter(|s| s.seat > after)
+        .chain(seats.iter().filter(|s| s.seat <= after))
+        .find(|s| predicate(s))
+        .map(|s| s.seat)
+}
+
+fn is_live(seat: &PokerSeat) -> bool {
+    seat.in_hand && !seat.folded
+}
+
+fn needs_action(seat: &PokerSeat, current_bet: i64) -> bool {
+    is_live(seat) && !seat.all_in && (!seat.acted || seat.bet < current_bet)
+}
+
+fn put_chips(seat: &mut PokerSeat, amount: i64) {
+    let amount = amount.min(seat.stack);
+    seat.stack -= amount;
+    seat.bet += amount;
+    seat.committed += amount;
+    if seat.stack == 0 {
+        seat.all_in = true;
+    }
+}
+
+fn deal_cards(table: &mut PokerTable, count: usize) -> Vec<Card> {
+    let mut deck = cards::decode_cards(&table.deck);
+    let dealt = deck.split_off(deck.len().saturating_sub(count));
+    table.deck = cards::encode_cards(&deck);
+    dealt
+}
+
+/// Deals a new hand and posts the blinds, returns the seat of the big blind.
+fn start_hand(table: &mut PokerTable, seats: &mut [PokerSeat]) -> Result<i64, String> {
+    let players = seats.iter().filter(|s| s.stack > 0 && !s.leaving).count();
+    if players < 2 {
+        return Err("Käe alustamiseks on vaja vähemalt kahte mängijat.".into());
+    }
+
+    for seat in seats.iter_mut() {
+        seat.hole_cards = String::new();
+        seat.bet = 0;
+        seat.committed = 0;
+        seat.in_hand = seat.stack > 0 && !seat.leaving;
+        seat.folded = false;
+        seat.all_in = false;
+        seat.acted = false;
+    }
+
+    let after = if table.hand_no == 0 {
+        -1
+    } else {
+        table.dealer_seat
+    };
+    let dealer = next_seat(seats, after, |s| s.in_hand).unwrap();
+
+    table.deck = cards::encode_cards(&cards::shuffled_deck());
+    for seat in seats
+        .iter()
+        .filter(|s| s.in_hand)
+        .map(|s| s.seat)
+        .collect::<Vec<_>>()
+    {
+        let hole = deal_cards(table, 2);
+        seat_mut(seats, seat).hole_cards = cards::encode_cards(&hole);
+    }
+
+    let (small_blind, big_blind) = if players == 2 {
+        (dealer, next_seat(seats, dealer, |s| s.in_hand).unwrap())
+    } else {
+        let small_blind = next_seat(seats, dealer, |s| s.in_hand).unwrap();
+        (
+            small_blind,
+            next_seat(seats, small_blind, |s| s.in_hand).unwrap(),
+        )
+    };
+
+    put_chips(seat_mut(seats, small_blind), table.small_blind);
+    put_chips(seat_mut(seats, big_blind), table.big_blind);
+
+    table.status = STATUS_PLAYING.into();
+    table.hand_no += 1;
+    table.street = STREET_PREFLOP.into();
+    table.dealer_seat = dealer;
+    table.board = String::new();
+    table.current_bet = table.big_blind;
+    table.min_raise = table.big_blind;
+    table.to_act = None;
+    table.last_result = None;
+
+    Ok(big_blind)
+}
+
+fn apply_action(
+    table: &mut PokerTable,
+    seats: &mut [PokerSeat],
+    seat_no: i64,
+    command: TableCommand,
+) -> Result<(), String> {
+    let current_bet = table.current_bet;
+    let min_raise = table.min_raise;
+    let seat = seat_mut(seats, seat_no);
+    let to_call = current_bet - seat.bet;
+
+    let raise_to = match command {
+        TableCommand::Fold => {
+            seat.folded = true;
+            None
+        }
+        TableCommand::Call => {
+            put_chips(seat, to_call);
+            None
+        }
+        TableCommand::AllIn => Some(seat.bet + seat.stack),
+        TableCommand::Raise(amount) => {
+            let amount = amount.unwrap_or(current_bet + min_raise);
+            if amount > seat.bet + seat.stack {
+                return Err("Sul pole nii palju žetoone.".into());
+            }
+            if amount < current_bet + min_raise && amount < seat.bet + seat.stack {
+                return Err(format!(
+                    "Minimaalne tõstmine on {} peale.",
+                    current_bet + min_raise
+                ));
+            }
+            Some(amount)
+        }
+        _ => return Err("Seda käiku ei saa praegu teha.".into()),
+    };
+
+    if let Some(raise_to) = raise_to {
+        put_chips(seat, raise_to - seat.bet);
+        if raise_to > current_bet {
+            let is_full_raise = raise_to - current_bet >= min_raise;
+            table.current_bet = raise_to;
+            if is_full_raise {
+                table.min_raise = raise_to - current_bet;
+                for other in seats.iter_mut().filter(|s| s.seat != seat_no) {
+                    other.acted = false;
+                }
+            }
+        }
+    }
+
+    seat_mut(seats, seat_no).acted = true;
+
+    Ok(())
+}
+
+/// Moves the hand forward after an action of `from` until somebody has to
+/// act again, returns `true` when the hand is over.
+fn progress(table: &mut PokerTable, seats: &mut [PokerSeat], from: i64) -> bool {
+    let mut from = from;
+
+    loop {
+        if seats.iter().filter(|s| is_live(s)).count() < 2 {
+            return true;
+        }
+
+        let can_act = seats
+            .iter()
+            .filter(|s| is_live(s) && !s.all_in)
+            .collect::<Vec<_>>();
+        let nobody_to_bet_against =
+            can_act.len() < 2 && can_act.iter().all(|s| s.bet >= table.current_bet);
+
+        if !nobody_to_bet_against {
+            let current_bet = table.current_bet;
+            if let Some(next) = next_seat(seats, from, |s| needs_action(s, current_bet)) {
+                table.to_act = Some(next);
+                return false;
+            }
+        }
+
+        for seat in seats.iter_mut() {
+            seat.bet = 0;
+            seat.acted = false;
+        }
+        table.current_bet = 0;
+        table.min_raise = table.big_blind;
+        table.to_act = None;
+
+        let (street, count) = match table.street.as_str() {
+            STREET_PREFLOP => (STREET_FLOP, 3),
+            STREET_FLOP => (STREET_TURN, 1),
+            STREET_TURN => (STREET_RIVER, 1),
+            _ => return true,
+        };
+
+        let mut board = cards::decode_cards(&table.board);
+        board.extend(deal_cards(table, count));
+        table.board = cards::encode_cards(&board);
+        table.street = street.into();
+
+        from = table.dealer_seat;
+    }
+}
+
+/// Splits the pots between the winners and takes the rake, returns the rake.
+fn finish_hand(table: &mut PokerTable, seats: &mut [PokerSeat], settings: &Poker) -> i64 {
+    let board = cards::decode_cards(&table.board);
+    let live = seats
+        .iter()
+        .filter(|s| is_live(s))
+        .map(|s| s.seat)
+        .collect::<Vec<_>>();
+
+    let ranks = seats
+        .iter()
+        .filter(|s| is_live(s) && live.len() > 1)
+        .map(|s| {
+            let mut hand = cards::decode_cards(&s.hole_cards);
+            hand.extend(board.iter().copied());
+            (s.seat, cards::best_hand(&hand))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let mut levels = seats
+        .iter()
+        .map(|s| s.committed)
+        .filter(|&c| c > 0)
+        .collect::<Vec<_>>();
+    levels.sort();
+    levels.dedup();
+
+    // No flop, no drop.
+    let mut rake_left = if board.len() >= 3 {
+        settings.rake_cap
+    } else {
+        0
+    };
+    let mut total_rake = 0;
+    let mut winnings = BTreeMap::<i64, i64>::new();
+    let mut previous = 0;
+
+    for level in levels {
+        let pot = seats
+            .iter()
+            .map(|s| s.committed.min(level) - s.committed.min(previous))
+            .sum::<i64>();
+        previous = level;
+
+        let mut eligible = live
+            .iter()
+            .copied()
+            .filter(|&seat| seats.iter().any(|s| s.seat == seat && s.committed >= level))
+            .collect::<Vec<_>>();
+        if eligible.is_empty() {
+            eligible = live.clone();
+        }
+
+        let winners = match eligible.iter().filter_map(|seat| ranks.get(seat)).max() {
+            Some(best) => eligible
+                .iter()
+                .copied()
+                .filter(|seat| ranks.get(seat) == Some(best))
+                .collect::<Vec<_>>(),
+            None => eligible,
+        };
+
+        let rake = ((pot as f64 * settings.rake).floor() as i64).min(rake_left);
+        rake_left -= rake;
+        total_rake += rake;
+
+        let share = (pot - rake) / winners.len() as i64;
+        let remainder = (pot - rake) % winners.len() as i64;
+        for winner in winners.iter() {
+            *winnings.entry(*winner).or_default() += share;
+        }
+        // Odd chips go to the first winner left of the dealer.
+        if let Some(first) = next_seat(seats, table.dealer_seat, |s| winners.contains(&s.seat)) {
+            *winnings.entry(first).or_default() += remainder;
+        }
+    }
+
+    let mut lines = Vec::new();
+    for seat in seats.iter_mut() {
+        let won = winnings.get(&seat.seat).copied().unwrap_or(0);
+        seat.stack += won;
+
+        let mention = mention(&seat.discord_user_id);
+        match (ranks.get(&seat.seat), won > 0) {
+            (Some(rank), true) => lines.push(format!(
+                "{} {} - {}, võitis {} :potato:",
+                mention,
+                cards::display_cards(&cards::decode_cards(&seat.hole_cards)),
+                rank.category.name(),
+                won
+            )),
+            (Some(rank), false) => lines.push(format!(
+                "{} {} - {}",
+                mention,
+                cards::display_cards(&cards::decode_cards(&seat.hole_cards)),
+                rank.category.name()
+            )),
+            (None, true) => lines.push(format!("{} võitis {} :potato:", mention, won)),
+            (None, false) => {}
+        }
+    }
+
+    if total_rake > 0 {
+        lines.push(format!("Maja võttis {} :potato:", total_rake));
+    }
+
+    for seat in seats.iter_mut() {
+        seat.bet = 0;
+        seat.committed = 0;
+        seat.in_hand = false;
+        seat.folded = false;
+        seat.all_in = false;
+        seat.acted = false;
+    }
+
+    table.status = STATUS_WAITING.into();
+    table.to_act = None;
+    table.current_bet = 0;
+    table.last_result = Some(lines.join("\n"));
+
+    total_rake
+}
+
+fn mention(user_id: &str) -> String {
+    user_id
+        .parse::<u64>()
+        .map(|id| serenity::Mention::from(serenity::UserId::new(id)).to_string())
+        .unwrap_or_else(|_| user_id.to_string())
+}
+
+/// Runs a command on the table for the user and saves the result. The error
+/// is a message meant for the user.
+pub async fn execute(
+    data: &Data,
+    table_id: i64,
+    user_id: serenity::UserId,
+    command: TableCommand,
+) -> Result<TableUpdate, String> {
+    let database = &data.database;
+    let discord_user_id = user_id.to_string();
+    let now = data.clock.timestamp();
+
+    let Some(mut table) = find_poker_table(table_id, database).await else {
+        return Err("Sellist lauda pole.".into());
+    };
+
+    if table.status == STATUS_CLOSED {
+        return Err("See laud on suletud.".into());
+    }
+
+    let mut seats = load_poker_seats(table_id, database).await;
+    let my_seat = seats
+        .iter()
+        .find(|s| s.discord_user_id == discord_user_id)
+        .map(|s| s.seat);
+
+    let mut buyer = None;
+    let mut hand_started = false;
+    let mut hand_over = false;
+
+    match (command, my_seat) {
+        (TableCommand::Join, Some(_)) => return Err("Sa juba istud selle laua taga.".into()),
+        (TableCommand::Join, None) => {
+            let Some(free_seat) =
+                (0..data.poker.max_seats).find(|n| seats.iter().all(|s| s.seat != *n))
+            else {
+                return Err("Laud on täis.".into());
+            };
+
+            let player = match data.players.find_player(&discord_user_id).await {
+                Some(player) => Some(player),
+                None => data.players.create_player(&discord_user_id, now).await,
+            };
+            let Some(mut player) = player else {
+                return Err("Laud muutus vahepeal, proovi uuesti.".into());
+            };
+
+            if player.balance < table.buy_in {
+                return Err("Sul pole sisseostuks piisavalt :potato:.".into());
+            }
+
+            if let Some(reason) = limits::bet_refusal(
+                &discord_user_id,
+                table.buy_in,
+                data.clock.as_ref(),
+                database,
+            )
+            .await
+            {
+                return Err(reason);
+            }
+
+            player.balance -= table.buy_in;
+            player.idle_since_ts = now;
+            buyer = Some(player);
+
+            seats.push(PokerSeat {
+                seat: free_seat,
+                discord_user_id: discord_user_id.clone(),
+                stack: table.buy_in,
+                hole_cards: String::new(),
+                bet: 0,
+                committed: 0,
+                in_hand: false,
+                folded: false,
+                all_in: false,
+                acted: false,
+                leaving: false,
+            });
+            seats.sort_by_key(|s| s.seat);
+        }
+        (_, None) => return Err("Sa ei istu selle laua taga.".into()),
+        (TableCommand::Leave, Some(seat_no)) => {
+            let seat = seat_mut(&mut seats, seat_no);
+            seat.leaving = true;
+            if table.status == STATUS_PLAYING && is_live(seat) {
+                seat.folded = true;
+                let from = match table.to_act {
+                    Some(to_act) if to_act != seat_no => to_act - 1,
+                    _ => seat_no,
+                };
+                hand_over = progress(&mut table, &mut seats, from);
+            }
+        }
+        (TableCommand::Deal, Some(_)) => {
+            if table.status != STATUS_WAITING {
+                return Err("Käsi on juba käimas.".into());
+            }
+            let big_blind = start_hand(&mut table, &mut seats)?;
+            hand_started = true;
+            hand_over = progress(&mut table, &mut seats, big_blind);
+        }
+        (TableCommand::Cards, Some(_)) => return Err("Kaarte saab vaadata nupuga.".into()),
+        (TableCommand::Close, Some(_)) => {
+            if table.owner_id != discord_user_id {
+                return Err("Lauda saab sulgeda ainult selle avaja.".into());
+            }
+            if table.status != STATUS_WAITING {
+                return Err("Lauda ei saa käe ajal sulgeda.".into());
+            }
+            for seat in seats.iter_mut() {
+                seat.leaving = true;
+            }
+        }
+        (action, Some(seat_no)) => {
+            if table.status != STATUS_PLAYING {
+                return Err("Käsi pole käimas.".into());
+            }
+            if table.to_act != Some(seat_no) {
+                return Err("Pole sinu kord.".into());
+            }
+            apply_action(&mut table, &mut seats, seat_no, action)?;
+            hand_over = progress(&mut table, &mut seats, seat_no);
+        }
+    }
+
+    let rake = if hand_over {
+        finish_hand(&mut table, &mut seats, &data.poker)
+    } else {
+        0
+    };
+
+    // Players who left or went broke are cashed out once they are not in a hand.
+    let (cash_outs, remaining): (Vec<_>, Vec<_>) = seats
+        .into_iter()
+        .partition(|s| !s.in_hand && (s.leaving || s.stack == 0));
+    let seats = remaining;
+
+    if seats.is_empty() {
+        table.status = STATUS_CLOSED.into();
+    }
+
+    if !settle_poker_table(
+        &mut table,
+        &seats,
+        buyer.as_ref(),
+        &cash_outs,
+        rake,
+        data.clock.day(),
+        database,
+    )
+    .await
+    {
+        return Err("Laud muutus vahepeal, proovi uuesti.".into());
+    }
+
+    if buyer.is_some() {
+        metrics::bet("poker", table.buy_in);
+    }
+
+    for seat in cash_outs.iter().filter(|s| s.stack > 0) {
+        info!(
+            "Cashed out {} potatoes from poker table {} to user {}",
+            seat.stack, table.id, seat.discord_user_id
+        );
+        metrics::payout("poker", seat.stack);
+    }
+
+    Ok(TableUpdate {
+        table,
+        seats,
+        hand_started,
+    })
+}
+
+pub fn table_embed(table: &PokerTable, seats: &[PokerSeat]) -> serenity::CreateEmbed {
+    let pot = seats.iter().map(|s| s.committed).sum::<i64>();
+    let board = cards::decode_cards(&table.board);
+
+    let seat_lines = seats
+        .iter()
+        .map(|s| {
+            let mut line = format!(
+                "{}{} - {} :potato:",
+                if table.dealer_seat == s.seat && table.hand_no > 0 {
+                    "(D) "
+                } else {
+                    ""
+                },
+                mention(&s.discord_user_id),
+                s.stack
+            );
+            if s.bet > 0 {
+                line.push_str(&format!(", panus {}", s.bet));
+            }
+            if s.folded {
+                line.push_str(" [fold]");
+            } else if s.all_in {
+                line.push_str(" [all-in]");
+            }
+            if s.leaving {
+                line.push_str(" [lahkub]");
+            }
+            if table.to_act == Some(s.seat) {
+                line.push_str(" :arrow_left:");
+            }
+            line
+        })
+        .collect::<Vec<_>>();
+
+    let mut embed = serenity::CreateEmbed::new()
+        .title(format!(":black_joker: Pokkerilaud #{}", table.id))
+        .description(format!(
+            "Sisseost {} :potato:, blindid {}/{}",
+            table.buy_in, table.small_blind, table.big_blind
+        ))
+        .field(
+            "Mängijad",
+            if seat_lines.is_empty() {
+                "Laud on tühi.".to_string()
+            } else {
+                seat_lines.join("\n")
+            },
+            false,
+        );
+
+    if table.status == STATUS_PLAYING {
+        embed = embed
+            .field(
+                "Laud",
+                if board.is_empty() {
+                    "-".to_string()
+                } else {
+                    cards::display_cards(&board)
+                },
+                true,
+            )
+            .field("Pott", format!("{} :potato:", pot), true);
+    }
+
+    if let Some(last_result) = &table.last_result {
+        let title = if board.is_empty() {
+            "Eelmine käsi".to_string()
+        } else {
+            format!("Eelmine käsi - {}", cards::display_cards(&board))
+        };
+        embed = embed.field(title, last_result, false);
+    }
+
+    let color = match table.status.as_str() {
+        STATUS_PLAYING => serenity::Color::DARK_GREEN,
+        STATUS_CLOSED => serenity::Color::RED,
+        _ => serenity::Color::GOLD,
+    };
+
+    embed.color(color)
+}
+
+fn button(table: &PokerTable, command: TableCommand, label: String) -> serenity::CreateButton {
+    serenity::CreateButton::new(format!("{}{}:{}", BUTTON_PREFIX, table.id, command.key()))
+        .label(label)
+}
+
+pub fn table_components(table: &PokerTable, seats: &[PokerSeat]) -> Vec<serenity::CreateActionRow> {
+    let join = button(table, TableCommand::Join, "Istu".into());
+    let leave =
+        button(table, TableCommand::Leave, "Lahku".into()).style(serenity::ButtonStyle::Danger);
+
+    match table.status.as_str() {
+        STATUS_WAITING => vec![serenity::CreateActionRow::Buttons(vec![
+            join,
+            button(table, TableCommand::Deal, "Jaga".into()).style(serenity::ButtonStyle::Success),
+            leave,
+        ])],
+        STATUS_PLAYING => {
+            let to_call = table
+                .to_act
+                .and_then(|to_act| seats.iter().find(|s| s.seat == to_act))
+                .map(|s| (table.current_bet - s.bet).min(s.stack))
+                .unwrap_or(0);
+            let call_label = if to_call > 0 {
+                format!("Maksa {}", to_call)
+            } else {
+                "Check".to_string()
+            };
+            vec![
+                serenity::CreateActionRow::Buttons(vec![
+                    button(table, TableCommand::Fold, "Fold".into())
+                        .style(serenity::ButtonStyle::Danger),
+                    button(table, TableCommand::Call, call_label)
+                        .style(serenity::ButtonStyle::Primary),
+                    button(
+                        table,
+                        TableCommand::Raise(None),
+                        format!("Tõsta {}", table.current_bet + table.min_raise),
+                    )
+                    .style(serenity::ButtonStyle::Primary),
+                    button(table, TableCommand::AllIn, "All-in".into())
+                        .style(serenity::ButtonStyle::Primary),
+                ]),
+                serenity::CreateActionRow::Buttons(vec![
+                    button(table, TableCommand::Cards, "Minu kaardid".into())
+                        .style(serenity::ButtonStyle::Secondary),
+                    join,
+                    leave,
+                ]),
+            ]
+        }
+        _ => vec![],
+    }
+}
+
+/// Sends hole cards to every player of a freshly dealt hand, players with
+/// closed DMs can still look at them with the button.
+pub async fn send_hole_cards(ctx: &serenity::Context, table: &PokerTable, seats: &[PokerSeat]) {
+    for seat in seats.iter().filter(|s| s.in_hand) {
+        let Ok(user_id) = seat.discord_user_id.parse::<u64>() else {
+            continue;
+        };
+        let message = serenity::CreateMessage::new().content(format!(
+            "Pokkerilaud #{}, käsi {}: {}",
+            table.id,
+            table.hand_no,
+            cards::display_cards(&cards::decode_cards(&seat.hole_cards))
+        ));
+        if let Err(why) = serenity::UserId::new(user_id)
+            .direct_message(ctx, message)
+            .await
+        {
+            warn!("Could not send hole cards to user {}: {why:?}", user_id);
+        }
+    }
+}
+
+pub async fn update_table_message(
+    ctx: &serenity::Context,
+    table: &PokerTable,
+    seats: &[PokerSeat],
+) {
+    let (Ok(channel_id), Some(Ok(message_id))) = (
+        table.channel_id.parse::<u64>(),
+        table.message_id.as_ref().map(|id| id.parse::<u64>()),
+    ) else {
+        return;
+    };
+
+    let edit = serenity::EditMessage::new()
+        .embed(table_embed(table, seats))
+        .components(table_components(table, seats));
+
+    if let Err(why) = serenity::ChannelId::new(channel_id)
+        .edit_message(ctx, serenity::MessageId::new(message_id), edit)
+        .await
+    {
+        error!("Error editing message: {why:?}");
+    }
+}
+
+/// Handles button presses on poker table messages. Buttons are routed through
+/// the event handler so tables keep working after the bot restarts.
+pub async fn handle_interaction(
+    ctx: &serenity::Context,
+    data: &Data,
+    interaction: &serenity::ComponentInteraction,
+) {
+    let Some((table_id, command)) = interaction
+        .data
+        .custom_id
+        .strip_prefix(BUTTON_PREFIX)
+        .and_then(|rest| rest.split_once(':'))
+        .and_then(|(id, key)| Some((id.parse::<i64>().ok()?, TableCommand::from_key(key)?)))
+    else {
+        return;
+    };
+
+    let response = if command == TableCommand::Cards {
+        let seats = load_poker_seats(table_id, &data.database).await;
+        let content = match seats
+            .iter()
+            .find(|s| s.discord_user_id == interaction.user.id.to_string() && s.in_hand)
+        {
+            Some(seat) => format!(
+                "Sinu kaardid: {}",
+                cards::display_cards(&cards::decode_cards(&seat.hole_cards))
+            ),
+            None => "Sa ei osale praeguses käes.".to_string(),
+        };
+        ephemeral(content)
+    } else {
+        match execute(data, table_id, interaction.user.id, command).await {
+            Ok(update) => {
+                if update.hand_started {
+                    send_hole_cards(ctx, &update.table, &update.seats).await;
+                }
+                serenity::CreateInteractionResponse::UpdateMessage(
+                    serenity::CreateInteractionResponseMessage::new()
+                        .embed(table_embed(&update.table, &update.seats))
+                        .components(table_components(&update.table, &update.seats)),
+                )
+            }
+            Err(message) => ephemeral(message),
+        }
+    };
+
+    if let Err(why) = interaction.create_response(ctx, response).await {
+        error!("Error responding to interaction: {why:?}");
+    }
+}
+
+fn ephemeral(content: impl Into<String>) -> serenity::CreateInteractionResponse {
+    serenity::CreateInteractionResponse::Message(
+        serenity::CreateInteractionResponseMessage::new()
+            .content(content)
+            .ephemeral(true),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn river(board: &str) -> PokerTable {
+        PokerTable {
+            id: 1,
+            channel_id: "1".into(),
+            message_id: None,
+            owner_id: "1".into(),
+            buy_in: 1000,
+            small_blind: 10,
+            big_blind: 20,
+            status: STATUS_PLAYING.into(),
+            hand_no: 1,
+            street: STREET_RIVER.into(),
+            dealer_seat: 1,
+            to_act: None,
+            current_bet: 0,
+            min_raise: 20,
+            deck: String::new(),
+            board: board.into(),
+            last_result: None,
+            version: 1,
+        }
+    }
+
+    fn seat(seat: i64, hole_cards: &str, committed: i64, stack: i64) -> PokerSeat {
+        PokerSeat {
+            seat,
+            discord_user_id: seat.to_string(),
+            stack,
+            hole_cards: hole_cards.into(),
+            bet: 0,
+            committed,
+            in_hand: true,
+            folded: false,
+            all_in: stack == 0,
+            acted: true,
+            leaving: false,
+        }
+    }
+
+    fn no_rake() -> Poker {
+        Poker {
+            max_seats: 8,
+            rake: 0.0,
+            rake_cap: 0,
+        }
+    }
+
+    fn stacks(seats: &[PokerSeat]) -> Vec<i64> {
+        seats.iter().map(|seat| seat.stack).collect()
+    }
+
+    #[test]
+    fn tied_hands_split_the_pot() {
+        let mut table = river("As Kd Qc Jh Ts");
+        let mut seats = vec![
+            seat(1, "2c 3d", 100, 0),
+            seat(2, "4c 5d", 100, 0),
+            seat(3, "6c 7d", 101, 0),
+        ];
+
+        let rake = finish_hand(&mut table, &mut seats, &no_rake());
+
+        assert_eq!(rake, 0);
+        // 300 split three ways, the extra chip only seat 3 could win.
+        assert_eq!(stacks(&seats), vec![100, 100, 101]);
+    }
+
+    #[test]
+    fn odd_chip_goes_left_of_the_dealer() {
+        let mut table = river("As Kd Qc Jh Ts");
+        let mut seats = vec![
+            seat(1, "2c 3d", 50, 0),
+            seat(2, "4c 5d", 50, 0),
+            seat(3, "6c 7d", 1, 0),
+        ];
+        seats[2].folded = true;
+
+        finish_hand(&mut table, &mut seats, &no_rake());
+
+        assert_eq!(stacks(&seats), vec![50, 51, 0]);
+    }
+
+    #[test]
+    fn all_in_player_only_wins_the_main_pot() {
+        let mut table = river("Ah Kd 7c 4h 2s");
+        let mut seats = vec![
+            // Best hand but all in for 100.
+            seat(1, "As Ac", 100, 0),
+            // Second best, all in for 300.
+            seat(2, "Ks Kc", 300, 0),
+            // Worst, covers everybody.
+            seat(3, "Qs Qc", 500, 200),
+        ];
+
+        finish_hand(&mut table, &mut seats, &no_rake());
+
+        // Main pot 300 to seat 1, side pot 400 to seat 2 and the uncalled
+        // 200 back to seat 3.
+        assert_eq!(stacks(&seats), vec![300, 400, 400]);
+        assert!(seats
+            .iter()
+            .all(|seat| seat.committed == 0 && !seat.in_hand));
+        assert_eq!(table.status, STATUS_WAITING);
+    }
+
+    #[test]
+    fn rake_is_capped_and_needs_a_flop() {
+        let settings = Poker {
+            max_seats: 8,
+            rake: 0.1,
+            rake_cap: 15,
+        };
+
+        let mut table = river("Ah Kd 7c 4h 2s");
+        let mut seats = vec![seat(1, "As Ac", 100, 0), seat(2, "Ks Kc", 100, 0)];
+        assert_eq!(finish_hand(&mut table, &mut seats, &settings), 15);
+        assert_eq!(stacks(&seats), vec![185, 0]);
+
+        let mut table = river("");
+        let mut seats = vec![seat(1, "As Ac", 20, 0), seat(2, "Ks Kc", 10, 0)];
+        seats[1].folded = true;
+        assert_eq!(finish_hand(&mut table, &mut seats, &settings), 0);
+        assert_eq!(stacks(&seats), vec![30, 0]);
+    }
+}
@@ -0,0 +1,471 @@
+use poise::serenity_prelude as serenity;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use sqlx::{Pool, Sqlite};
+use std::sync::Arc;
+use tracing::{error, info, warn};
+
+use crate::database::events::TARGET_RACE;
+use crate::database::races::{
+    cancel_race, find_unfinished_races, finish_race, load_race_bets, load_racers, update_race,
+    update_racer, Race, RaceBet, Racer, STATUS_CANCELLED, STATUS_FINISHED, STATUS_RUNNING,
+};
+use crate::internal::clock::Clock;
+use crate::internal::data::Data;
+use crate::internal::events;
+use crate::internal::jackpot::JackpotRoller;
+use crate::internal::metrics;
+use crate::internal::settings;
+
+/// Estonian potato varieties the racers are named after.
+const RACER_NAMES: [&str; 10] = [
+    "Ando",
+    "Anti",
+    "Piret",
+    "Maret",
+    "Sarme",
+    "Juku",
+    "Reet",
+    "Teele",
+    "Jõgeva",
+    "Väärikas",
+];
+
+const TRACK_WIDTH: i64 = 20;
+
+/// Lines up the racers with random names and strengths.
+pub fn new_racers(count: usize) -> Vec<Racer> {
+    let mut rng = rand::rng();
+    let mut names = RACER_NAMES.to_vec();
+    names.shuffle(&mut rng);
+
+    names
+        .into_iter()
+        .take(count.clamp(2, RACER_NAMES.len()))
+        .enumerate()
+        .map(|(index, name)| Racer {
+            lane: index as i64 + 1,
+            name: name.into(),
+            strength: rng.random_range(2..=5),
+            distance: 0,
+            finish_position: None,
+        })
+        .collect()
+}
+
+/// Moves every racer forward, stronger racers get an extra step more often.
+/// Returns `true` once somebody has crossed the finish line.
+fn step(racers: &mut [Racer], track_length: i64) -> bool {
+    let mut rng = rand::rng();
+
+    for racer in racers.iter_mut() {
+        let bonus = if rng.random_range(0..10) < racer.strength {
+            1
+        } else {
+            0
+        };
+        racer.distance = (racer.distance + rng.random_range(1..=3) + bonus).min(track_length);
+    }
+
+    racers.iter().any(|racer| racer.distance >= track_length)
+}
+
+/// Orders the racers by distance, racers crossing the line on the same step
+/// are ordered randomly.
+fn assign_positions(racers: &mut [Racer]) {
+    let mut rng = rand::rng();
+    let mut order = racers
+        .iter()
+        .enumerate()
+        .map(|(index, racer)| (racer.distance, rng.random::<u32>(), index))
+        .collect::<Vec<_>>();
+    order.sort_by(|a, b| b.cmp(a));
+
+    for (position, (_, _, index)) in order.into_iter().enumerate() {
+        racers[index].finish_position = Some(position as i64 + 1);
+    }
+}
+
+fn lane_pool(bets: &[RaceBet], lane: i64) -> i64 {
+    bets.iter()
+        .filter(|bet| bet.lane == lane)
+        .map(|bet| bet.amount)
+        .sum()
+}
+
+/// Current pari-mutuel odds of the racer, `None` while nobody has bet on it.
+fn odds(bets: &[RaceBet], lane: i64, house_take: f64) -> Option<f64> {
+    let pool = bets.iter().map(|bet| bet.amount).sum::<i64>();
+    let lane_pool = lane_pool(bets, lane);
+
+    if lane_pool == 0 {
+        return None;
+    }
+
+    Some(pool as f64 * (1.0 - house_take) / lane_pool as f64)
+}
+
+/// Splits the pool minus the house take between the bets on the winner in
+/// proportion to their size, the house adds the `boost` of running events on
+/// top. Everybody is refunded when nobody bet on the winner. Returns the bets
+/// with payouts and the amount kept by the house, negative when the boost
+/// costs more than the take.
+fn settle(bets: &[RaceBet], winner_lane: i64, house_take: f64, boost: f64) -> (Vec<RaceBet>, i64) {
+    let pool = bets.iter().map(|bet| bet.amount).sum::<i64>();
+    let winner_pool = lane_pool(bets, winner_lane);
+
+    if winner_pool == 0 {
+        let refunded = bets
+            .iter()
+            .map(|bet| RaceBet {
+                payout: Some(bet.amount),
+                ..bet.clone()
+            })
+            .collect();
+        return (refunded, 0);
+    }
+
+    let distributable = pool - (pool as f64 * house_take).floor() as i64;
+
+    let settled = bets
+        .iter()
+        .map(|bet| {
+            let payout = if bet.lane == winner_lane {
+                let share =
+                    (distributable as i128 * bet.amount as i128 / winner_pool as i128) as i64;
+                events::multiply(share, boost)
+            } else {
+                0
+            };
+            RaceBet {
+                payout: Some(payout),
+                ..bet.clone()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let paid = settled
+        .iter()
+        .map(|bet| bet.payout.unwrap_or_default())
+        .sum::<i64>();
+
+    (settled, pool - paid)
+}
+
+fn mention(user_id: &str) -> String {
+    user_id
+        .parse::<u64>()
+        .map(|id| serenity::Mention::from(serenity::UserId::new(id)).to_string())
+        .unwrap_or_else(|_| user_id.to_string())
+}
+
+fn describe_racers(racers: &[Racer], bets: &[RaceBet], settings: &settings::Race) -> String {
+    racers
+        .iter()
+        .map(|racer| {
+            let odds = odds(bets, racer.lane, settings.house_take)
+                .map_or_else(|| "-".to_string(), |odds| format!("{:.2}x", odds));
+            format!(
+                "`{}` {} {} - koefitsient {} ({} :potato:)",
+                racer.lane,
+                racer.name,
+                ":star:".repeat(racer.strength as usize),
+                odds,
+                lane_pool(bets, racer.lane)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn describe_track(racers: &[Racer], track_length: i64) -> String {
+    racers
+        .iter()
+        .map(|racer| {
+            let position = (racer.distance * TRACK_WIDTH / track_length.max(1)).min(TRACK_WIDTH);
+            format!(
+                "`{}` :checkered_flag:{}:potato:{} {}",
+                racer.lane,
+                "·".repeat((TRACK_WIDTH - position) as usize),
+                "·".repeat(position as usize),
+                racer.name
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn footer(race: &Race) -> serenity::CreateEmbedFooter {
+    serenity::CreateEmbedFooter::new(format!("Võistlus #{}", race.id))
+}
+
+pub fn betting_embed(
+    race: &Race,
+    racers: &[Racer],
+    bets: &[RaceBet],
+    settings: &settings::Race,
+) -> serenity::CreateEmbed {
+    serenity::CreateEmbed::new()
+        .title(":potato: Kartulite võidujooks")
+        .description(format!(
+            "Panuseid saab teha {} sekundit käsuga `!race bet <rada> <panus>`.",
+            settings.betting_time.as_secs()
+        ))
+        .field("Jooksjad", describe_racers(racers, bets, settings), false)
+        .field(
+            "Pank",
+            format!(
+                "{} :potato:",
+                bets.iter().map(|bet| bet.amount).sum::<i64>()
+            ),
+            false,
+        )
+        .footer(footer(race))
+        .color(serenity::Color::GOLD)
+}
+
+fn running_embed(
+    race: &Race,
+    racers: &[Racer],
+    settings: &settings::Race,
+) -> serenity::CreateEmbed {
+    serenity::CreateEmbed::new()
+        .title(":potato: Võidujooks käib!")
+        .description(describe_track(racers, settings.track_length))
+        .field("Pank", format!("{} :potato:", race.pool), false)
+        .footer(footer(race))
+        .color(serenity::Color::DARK_GREEN)
+}
+
+fn finished_embed(
+    race: &Race,
+    racers: &[Racer],
+    bets: &[RaceBet],
+    settings: &settings::Race,
+) -> serenity::CreateEmbed {
+    let winner = racers
+        .iter()
+        .find(|racer| Some(racer.lane) == race.winner_lane)
+        .map_or_else(|| "?".to_string(), |racer| racer.name.clone());
+
+    let winners = bets
+        .iter()
+        .filter(|bet| Some(bet.lane) == race.winner_lane)
+        .map(|bet| {
+            format!(
+                "{} {} :potato: → {} :potato:",
+                mention(&bet.discord_user_id),
+                bet.amount,
+                bet.payout.unwrap_or_default()
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let winners = if winners.is_empty() {
+        "Keegi ei panustanud võitjale, kõik panused tagastati.".to_string()
+    } else {
+        winners.join("\n")
+    };
+
+    serenity::CreateEmbed::new()
+        .title(format!(":checkered_flag: {} võitis!", winner))
+        .description(describe_track(racers, settings.track_length))
+        .field("Võitjad", winners, false)
+        .field(
+            "Pank",
+            format!(
+                "{} :potato: (maja osa {} :potato:)",
+                race.pool, race.house_take
+            ),
+            false,
+        )
+        .footer(footer(race))
+        .color(serenity::Color::BLUE)
+}
+
+fn cancelled_embed(race: &Race, description: &str) -> serenity::CreateEmbed {
+    serenity::CreateEmbed::new()
+        .title(":potato: Kartulite võidujooks")
+        .description(description)
+        .footer(footer(race))
+        .color(serenity::Color::RED)
+}
+
+/// What a race keeps using after the command that started it has returned.
+pub struct RaceHandles {
+    pub database: Pool<Sqlite>,
+    pub clock: Arc<dyn Clock>,
+    pub jackpot: JackpotRoller,
+}
+
+impl RaceHandles {
+    pub fn from_data(data: &Data) -> Self {
+        RaceHandles {
+            database: data.database.clone(),
+            clock: data.clock.clone(),
+            jackpot: JackpotRoller::from_data(data),
+        }
+    }
+}
+
+/// Runs the race: waits for the betting time, moves the racers step by step
+/// until one of them crosses the finish line and pays out the pool.
+pub async fn run_race(
+    ctx: serenity::Context,
+    handles: RaceHandles,
+    settings: settings::Race,
+    mut race: Race,
+    mut message: serenity::Message,
+) {
+    let RaceHandles {
+        database,
+        clock,
+        jackpot,
+    } = handles;
+
+    tokio::time::sleep(settings.betting_time).await;
+
+    // Closing the betting first makes sure no bet sneaks in after the pool is counted.
+    race.status = STATUS_RUNNING.into();
+    if !update_race(&race, &database).await {
+        error!("Could not start race {}", race.id);
+        return;
+    }
+
+    let bets = load_race_bets(race.id, &database).await;
+
+    if bets.is_empty() {
+        race.status = STATUS_CANCELLED.into();
+        race.finished_ts = Some(clock.timestamp());
+        if !update_race(&race, &database).await {
+            warn!("Could not cancel race {}", race.id);
+        }
+        let edit = serenity::EditMessage::new()
+            .embed(cancelled_embed(&race, "Keegi ei julgenud panust teha."));
+        if let Err(why) = message.edit(&ctx, edit).await {
+            error!("Error editing message: {why:?}");
+        }
+        return;
+    }
+
+    race.pool = bets.iter().map(|bet| bet.amount).sum();
+    if !update_race(&race, &database).await {
+        warn!("Could not save pool of race {}", race.id);
+    }
+
+    info!("Race {} started with pool of {}", race.id, race.pool);
+
+    let mut racers = load_racers(race.id, &database).await;
+    let mut interval_timer = tokio::time::interval(settings.step_interval);
+
+    loop {
+        interval_timer.tick().await;
+
+        let finished = step(&mut racers, settings.track_length);
+        if finished {
+            break;
+        }
+
+        let edit = serenity::EditMessage::new().embed(running_embed(&race, &racers, &settings));
+        if let Err(why) = message.edit(&ctx, edit).await {
+            error!("Error editing message: {why:?}");
+        }
+    }
+
+    assign_positions(&mut racers);
+
+    for racer in racers.iter() {
+        if !update_racer(race.id, racer, &database).await {
+            warn!("Could not save racer {} of race {}", racer.lane, race.id);
+        }
+    }
+
+    race.winner_lane = racers
+        .iter()
+        .find(|racer| racer.finish_position == Some(1))
+        .map(|racer| racer.lane);
+
+    let now = clock.timestamp();
+    let boost = events::multiplier(TARGET_RACE, now, &database).await;
+    let (bets, house_take) = settle(
+        &bets,
+        race.winner_lane.unwrap_or_default(),
+        settings.house_take,
+        boost,
+    );
+
+    race.house_take = house_take;
+    race.status = STATUS_FINISHED.into();
+    race.finished_ts = Some(clock.timestamp());
+    if !finish_race(&race, &bets, clock.day(), &database).await {
+        error!("Could not finish race {}", race.id);
+        return;
+    }
+
+    for bet in bets.iter() {
+        metrics::payout("race", bet.payout.unwrap_or_default());
+    }
+
+    for bet in bets.iter() {
+        let lost = if bet.payout == Some(0) { bet.amount } else { 0 };
+        jackpot
+            .settle_in(
+                &ctx.http,
+                &race.channel_id,
+                &bet.discord_user_id,
+                lost,
+                "race",
+            )
+            .await;
+    }
+
+    info!("Race {} won by lane {:?}", race.id, race.winner_lane);
+
+    let edit = serenity::EditMessage::new().embed(finished_embed(&race, &racers, &bets, &settings));
+    if let Err(why) = message.edit(&ctx, edit).await {
+        error!("Error editing message: {why:?}");
+    }
+}
+
+/// Cancels races interrupted by a restart and refunds the bets that were not
+/// paid out yet.
+pub async fn recover_races(ctx: &serenity::Context, database: &Pool<Sqlite>, clock: &dyn Clock) {
+    for mut race in find_unfinished_races(database).await {
+        warn!("Recovering race {} left in status {}", race.id, race.status);
+
+        let refunds: i64 = load_race_bets(race.id, database)
+            .await
+            .iter()
+            .filter(|bet| bet.payout.is_none())
+            .map(|bet| bet.amount)
+            .sum();
+
+        race.status = STATUS_CANCELLED.into();
+        race.finished_ts = Some(clock.timestamp());
+        if !cancel_race(&race, clock.day(), database).await {
+            error!("Could not cancel race {}", race.id);
+            continue;
+        }
+
+        metrics::payout("race", refunds);
+
+        let (Ok(channel_id), Some(Ok(message_id))) = (
+            race.channel_id.parse::<u64>(),
+            race.message_id.as_ref().map(|id| id.parse::<u64>()),
+        ) else {
+            continue;
+        };
+
+        let edit = serenity::EditMessage::new().embed(cancelled_embed(
+            &race,
+            "Võistlus katkes, panused tagastati.",
+        ));
+
+        if let Err(why) = serenity::ChannelId::new(channel_id)
+            .edit_message(ctx, serenity::MessageId::new(message_id), edit)
+            .await
+        {
+            error!("Error editing message: {why:?}");
+        }
+    }
+}
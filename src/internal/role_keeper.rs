@@ -0,0 +1,91 @@
+use poise::serenity_prelude as serenity;
+use sqlx::{Pool, Sqlite};
+use std::sync::{Arc, Mutex};
+use tracing::{error, info, instrument, warn};
+
+use crate::database::roles::{find_expired_role_purchases, remove_role_purchase};
+use crate::internal::clock::Clock;
+
+#[derive(Debug)]
+pub struct RoleKeeper {
+    database: Pool<Sqlite>,
+    clock: Arc<dyn Clock>,
+    is_running: Mutex<bool>,
+}
+
+impl RoleKeeper {
+    pub fn new(database: Pool<Sqlite>, clock: Arc<dyn Clock>) -> Self {
+        RoleKeeper {
+            database,
+            clock,
+            is_running: Mutex::new(false),
+        }
+    }
+
+    #[instrument]
+    pub fn start(&self, ctx: serenity::Context) {
+        let mut is_running = self.is_running.lock().unwrap();
+        if *is_running {
+            return;
+        }
+
+        *is_running = true;
+
+        let database = self.database.clone();
+        let clock = self.clock.clone();
+
+        tokio::spawn(async move {
+            let mut interval_timer = tokio::time::interval(duration_str::parse("1m").unwrap());
+
+            loop {
+                interval_timer.tick().await;
+
+                remove_expired_roles(&ctx, &database, clock.timestamp()).await;
+            }
+        });
+    }
+}
+
+async fn remove_expired_roles(ctx: &serenity::Context, database: &Pool<Sqlite>, now: i64) {
+    for purchase in find_expired_role_purchases(now, database).await {
+        let (Ok(guild_id), Ok(role_id), Ok(user_id)) = (
+            purchase.guild_id.parse::<u64>(),
+            purchase.role_id.parse::<u64>(),
+            purchase.discord_user_id.parse::<u64>(),
+        ) else {
+            warn!("Dropping malformed role purchase {}", purchase.id);
+            remove_role_purchase(&purchase, database).await;
+            continue;
+        };
+
+        info!(
+            "Removing expired role {} from user {} ...",
+            purchase.role_id, purchase.discord_user_id
+        );
+
+        let result = ctx
+            .http
+            .remove_member_role(
+                serenity::GuildId::new(guild_id),
+                serenity::UserId::new(user_id),
+                serenity::RoleId::new(role_id),
+                Some("Purchased role expired"),
+            )
+            .await;
+
+        match result {
+            Ok(()) => {}
+            // Member has left or the role was deleted, nothing left to take away.
+            Err(serenity::Error::Http(serenity::HttpError::UnsuccessfulRequest(response)))
+                if response.status_code.as_u16() == 404 => {}
+            Err(why) => {
+                error!("Could not remove role {}: {why:?}", purchase.role_id);
+                continue;
+            }
+        }
+
+        if !remove_role_purchase(&purchase, database).await {
+            warn!("Could not remove role purchase {}", purchase.id);
+        }
+    }
+}
@@ -1,12 +1,26 @@
+use chrono_tz::Tz;
 use config::{Config, ConfigError, File};
-use duration_str::deserialize_duration;
+use duration_str::{deserialize_duration, deserialize_option_duration};
 use serde::Deserialize;
-use std::{env, time::Duration};
+use std::{collections::HashMap, env, time::Duration};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    #[default]
+    Sqlite,
+    Postgres,
+}
 
 #[allow(unused)]
 #[derive(Debug, Deserialize)]
 pub struct Database {
+    /// Where the players are kept, the game tables are always in SQLite.
+    #[serde(default)]
+    pub backend: Backend,
     pub filename: String,
+    /// Connection url of the PostgreSQL database.
+    pub url: Option<String>,
 }
 
 #[allow(unused)]
@@ -28,6 +42,209 @@ pub struct PotatoFeeder {
     pub zero_points_emoji: String,
 }
 
+#[allow(unused)]
+#[derive(Clone, Debug, Deserialize)]
+pub struct Crash {
+    #[serde(alias = "betting-time", deserialize_with = "deserialize_duration")]
+    pub betting_time: Duration,
+    #[serde(alias = "tick-interval", deserialize_with = "deserialize_duration")]
+    pub tick_interval: Duration,
+    #[serde(alias = "growth-rate")]
+    pub growth_rate: f64,
+    #[serde(alias = "house-edge")]
+    pub house_edge: f64,
+}
+
+#[allow(unused)]
+#[derive(Clone, Debug, Deserialize)]
+pub struct Dice {
+    #[serde(alias = "house-edge")]
+    pub house_edge: f64,
+}
+
+#[allow(unused)]
+#[derive(Clone, Debug, Deserialize)]
+pub struct Poker {
+    #[serde(alias = "max-seats")]
+    pub max_seats: i64,
+    pub rake: f64,
+    #[serde(alias = "rake-cap")]
+    pub rake_cap: i64,
+}
+
+#[allow(unused)]
+#[derive(Clone, Debug, Deserialize)]
+pub struct Race {
+    #[serde(alias = "betting-time", deserialize_with = "deserialize_duration")]
+    pub betting_time: Duration,
+    #[serde(alias = "step-interval", deserialize_with = "deserialize_duration")]
+    pub step_interval: Duration,
+    pub racers: usize,
+    #[serde(alias = "track-length")]
+    pub track_length: i64,
+    #[serde(alias = "house-take")]
+    pub house_take: f64,
+}
+
+#[allow(unused)]
+#[derive(Clone, Debug, Deserialize)]
+pub struct Markets {
+    #[serde(alias = "house-take")]
+    pub house_take: f64,
+}
+
+#[allow(unused)]
+#[derive(Clone, Debug, Deserialize)]
+pub struct Mines {
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub timeout: Duration,
+    #[serde(alias = "house-edge")]
+    pub house_edge: f64,
+}
+
+#[allow(unused)]
+#[derive(Clone, Debug, Deserialize)]
+pub struct Jackpot {
+    pub contribution: f64,
+    #[serde(alias = "win-chance")]
+    pub win_chance: f64,
+}
+
+#[allow(unused)]
+#[derive(Clone, Debug, Deserialize)]
+pub struct House {
+    #[serde(alias = "max-payout-ratio")]
+    pub max_payout_ratio: f64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FeeDestination {
+    House,
+    Charity,
+}
+
+#[allow(unused)]
+#[derive(Clone, Debug, Deserialize)]
+pub struct Give {
+    pub fee: f64,
+    #[serde(alias = "fee-to")]
+    pub fee_to: FeeDestination,
+    /// Most a player can give away in a day, `0` for no limit.
+    #[serde(alias = "daily-limit")]
+    pub daily_limit: i64,
+    /// Most a player can receive in a day, `0` for no limit.
+    #[serde(alias = "recipient-daily-limit")]
+    pub recipient_daily_limit: i64,
+    #[serde(alias = "min-receiver-age", deserialize_with = "deserialize_duration")]
+    pub min_receiver_age: Duration,
+    #[serde(alias = "report-window", deserialize_with = "deserialize_duration")]
+    pub report_window: Duration,
+}
+
+#[allow(unused)]
+#[derive(Clone, Debug, Deserialize)]
+pub struct Limits {
+    /// How long players have to wait before loosening their own limits.
+    #[serde(alias = "change-delay", deserialize_with = "deserialize_duration")]
+    pub change_delay: Duration,
+    #[serde(
+        alias = "max-self-exclusion",
+        deserialize_with = "deserialize_duration"
+    )]
+    pub max_self_exclusion: Duration,
+}
+
+#[allow(unused)]
+#[derive(Clone, Debug, Deserialize)]
+pub struct Backups {
+    pub directory: String,
+    /// Time between the scheduled backups.
+    #[serde(deserialize_with = "deserialize_duration")]
+    pub interval: Duration,
+    /// Days to keep the newest backup of.
+    #[serde(alias = "keep-daily")]
+    pub keep_daily: usize,
+    /// Weeks to keep the newest backup of, on top of the daily ones.
+    #[serde(alias = "keep-weekly")]
+    pub keep_weekly: usize,
+}
+
+#[allow(unused)]
+#[derive(Clone, Debug, Deserialize)]
+pub struct Metrics {
+    pub enabled: bool,
+    /// Address of the HTTP server serving `/metrics`.
+    pub listen: String,
+}
+
+#[allow(unused)]
+#[derive(Clone, Debug, Deserialize)]
+pub struct Health {
+    pub enabled: bool,
+    /// Address of the HTTP server serving `/healthz` and `/readyz`, shared
+    /// with the metrics when it is the same.
+    pub listen: String,
+    /// How long the feeder can go without a successful run before the bot is
+    /// no longer ready.
+    #[serde(alias = "feeder-max-age", deserialize_with = "deserialize_duration")]
+    pub feeder_max_age: Duration,
+}
+
+#[allow(unused)]
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Time {
+    /// IANA name of the time zone the weekly schedules are kept in, the
+    /// system time zone when not set.
+    #[serde(default)]
+    pub zone: Option<Tz>,
+}
+
+impl Time {
+    pub fn timezone(&self) -> Tz {
+        self.zone.unwrap_or_else(|| {
+            iana_time_zone::get_timezone()
+                .ok()
+                .and_then(|name| name.parse().ok())
+                .unwrap_or(Tz::UTC)
+        })
+    }
+}
+
+/// How long a command can't be used again, per user and per guild.
+#[allow(unused)]
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Cooldown {
+    #[serde(default, deserialize_with = "deserialize_option_duration")]
+    pub user: Option<Duration>,
+    #[serde(default, deserialize_with = "deserialize_option_duration")]
+    pub guild: Option<Duration>,
+}
+
+/// Where a command can be used, commands without their own allowlist can be
+/// used in the casino channels.
+#[allow(unused)]
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct ChannelRule {
+    #[serde(default)]
+    pub anywhere: bool,
+    #[serde(default)]
+    pub allow: Vec<u64>,
+    #[serde(default)]
+    pub deny: Vec<u64>,
+}
+
+#[allow(unused)]
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Channels {
+    /// Casino channels in addition to the potato channel.
+    #[serde(default)]
+    pub casino: Vec<u64>,
+    /// Rules by the qualified name of the command.
+    #[serde(default)]
+    pub commands: HashMap<String, ChannelRule>,
+}
+
 #[allow(unused)]
 #[derive(Debug, Deserialize)]
 pub struct Settings {
@@ -35,6 +252,26 @@ pub struct Settings {
     pub discord: Discord,
     #[serde(alias = "potato-feeder")]
     pub potato_feeder: PotatoFeeder,
+    pub crash: Crash,
+    pub dice: Dice,
+    pub poker: Poker,
+    pub race: Race,
+    pub mines: Mines,
+    pub markets: Markets,
+    pub jackpot: Jackpot,
+    pub house: House,
+    pub give: Give,
+    pub limits: Limits,
+    pub backups: Backups,
+    pub metrics: Metrics,
+    pub health: Health,
+    #[serde(default)]
+    pub time: Time,
+    /// Cooldowns by the qualified name of the command.
+    #[serde(default)]
+    pub cooldowns: HashMap<String, Cooldown>,
+    #[serde(default)]
+    pub channels: Channels,
 }
 
 impl Settings {
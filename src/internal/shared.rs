@@ -1,17 +1,21 @@
 use serenity::{all::UserId, prelude::Mentionable};
 
-use sqlx::{Pool, Sqlite};
-
-use crate::database::players::{create_player, Player};
+use crate::database::players::Player;
+use crate::engine::flip::{self, FlipError};
 
+use crate::internal::betting::{BetAmount, MINIMUM_BET};
 use crate::internal::{data::Context, discord, errors::PotatoGameError};
 
 pub async fn create_new_player(
     ctx: &Context<'_>,
     user_id: &UserId,
-    database: &Pool<Sqlite>,
 ) -> Result<Player, PotatoGameError> {
-    match create_player(&user_id.to_string(), database).await {
+    match ctx
+        .data()
+        .players
+        .create_player(&user_id.to_string(), ctx.data().clock.timestamp())
+        .await
+    {
         Some(player) => {
             discord::success_message(ctx, format!("{} pole varasemalt kartulikasiinos mänginud, viskasin seemneks kontole 5000 :potato:.", user_id.mention())).await;
             Ok(player)
@@ -19,3 +23,60 @@ pub async fn create_new_player(
         None => Err(PotatoGameError::ConcurrencyError),
     }
 }
+
+/// Amount of the bet checked against the minimum and the balance of the
+/// player, the player is told when the bet can't be made.
+pub async fn stake(ctx: &Context<'_>, bet_amount: &BetAmount, player: &Player) -> Option<i64> {
+    let stake = flip::stake(bet_amount, player, &mut rand::rng());
+    let message = match stake {
+        Ok(amount) => return Some(amount),
+        Err(FlipError::BelowMinimum) => format!(
+            "{} Minimaalne panus on {} :potato:.",
+            ctx.author().id.mention(),
+            MINIMUM_BET
+        ),
+        Err(FlipError::InsufficientBalance) => format!(
+            "{} Sul pole panuse tegemiseks piisavalt :potato:.",
+            ctx.author().id.mention()
+        ),
+    };
+
+    discord::failure_message(ctx, message).await;
+    None
+}
+
+pub fn format_duration(secs: i64) -> String {
+    let parts = [
+        (secs / 86_400, "d"),
+        (secs % 86_400 / 3_600, "h"),
+        (secs % 3_600 / 60, "m"),
+    ];
+    let formatted = parts
+        .iter()
+        .filter(|(value, _)| *value > 0)
+        .map(|(value, unit)| format!("{}{}", value, unit))
+        .collect::<Vec<_>>()
+        .join(" ");
+    if formatted.is_empty() {
+        format!("{}s", secs)
+    } else {
+        formatted
+    }
+}
+
+/// Whether the author of the command is an administrator in the channel.
+pub async fn is_admin(ctx: &Context<'_>) -> bool {
+    let Some(member) = ctx.author_member().await else {
+        return false;
+    };
+
+    let Some(guild) = ctx.guild() else {
+        return false;
+    };
+
+    guild
+        .channels
+        .get(&ctx.channel_id())
+        .map(|channel| guild.user_permissions_in(channel, &member).administrator())
+        .unwrap_or(false)
+}
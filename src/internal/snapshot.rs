@@ -0,0 +1,826 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Sqlite};
+use std::collections::{HashMap, HashSet};
+
+use crate::database::charity::{find_charity_pool, set_charity_pool};
+use crate::database::house::{
+    adjust_house_balance, find_house_balance, load_house_ledger, save_ledger_entry,
+    set_house_balance, LedgerEntry,
+};
+use crate::database::jackpot::{find_jackpot_pool, set_jackpot_pool};
+use crate::database::players::{import_player, Player};
+use crate::database::repository::PlayerRepository;
+use crate::internal::data::Error;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum, poise::ChoiceParameter)]
+pub enum Format {
+    #[default]
+    #[name = "json"]
+    Json,
+    /// A file per table.
+    #[name = "csv"]
+    Csv,
+}
+
+/// What to do with players, ledger entries and pools that differ from the
+/// ones in the database.
+#[derive(Clone, Copy, Debug, Default, PartialEq, clap::ValueEnum)]
+pub enum Conflict {
+    /// Refuse to import anything.
+    #[default]
+    Fail,
+    /// Keep the ones in the database.
+    Skip,
+    /// Replace the ones in the database.
+    Overwrite,
+}
+
+/// Totals at the time of the export, for reference only, they are not
+/// imported.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Statistics {
+    pub players: i64,
+    /// Potatoes held by the players.
+    pub circulation: i64,
+}
+
+/// Potatoes held outside of the players.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Pools {
+    pub house: i64,
+    pub charity_pool: i64,
+    pub jackpot: i64,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Snapshot {
+    pub exported_ts: i64,
+    pub players: Vec<Player>,
+    pub ledger: Vec<LedgerEntry>,
+    /// Missing from older exports, which leave the pools as they are.
+    #[serde(default)]
+    pub pools: Option<Pools>,
+    #[serde(default)]
+    pub statistics: Statistics,
+}
+
+pub async fn take(database: &Pool<Sqlite>, players: &dyn PlayerRepository, ts: i64) -> Snapshot {
+    let players = players.load_players_by_balance().await;
+
+    Snapshot {
+        exported_ts: ts,
+        statistics: Statistics {
+            players: players.len() as i64,
+            circulation: players.iter().map(|player| player.balance).sum(),
+        },
+        pools: Some(Pools {
+            house: find_house_balance(database).await,
+            charity_pool: find_charity_pool(database).await,
+            jackpot: find_jackpot_pool(database).await,
+        }),
+        ledger: load_house_ledger(database).await,
+        players,
+    }
+}
+
+pub const PLAYERS_CSV: &str = "players.csv";
+pub const LEDGER_CSV: &str = "ledger.csv";
+pub const POOLS_CSV: &str = "pools.csv";
+pub const STATISTICS_CSV: &str = "statistics.csv";
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn write_csv(header: &[&str], rows: impl Iterator<Item = Vec<String>>) -> String {
+    let mut csv = header.join(",") + "\n";
+    for row in rows {
+        csv += &row
+            .iter()
+            .map(|field| csv_field(field))
+            .collect::<Vec<_>>()
+            .join(",");
+        csv += "\n";
+    }
+    csv
+}
+
+fn parse_csv(csv: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = csv.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => row.push(std::mem::take(&mut field)),
+            (false, '\r') => {}
+            (false, '\n') => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (false, c) => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err("unterminated quoted field".to_string());
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+/// Rows of the CSV as maps by the column names of the header.
+fn read_csv(csv: &str, columns: &[&str]) -> Result<Vec<HashMap<String, String>>, String> {
+    let mut rows = parse_csv(csv)?.into_iter();
+    let header = rows.next().unwrap_or_default();
+
+    for column in columns {
+        if !header.iter().any(|name| name == column) {
+            return Err(format!("column {} is missing", column));
+        }
+    }
+
+    rows.enumerate()
+        .map(|(i, row)| {
+            if row.len() != header.len() {
+                return Err(format!(
+                    "line {} has {} fields instead of {}",
+                    i + 2,
+                    row.len(),
+                    header.len()
+                ));
+            }
+            Ok(header.iter().cloned().zip(row).collect())
+        })
+        .collect()
+}
+
+fn number(row: &HashMap<String, String>, column: &str, line: usize) -> Result<i64, String> {
+    row[column]
+        .trim()
+        .parse()
+        .map_err(|_| format!("line {}: {} is not a number", line, column))
+}
+
+fn players_from_csv(csv: &str) -> Result<Vec<Player>, String> {
+    let columns = [
+        "discord_user_id",
+        "balance",
+        "last_feed_ts",
+        "idle_since_ts",
+    ];
+
+    read_csv(csv, &columns)?
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            Ok(Player {
+                discord_user_id: row["discord_user_id"].trim().to_string(),
+                balance: number(row, "balance", i + 2)?,
+                last_feed_ts: number(row, "last_feed_ts", i + 2)?,
+                idle_since_ts: number(row, "idle_since_ts", i + 2)?,
+                version: 1,
+            })
+        })
+        .collect()
+}
+
+fn ledger_from_csv(csv: &str) -> Result<Vec<LedgerEntry>, String> {
+    read_csv(csv, &["day", "source", "amount"])?
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            Ok(LedgerEntry {
+                day: number(row, "day", i + 2)?,
+                source: row["source"].trim().to_string(),
+                amount: number(row, "amount", i + 2)?,
+            })
+        })
+        .collect()
+}
+
+fn pools_from_csv(csv: &str) -> Result<Pools, String> {
+    match read_csv(csv, &["house", "charity_pool", "jackpot"])?.as_slice() {
+        [row] => Ok(Pools {
+            house: number(row, "house", 2)?,
+            charity_pool: number(row, "charity_pool", 2)?,
+            jackpot: number(row, "jackpot", 2)?,
+        }),
+        rows => Err(format!("{} rows instead of 1", rows.len())),
+    }
+}
+
+impl Snapshot {
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// The files of the CSV export by name.
+    pub fn to_csv(&self) -> Vec<(&'static str, String)> {
+        let players = write_csv(
+            &[
+                "discord_user_id",
+                "balance",
+                "last_feed_ts",
+                "idle_since_ts",
+            ],
+            self.players.iter().map(|player| {
+                vec![
+                    player.discord_user_id.clone(),
+                    player.balance.to_string(),
+                    player.last_feed_ts.to_string(),
+                    player.idle_since_ts.to_string(),
+                ]
+            }),
+        );
+
+        let ledger = write_csv(
+            &["day", "source", "amount"],
+            self.ledger.iter().map(|entry| {
+                vec![
+                    entry.day.to_string(),
+                    entry.source.clone(),
+                    entry.amount.to_string(),
+                ]
+            }),
+        );
+
+        let statistics = write_csv(
+            &["exported_ts", "players", "circulation"],
+            std::iter::once(
+                [
+                    self.exported_ts,
+                    self.statistics.players,
+                    self.statistics.circulation,
+                ]
+                .iter()
+                .map(|value| value.to_string())
+                .collect(),
+            ),
+        );
+
+        let mut files = vec![(PLAYERS_CSV, players), (LEDGER_CSV, ledger)];
+
+        if let Some(pools) = &self.pools {
+            files.push((
+                POOLS_CSV,
+                write_csv(
+                    &["house", "charity_pool", "jackpot"],
+                    std::iter::once(
+                        [pools.house, pools.charity_pool, pools.jackpot]
+                            .iter()
+                            .map(|value| value.to_string())
+                            .collect(),
+                    ),
+                ),
+            ));
+        }
+
+        files.push((STATISTICS_CSV, statistics));
+        files
+    }
+
+    /// Reads the players, the ledger and the pools of a CSV export, a missing
+    /// table is empty and missing pools are left as they are.
+    pub fn from_csv(
+        players: Option<&str>,
+        ledger: Option<&str>,
+        pools: Option<&str>,
+    ) -> Result<Self, Error> {
+        Ok(Snapshot {
+            players: players
+                .map(players_from_csv)
+                .transpose()
+                .map_err(|why| format!("{}: {}", PLAYERS_CSV, why))?
+                .unwrap_or_default(),
+            ledger: ledger
+                .map(ledger_from_csv)
+                .transpose()
+                .map_err(|why| format!("{}: {}", LEDGER_CSV, why))?
+                .unwrap_or_default(),
+            pools: pools
+                .map(pools_from_csv)
+                .transpose()
+                .map_err(|why| format!("{}: {}", POOLS_CSV, why))?,
+            ..Default::default()
+        })
+    }
+
+    /// Problems making the snapshot unfit for importing.
+    pub fn validate(&self, now: i64) -> Vec<String> {
+        let mut problems = vec![];
+        let mut user_ids = HashSet::new();
+
+        for player in &self.players {
+            let user_id = &player.discord_user_id;
+
+            if user_id.parse::<u64>().is_err() {
+                problems.push(format!("player {:?} is not a Discord user id", user_id));
+            }
+            if !user_ids.insert(user_id) {
+                problems.push(format!("player {} is listed more than once", user_id));
+            }
+            if player.balance < 0 {
+                problems.push(format!("player {} has a negative balance", user_id));
+            }
+            for (name, ts) in [
+                ("last_feed_ts", player.last_feed_ts),
+                ("idle_since_ts", player.idle_since_ts),
+            ] {
+                if !(0..=now).contains(&ts) {
+                    problems.push(format!(
+                        "player {} has {} {} out of range",
+                        user_id, name, ts
+                    ));
+                }
+            }
+        }
+
+        let mut entries = HashSet::new();
+
+        for entry in &self.ledger {
+            if entry.source.is_empty() || entry.source.len() > 32 {
+                problems.push(format!(
+                    "ledger source {:?} should be 1 to 32 characters",
+                    entry.source
+                ));
+            }
+            if !entries.insert((entry.day, &entry.source)) {
+                problems.push(format!(
+                    "ledger entry {} {} is listed more than once",
+                    entry.day, entry.source
+                ));
+            }
+        }
+
+        if let Some(pools) = &self.pools {
+            for (name, balance) in [
+                ("charity pool", pools.charity_pool),
+                ("jackpot", pools.jackpot),
+            ] {
+                if balance < 0 {
+                    problems.push(format!("the {} is negative", name));
+                }
+            }
+        }
+
+        problems
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change<T> {
+    Added(T),
+    Changed { from: T, to: T },
+    Unchanged,
+}
+
+/// Differences between a snapshot and the database.
+#[derive(Debug)]
+pub struct Diff {
+    pub players: Vec<Change<Player>>,
+    pub ledger: Vec<Change<LedgerEntry>>,
+    pub pools: Change<Pools>,
+}
+
+pub fn diff(
+    snapshot: &Snapshot,
+    players: &[Player],
+    ledger: &[LedgerEntry],
+    pools: &Pools,
+) -> Diff {
+    let players = players
+        .iter()
+        .map(|player| (&player.discord_user_id, player))
+        .collect::<HashMap<_, _>>();
+    let ledger = ledger
+        .iter()
+        .map(|entry| ((entry.day, &entry.source), entry))
+        .collect::<HashMap<_, _>>();
+
+    Diff {
+        players: snapshot
+            .players
+            .iter()
+            .map(|player| match players.get(&player.discord_user_id) {
+                None => Change::Added(player.clone()),
+                Some(existing)
+                    if (
+                        existing.balance,
+                        existing.last_feed_ts,
+                        existing.idle_since_ts,
+                    ) == (player.balance, player.last_feed_ts, player.idle_since_ts) =>
+                {
+                    Change::Unchanged
+                }
+                Some(existing) => Change::Changed {
+                    from: (*existing).clone(),
+                    to: player.clone(),
+                },
+            })
+            .collect(),
+        ledger: snapshot
+            .ledger
+            .iter()
+            .map(|entry| match ledger.get(&(entry.day, &entry.source)) {
+                None => Change::Added(entry.clone()),
+                Some(existing) if existing.amount == entry.amount => Change::Unchanged,
+                Some(existing) => Change::Changed {
+                    from: (*existing).clone(),
+                    to: entry.clone(),
+                },
+            })
+            .collect(),
+        pools: match &snapshot.pools {
+            Some(imported) if imported != pools => Change::Changed {
+                from: pools.clone(),
+                to: imported.clone(),
+            },
+            _ => Change::Unchanged,
+        },
+    }
+}
+
+fn count<T>(changes: &[Change<T>]) -> (usize, usize, usize) {
+    changes.iter().fold(
+        (0, 0, 0),
+        |(added, changed, unchanged), change| match change {
+            Change::Added(_) => (added + 1, changed, unchanged),
+            Change::Changed { .. } => (added, changed + 1, unchanged),
+            Change::Unchanged => (added, changed, unchanged + 1),
+        },
+    )
+}
+
+impl Diff {
+    pub fn conflicts(&self) -> usize {
+        count(&self.players).1
+            + count(&self.ledger).1
+            + usize::from(self.pools != Change::Unchanged)
+    }
+
+    /// A line per added or changed row, `~` marks the conflicts.
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![];
+
+        for change in &self.players {
+            match change {
+                Change::Added(player) => lines.push(format!(
+                    "+ player {}: balance {}, last_feed_ts {}, idle_since_ts {}",
+                    player.discord_user_id,
+                    player.balance,
+                    player.last_feed_ts,
+                    player.idle_since_ts
+                )),
+                Change::Changed { from, to } => {
+                    let fields = [
+                        ("balance", from.balance, to.balance),
+                        ("last_feed_ts", from.last_feed_ts, to.last_feed_ts),
+                        ("idle_since_ts", from.idle_since_ts, to.idle_since_ts),
+                    ]
+                    .iter()
+                    .filter(|(_, from, to)| from != to)
+                    .map(|(name, from, to)| format!("{} {} -> {}", name, from, to))
+                    .collect::<Vec<_>>();
+                    lines.push(format!(
+                        "~ player {}: {}",
+                        to.discord_user_id,
+                        fields.join(", ")
+                    ));
+                }
+                Change::Unchanged => {}
+            }
+        }
+
+        for change in &self.ledger {
+            match change {
+                Change::Added(entry) => lines.push(format!(
+                    "+ ledger {} {}: {}",
+                    entry.day, entry.source, entry.amount
+                )),
+                Change::Changed { from, to } => lines.push(format!(
+                    "~ ledger {} {}: {} -> {}",
+                    to.day, to.source, from.amount, to.amount
+                )),
+                Change::Unchanged => {}
+            }
+        }
+
+        if let Change::Changed { from, to } = &self.pools {
+            let fields = [
+                ("house", from.house, to.house),
+                ("charity_pool", from.charity_pool, to.charity_pool),
+                ("jackpot", from.jackpot, to.jackpot),
+            ]
+            .iter()
+            .filter(|(_, from, to)| from != to)
+            .map(|(name, from, to)| format!("{} {} -> {}", name, from, to))
+            .collect::<Vec<_>>();
+            lines.push(format!("~ pools: {}", fields.join(", ")));
+        }
+
+        lines
+    }
+
+    pub fn summary(&self) -> String {
+        let players = count(&self.players);
+        let ledger = count(&self.ledger);
+
+        let pools = match self.pools {
+            Change::Unchanged => "unchanged",
+            _ => "changed",
+        };
+
+        format!(
+            "players: {} new, {} changed, {} unchanged; ledger: {} new, {} changed, {} unchanged; pools: {}",
+            players.0, players.1, players.2, ledger.0, ledger.1, ledger.2, pools
+        )
+    }
+}
+
+/// Saves the added rows and, depending on `conflict`, the changed ones, all
+/// or nothing. The house balance follows the imported ledger unless the pools
+/// are overwritten.
+pub async fn apply(diff: &Diff, conflict: Conflict, database: &Pool<Sqlite>) -> Result<(), Error> {
+    if conflict == Conflict::Fail && diff.conflicts() > 0 {
+        return Err(format!(
+            "{} rows differ from the database, choose to skip or overwrite them",
+            diff.conflicts()
+        )
+        .into());
+    }
+
+    let overwrite = conflict == Conflict::Overwrite;
+    let mut transaction = database.begin().await?;
+
+    for change in &diff.players {
+        let (player, version) = match change {
+            Change::Added(player) => (player, None),
+            Change::Changed { from, to } if overwrite => (to, Some(from.version)),
+            _ => continue,
+        };
+
+        if !import_player(player, version, &mut transaction).await {
+            return Err(format!("Could not import player {}", player.discord_user_id).into());
+        }
+    }
+
+    let mut house = 0;
+
+    for change in &diff.ledger {
+        let entry = match change {
+            Change::Added(entry) => {
+                house += entry.amount;
+                entry
+            }
+            Change::Changed { from, to } if overwrite => {
+                house += to.amount - from.amount;
+                to
+            }
+            _ => continue,
+        };
+
+        if !save_ledger_entry(entry, &mut transaction).await {
+            return Err(format!(
+                "Could not import ledger entry {} {}",
+                entry.day, entry.source
+            )
+            .into());
+        }
+    }
+
+    if house != 0 && !adjust_house_balance(house, &mut transaction).await {
+        return Err("Could not update the house balance".into());
+    }
+
+    if let Change::Changed { to: pools, .. } = &diff.pools {
+        if overwrite
+            && !(set_house_balance(pools.house, &mut transaction).await
+                && set_charity_pool(pools.charity_pool, &mut transaction).await
+                && set_jackpot_pool(pools.jackpot, &mut transaction).await)
+        {
+            return Err("Could not import the pools".into());
+        }
+    }
+
+    transaction.commit().await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn player(user_id: &str, balance: i64) -> Player {
+        Player {
+            discord_user_id: user_id.to_string(),
+            balance,
+            last_feed_ts: 100,
+            idle_since_ts: 100,
+            version: 1,
+        }
+    }
+
+    fn entry(day: i64, source: &str, amount: i64) -> LedgerEntry {
+        LedgerEntry {
+            day,
+            source: source.to_string(),
+            amount,
+        }
+    }
+
+    fn snapshot() -> Snapshot {
+        Snapshot {
+            exported_ts: 200,
+            players: vec![player("1", 1000), player("2", 500)],
+            ledger: vec![entry(20380, "flip", 150), entry(20380, "a, \"b\"", -20)],
+            pools: Some(Pools {
+                house: 5000,
+                charity_pool: 30,
+                jackpot: 70,
+            }),
+            statistics: Statistics {
+                players: 2,
+                circulation: 1500,
+            },
+        }
+    }
+
+    #[test]
+    fn csv_export_reads_back_the_same() {
+        let snapshot = snapshot();
+        let files = snapshot.to_csv().into_iter().collect::<HashMap<_, _>>();
+
+        assert!(files[LEDGER_CSV].contains("\"a, \"\"b\"\"\""));
+
+        let read = Snapshot::from_csv(
+            Some(&files[PLAYERS_CSV]),
+            Some(&files[LEDGER_CSV]),
+            Some(&files[POOLS_CSV]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            read.players
+                .iter()
+                .map(|player| (player.discord_user_id.as_str(), player.balance))
+                .collect::<Vec<_>>(),
+            vec![("1", 1000), ("2", 500)]
+        );
+        assert_eq!(read.ledger, snapshot.ledger);
+        assert_eq!(read.pools, snapshot.pools);
+    }
+
+    #[test]
+    fn malformed_csv_is_refused() {
+        assert!(Snapshot::from_csv(Some("discord_user_id,balance\n1,2\n"), None, None).is_err());
+        assert!(Snapshot::from_csv(
+            Some("discord_user_id,balance,last_feed_ts,idle_since_ts\n1,lots,0,0\n"),
+            None,
+            None
+        )
+        .is_err());
+        assert!(Snapshot::from_csv(None, Some("day,source,amount\n1,\"flip,2\n"), None).is_err());
+        assert!(Snapshot::from_csv(
+            None,
+            None,
+            Some("house,charity_pool,jackpot\n1,2,3\n4,5,6\n")
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn invalid_rows_are_reported() {
+        let mut snapshot = snapshot();
+        snapshot.players.push(player("jaxx", -5));
+        snapshot.players.push(player("1", 0));
+        snapshot.ledger.push(entry(20380, "flip", 1));
+
+        assert_eq!(
+            snapshot.validate(150),
+            vec![
+                "player \"jaxx\" is not a Discord user id",
+                "player jaxx has a negative balance",
+                "player 1 is listed more than once",
+                "ledger entry 20380 flip is listed more than once",
+            ]
+        );
+        assert_eq!(snapshot.validate(50).len(), 12);
+    }
+
+    #[test]
+    fn diff_marks_new_and_changed_rows() {
+        let diff = diff(
+            &snapshot(),
+            &[player("1", 1000), player("2", 400)],
+            &[entry(20380, "flip", 100)],
+            &Pools {
+                house: 4000,
+                charity_pool: 30,
+                jackpot: 70,
+            },
+        );
+
+        assert_eq!(diff.conflicts(), 3);
+        assert_eq!(
+            diff.lines(),
+            vec![
+                "~ player 2: balance 400 -> 500",
+                "~ ledger 20380 flip: 100 -> 150",
+                "+ ledger 20380 a, \"b\": -20",
+                "~ pools: house 4000 -> 5000",
+            ]
+        );
+        assert_eq!(
+            diff.summary(),
+            "players: 0 new, 1 changed, 1 unchanged; ledger: 1 new, 1 changed, 0 unchanged; pools: changed"
+        );
+    }
+
+    async fn current(database: &Pool<Sqlite>) -> (Vec<Player>, Vec<LedgerEntry>, Pools) {
+        let players = crate::database::players::load_players_by_balance(database).await;
+        let ledger = load_house_ledger(database).await;
+        let pools = Pools {
+            house: find_house_balance(database).await,
+            charity_pool: find_charity_pool(database).await,
+            jackpot: find_jackpot_pool(database).await,
+        };
+        (players, ledger, pools)
+    }
+
+    #[tokio::test]
+    async fn failed_import_saves_nothing() {
+        let database = crate::database::in_memory().await;
+        let (players, ledger, pools) = current(&database).await;
+        let mut diff = diff(&snapshot(), &players, &ledger, &pools);
+        diff.players.push(Change::Added(player("1", 1)));
+
+        assert!(apply(&diff, Conflict::Overwrite, &database).await.is_err());
+
+        let (after, ledger_after, pools_after) = current(&database).await;
+        assert_eq!(after.len(), players.len());
+        assert_eq!(ledger_after, ledger);
+        assert_eq!(pools_after, pools);
+    }
+
+    #[tokio::test]
+    async fn imported_ledger_moves_the_house() {
+        let database = crate::database::in_memory().await;
+        let snapshot = Snapshot {
+            pools: None,
+            ..snapshot()
+        };
+        let (players, ledger, pools) = current(&database).await;
+        let diff = diff(&snapshot, &players, &ledger, &pools);
+
+        apply(&diff, Conflict::Fail, &database).await.unwrap();
+
+        let (players, ledger, imported) = current(&database).await;
+        assert_eq!(players.len(), 2);
+        assert_eq!(ledger.len(), 2);
+        assert_eq!(imported.house, pools.house + 130);
+        assert_eq!(imported.jackpot, pools.jackpot);
+    }
+
+    #[tokio::test]
+    async fn overwritten_pools_replace_the_house_and_the_pools() {
+        let database = crate::database::in_memory().await;
+        let (players, ledger, pools) = current(&database).await;
+        let first = diff(&snapshot(), &players, &ledger, &pools);
+
+        apply(&first, Conflict::Skip, &database).await.unwrap();
+        assert_eq!(current(&database).await.2.house, pools.house + 130);
+
+        let (players, ledger, pools) = current(&database).await;
+        let second = diff(&snapshot(), &players, &ledger, &pools);
+
+        assert_eq!(second.conflicts(), 1);
+
+        apply(&second, Conflict::Overwrite, &database)
+            .await
+            .unwrap();
+        assert_eq!(current(&database).await.2, snapshot().pools.unwrap());
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+use crate::database::transfers::Transfer;
+
+/// Potatoes going around in a circle between players, the usual sign of
+/// alts feeding each other or a main account.
+#[derive(Debug)]
+pub struct TransferCycle {
+    /// Players in the order the potatoes travelled, the last one sent them
+    /// back to the first.
+    pub players: Vec<String>,
+    /// Total amount sent along each step of the cycle.
+    pub amounts: Vec<i64>,
+}
+
+/// Finds players sending potatoes back to each other directly or through a
+/// third player.
+pub fn find_cycles(transfers: &[Transfer]) -> Vec<TransferCycle> {
+    let edges = transfers.iter().fold(BTreeMap::new(), |mut acc, transfer| {
+        *acc.entry((transfer.sender_id.clone(), transfer.receiver_id.clone()))
+            .or_insert(0i64) += transfer.amount;
+        acc
+    });
+
+    let amount = |from: &String, to: &String| edges.get(&(from.clone(), to.clone())).copied();
+
+    let mut cycles = Vec::new();
+
+    for (a, b) in edges.keys() {
+        // Every cycle is reported once, starting from its smallest player.
+        if b <= a {
+            continue;
+        }
+
+        if let (Some(ab), Some(ba)) = (amount(a, b), amount(b, a)) {
+            cycles.push(TransferCycle {
+                players: vec![a.clone(), b.clone()],
+                amounts: vec![ab, ba],
+            });
+        }
+
+        for ((from, c), _) in edges.range((b.clone(), String::new())..) {
+            if from != b {
+                break;
+            }
+            if c <= a || c == b {
+                continue;
+            }
+            if let (Some(ab), Some(bc), Some(ca)) = (amount(a, b), amount(b, c), amount(c, a)) {
+                cycles.push(TransferCycle {
+                    players: vec![a.clone(), b.clone(), c.clone()],
+                    amounts: vec![ab, bc, ca],
+                });
+            }
+        }
+    }
+
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transfer(sender_id: &str, receiver_id: &str, amount: i64) -> Transfer {
+        Transfer {
+            sender_id: sender_id.into(),
+            receiver_id: receiver_id.into(),
+            amount,
+            fee: 0,
+            created_ts: 0,
+        }
+    }
+
+    #[test]
+    fn two_players_sending_back_and_forth() {
+        let cycles = find_cycles(&[
+            transfer("b", "a", 30),
+            transfer("a", "b", 100),
+            transfer("a", "b", 50),
+        ]);
+
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].players, vec!["a", "b"]);
+        assert_eq!(cycles[0].amounts, vec![150, 30]);
+    }
+
+    #[test]
+    fn three_players_in_a_circle() {
+        let cycles = find_cycles(&[
+            transfer("c", "a", 80),
+            transfer("a", "b", 100),
+            transfer("b", "c", 90),
+        ]);
+
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].players, vec!["a", "b", "c"]);
+        assert_eq!(cycles[0].amounts, vec![100, 90, 80]);
+    }
+
+    #[test]
+    fn one_way_transfers_are_no_cycle() {
+        let cycles = find_cycles(&[
+            transfer("a", "b", 100),
+            transfer("b", "c", 90),
+            transfer("a", "c", 80),
+        ]);
+
+        assert!(cycles.is_empty());
+    }
+}
@@ -1,33 +1,100 @@
+mod cli;
 mod commands;
 mod database;
+mod engine;
 mod internal;
 
+use clap::Parser;
+use cli::{Cli, Command};
 use dotenv::dotenv;
+use internal::clock::SystemClock;
 use internal::data::Data;
 use internal::discord;
-use internal::settings::Settings;
-use serenity::model::prelude::ChannelId;
+use internal::metrics::QueryLatency;
+use internal::settings::{Backend, Settings};
+use std::sync::Arc;
 use tracing::instrument;
+use tracing_subscriber::filter::{LevelFilter, Targets};
+use tracing_subscriber::prelude::*;
 
 #[tokio::main]
 #[instrument]
 async fn main() {
-    tracing_subscriber::fmt::init();
+    // Logs go to stderr, the subcommands print their output to stdout. The
+    // statements sqlx logs below the log level are only counted for metrics.
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_filter(LevelFilter::INFO),
+        )
+        .with(
+            QueryLatency
+                .with_filter(Targets::new().with_target("sqlx::query", tracing::Level::DEBUG)),
+        )
+        .init();
+
+    let cli = Cli::parse();
 
     dotenv().ok();
     dotenv::from_filename(".env.local").ok();
 
-    let settings = Settings::new().expect("Could not load bot settings");
+    let settings = match Settings::new() {
+        Ok(settings) => settings,
+        Err(why) => {
+            eprintln!("Could not load bot settings: {why}");
+            std::process::exit(1);
+        }
+    };
+
+    let result = match cli.command.unwrap_or(Command::Run) {
+        Command::Run => {
+            run(&settings).await;
+            Ok(())
+        }
+        Command::Migrate => {
+            cli::migrate(&settings).await;
+            Ok(())
+        }
+        Command::CheckConfig => cli::check_config(&settings),
+        Command::Backup { path } => cli::backup(path.as_deref(), &settings).await,
+        Command::Restore { path } => cli::restore(&path, &settings).await,
+        Command::Export { format, path } => cli::export(format, path.as_deref(), &settings).await,
+        Command::Import {
+            path,
+            dry_run,
+            on_conflict,
+        } => cli::import(&path, dry_run, on_conflict, &settings).await,
+        Command::Feed { dry_run } => cli::feed(dry_run, &settings).await,
+        Command::Player { command } => cli::player(&command, &settings).await,
+        Command::Simulate(args) => cli::simulate(&args, &settings).await,
+    };
+
+    if let Err(why) = result {
+        eprintln!("{why}");
+        std::process::exit(1);
+    }
+}
 
-    let database = database::init(&settings).await;
+async fn run(settings: &Settings) {
+    // The games move potatoes in the same SQLite transactions as their own
+    // tables, so the players can't live elsewhere while the bot runs.
+    assert!(
+        settings.database.backend == Backend::Sqlite,
+        "The bot runs on the SQLite backend only, PostgreSQL works with the player, feed and simulate commands"
+    );
+
+    let database = database::init(settings).await;
     database::migrate(&database).await;
 
+    let players = database::repository::player_repository(settings, &database).await;
+
     let data = Data::new(
         database,
-        ChannelId::new(settings.potato_feeder.channel_id),
-        settings.potato_feeder.amount,
-        settings.potato_feeder.zero_points_emoji.clone(),
+        players,
+        Arc::new(SystemClock::new(settings.time.timezone())),
+        settings,
     );
 
-    discord::start_client(data, &settings).await;
+    discord::start_client(data, settings).await;
 }